@@ -0,0 +1,42 @@
+//! Feeding a single [`PaymentsEngine`] from several concurrent producer tasks.
+//!
+//! Usage: `cargo run --example concurrent_producers`
+//!
+//! Each producer gets its own clone of the engine's sender. Transactions from
+//! one sender are applied in order, but different senders interleave
+//! arbitrarily, so each producer here owns a disjoint set of clients.
+
+use crypto_events::{ClientId, Decimal, PaymentsEngine, Transaction, TransactionId};
+
+#[tokio::main]
+async fn main() {
+    let engine = PaymentsEngine::new();
+
+    let mut producers = Vec::new();
+    for client in 1..=4 as ClientId {
+        let sender = engine.sender();
+        producers.push(tokio::spawn(async move {
+            let base = client as TransactionId * 1000;
+            for tx in [
+                Transaction::deposit(client, base + 1, Decimal::from_f64(100.0)),
+                Transaction::deposit(client, base + 2, Decimal::from_f64(10.0)),
+                Transaction::withdrawal(client, base + 3, Decimal::from_f64(25.0)),
+                Transaction::dispute(client, base + 2),
+            ] {
+                sender.send(tx).await.expect("engine stopped");
+            }
+        }));
+    }
+
+    // The engine finishes once every sender (including the engine's own) is dropped
+    let handle = engine.serve().await;
+    for producer in producers {
+        producer.await.expect("producer panicked");
+    }
+
+    let accounts = handle
+        .await
+        .expect("engine panicked")
+        .expect("engine aborted");
+    crypto_events::write_accounts_csv(accounts, std::io::stdout()).expect("write failed");
+}
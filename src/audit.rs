@@ -0,0 +1,150 @@
+//! Audit log of every transaction the engine processed, and replay from it.
+//!
+//! Attach an [`AuditSink`] via
+//! [`PaymentsEngine::with_audit_sink`](crate::PaymentsEngine::with_audit_sink)
+//! to record one [`AuditEvent`] per processed transaction, then rebuild the
+//! final account states from the log alone with [`replay_audit`].
+//!
+//! For debugging a single client, an engine can instead retain each client's
+//! transactions in a [`ClientHistory`] (see
+//! [`PaymentsEngine::with_retained_history`](crate::PaymentsEngine::with_retained_history)),
+//! which [`replay_client`] reapplies through a fresh account.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use crate::ClientId;
+use crate::client_account::ClientAccount;
+use crate::clock::{Clock, SystemClock};
+use crate::engine::{self, TxIdScope};
+use crate::rejection::RejectionReason;
+use crate::transaction::Transaction;
+
+/// A transaction as the engine processed it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEvent {
+    /// The transaction applied, after any
+    /// [`InterceptDecision::Modify`](crate::InterceptDecision::Modify).
+    pub tx: Transaction,
+    /// Engine clock time when it was processed.
+    pub at: i64,
+    /// Why it was rejected, if it was.
+    pub rejection: Option<RejectionReason>,
+    /// Scope the recording engine deduplicated tx IDs under, so a replay
+    /// treats reused tx IDs the same way.
+    pub tx_id_scope: TxIdScope,
+}
+
+/// Destination for [`AuditEvent`]s emitted by the engine, in processing order.
+pub trait AuditSink: Send {
+    fn record(&mut self, event: AuditEvent);
+}
+
+/// In-memory [`AuditSink`] whose clones share the same log.
+#[derive(Debug, Clone, Default)]
+pub struct AuditLog {
+    events: Arc<Mutex<Vec<AuditEvent>>>,
+}
+
+impl AuditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot of all events recorded so far, in processing order.
+    pub fn events(&self) -> Vec<AuditEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl AuditSink for AuditLog {
+    fn record(&mut self, event: AuditEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+/// Rebuild account states by reapplying `events` in order, each at its recorded time.
+///
+/// Rejected events are reapplied too, since some rejections still have effects
+/// (a rejected withdrawal claims its tx ID); interceptor rejections are
+/// skipped, as the interceptor itself is not part of the log. The result
+/// matches the recording engine's output provided it started without seeded
+/// accounts and used the default [`AccountPolicy`](crate::AccountPolicy).
+///
+/// Tx IDs are deduplicated under the [`TxIdScope`] recorded in the events.
+pub fn replay_audit(events: &[AuditEvent]) -> BTreeMap<ClientId, ClientAccount> {
+    let scope = events
+        .first()
+        .map_or(TxIdScope::default(), |event| event.tx_id_scope);
+    engine::replay(
+        scope,
+        events
+            .iter()
+            .filter(|event| event.rejection != Some(RejectionReason::Intercepted))
+            .map(|event| (event.tx.clone(), event.at)),
+    )
+}
+
+/// Shared view of the transactions an engine has processed for each client, in
+/// processing order; clones observe the same history.
+///
+/// Obtain one from [`PaymentsEngine::history`](crate::PaymentsEngine::history)
+/// before serving. Only engines built with
+/// [`with_retained_history`](crate::PaymentsEngine::with_retained_history) have one.
+#[derive(Debug, Clone, Default)]
+pub struct ClientHistory {
+    transactions: Arc<Mutex<BTreeMap<ClientId, Vec<Transaction>>>>,
+}
+
+impl ClientHistory {
+    /// Transactions processed for `client` so far, as applied (after any
+    /// [`InterceptDecision::Modify`](crate::InterceptDecision::Modify)) and
+    /// including rejected ones. Rows rejected as duplicate tx IDs are left out,
+    /// since they had no effect and, when the ID belongs to another client,
+    /// would look fresh to [`replay_client`].
+    pub fn for_client(&self, client: ClientId) -> Vec<Transaction> {
+        self.transactions
+            .lock()
+            .unwrap()
+            .get(&client)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Clients with any retained transactions, in ascending order.
+    pub fn clients(&self) -> Vec<ClientId> {
+        self.transactions.lock().unwrap().keys().copied().collect()
+    }
+
+    pub(crate) fn record(&self, tx: &Transaction) {
+        self.transactions
+            .lock()
+            .unwrap()
+            .entry(tx.client)
+            .or_default()
+            .push(tx.clone());
+    }
+}
+
+/// Reapply `client`'s transactions from `txs` in order through a fresh account,
+/// e.g., [`ClientHistory::for_client`], to check it against the engine's result.
+///
+/// Transactions for other clients are skipped. Like [`replay_audit`], this
+/// assumes the engine started without a seeded account for `client` and used
+/// the default [`AccountPolicy`](crate::AccountPolicy); time-based policies
+/// are not reproduced, as the history records no processing times.
+pub fn replay_client(
+    client: ClientId,
+    txs: impl IntoIterator<Item = Transaction>,
+) -> ClientAccount {
+    let now = SystemClock.now();
+    // With a single client, either scope deduplicates the same tx IDs
+    engine::replay(
+        TxIdScope::default(),
+        txs.into_iter()
+            .filter(|tx| tx.client == client)
+            .map(|tx| (tx, now)),
+    )
+    .remove(&client)
+    .unwrap_or_else(|| ClientAccount::new(client))
+}
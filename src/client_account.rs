@@ -4,23 +4,92 @@
 //! and handles the business logic for deposits, withdrawals, and dispute resolution.
 
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 
 use crate::{
     ClientId, TransactionId,
+    clock::{Clock, SystemClock},
     decimal::Decimal,
-    error,
+    error, info,
+    rejection::RejectionReason,
     transaction::{Transaction, TransactionType},
 };
 
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
 /// Dispute state for a transaction.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DisputeState {
     Disputed,
     Resolved,
     ChargedBack,
 }
 
+/// The disputes on a single deposit and the portion of it currently held.
+///
+/// A dispute row without an amount claims the whole deposit. One with an amount
+/// is a partial claim, and a deposit may collect several over time as long as
+/// they sum to at most its amount. Each resolve or chargeback settles the
+/// oldest open claim.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisputeRecord {
+    /// [`Disputed`](DisputeState::Disputed) while any claim is open; once all
+    /// are settled, [`ChargedBack`](DisputeState::ChargedBack) if any claim was
+    /// charged back, otherwise [`Resolved`](DisputeState::Resolved).
+    pub state: DisputeState,
+    /// Amount moved into `held` by the open claims. Less than what they claim
+    /// when capped by [`AccountPolicy::max_held`]; the remainder stays in `available`.
+    pub held: Decimal,
+    /// Sum of every claim ever made on the deposit, settled or not.
+    pub claimed: Decimal,
+    /// Reason code of the latest dispute on the deposit that gave one.
+    pub reason: Option<String>,
+    /// Open claims, oldest first.
+    open_claims: VecDeque<Claim>,
+    charged_back: bool,
+}
+
+/// One dispute claim on a deposit: the amount claimed and the part of it held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Claim {
+    amount: Decimal,
+    held: Decimal,
+}
+
+impl DisputeRecord {
+    /// Take the oldest open claim to settle, updating the state once none remain.
+    fn settle_next(&mut self, charged_back: bool) -> Option<Claim> {
+        let claim = self.open_claims.pop_front()?;
+        self.held -= claim.held;
+        self.charged_back |= charged_back;
+        if self.open_claims.is_empty() {
+            self.state = if self.charged_back {
+                DisputeState::ChargedBack
+            } else {
+                DisputeState::Resolved
+            };
+        }
+        Some(claim)
+    }
+}
+
+/// An open dispute as [`EngineState`](crate::EngineState) saves it: what a
+/// reloaded account needs to settle the dispute's remaining claims.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SavedDispute {
+    pub tx: TransactionId,
+    /// Amount of the disputed deposit, restored as its ledger entry.
+    pub deposit: Decimal,
+    pub claimed: Decimal,
+    pub reason: Option<String>,
+    /// Whether an earlier claim on the deposit was already charged back.
+    pub charged_back: bool,
+    /// Open claims, oldest first, as `(amount, held)`.
+    pub claims: Vec<(Decimal, Decimal)>,
+}
+
 /// Entry in [`ClientAccount`]'s transaction history.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TransactionHistoryEntry {
@@ -28,10 +97,10 @@ pub struct TransactionHistoryEntry {
     pub amount: Decimal,
 }
 
-impl TryFrom<Transaction> for TransactionHistoryEntry {
+impl TryFrom<&Transaction> for TransactionHistoryEntry {
     type Error = (); // Could add some error type here
 
-    fn try_from(tx: Transaction) -> Result<Self, Self::Error> {
+    fn try_from(tx: &Transaction) -> Result<Self, Self::Error> {
         if tx.is_dispute_related() {
             return Err(());
         }
@@ -42,10 +111,167 @@ impl TryFrom<Transaction> for TransactionHistoryEntry {
     }
 }
 
+/// How a chargeback that would leave `total` negative is booked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NegativeTotalPolicy {
+    /// Let `total` go negative: the client owes the difference.
+    #[default]
+    Allow,
+    /// Stop `total` at zero, writing off (and logging) the shortfall.
+    ClampZero,
+}
+
+/// Tunable business rules applied by a [`ClientAccount`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountPolicy {
+    /// Record successful withdrawals in the ledger (default: `true`).
+    ///
+    /// Withdrawals can never be disputed, so their ledger entries only serve to
+    /// produce a more specific rejection ([`RejectionReason::NotDisputable`]) when
+    /// a feed tries to dispute one. Disabling this saves one ledger entry per
+    /// withdrawal; disputes on withdrawals are then rejected as
+    /// [`RejectionReason::UnknownTransaction`] instead.
+    pub store_withdrawals_in_ledger: bool,
+    /// Upper bound on `held` (default: no cap).
+    ///
+    /// A dispute that would push `held` above the cap holds only up to the cap
+    /// and leaves the excess in `available`. Resolving releases exactly what was
+    /// held; a chargeback still reverses the full deposit, taking the excess out
+    /// of `available`.
+    pub max_held: Option<Decimal>,
+    /// Accept negative-amount deposits as refunds (default: `false`).
+    ///
+    /// A refund debits `available` and `total` like a withdrawal (and is rejected
+    /// on insufficient funds), but is recorded in the ledger as a deposit so it
+    /// can be disputed in reverse: the funds have already left the account, so a
+    /// dispute holds nothing, and a chargeback credits the refunded amount back.
+    pub allow_negative_deposits: bool,
+    /// Log withdrawals rejected for insufficient funds (default: `false`).
+    ///
+    /// Over-withdrawals are routine in most feeds, so they are rejected quietly
+    /// unless this is enabled for debugging. They are always counted in
+    /// [`ProcessStats::insufficient_funds`](crate::ProcessStats::insufficient_funds).
+    pub log_insufficient_funds: bool,
+    /// Accept disputes whose hold exceeds the available balance (default: `true`).
+    ///
+    /// Disputing a deposit that was partially spent drives `available` negative
+    /// while the dispute is open. When disabled, such disputes are rejected as
+    /// [`RejectionReason::DisputeExceedsAvailable`] instead; the hold is compared
+    /// after any [`max_held`](Self::max_held) cap.
+    pub allow_negative_available_on_dispute: bool,
+    /// Cap on the sum of successful withdrawals per UTC day (default: no cap).
+    ///
+    /// Days are read from the engine's [`Clock`](crate::Clock) at processing
+    /// time, not from transaction timestamps. A withdrawal that would exceed the
+    /// cap is rejected as [`RejectionReason::DailyLimitExceeded`]; the count
+    /// resets when the day rolls over.
+    pub daily_withdrawal_limit: Option<Decimal>,
+    /// Seconds to keep resolved and charged-back disputes before pruning them
+    /// (default: keep forever).
+    ///
+    /// Settled disputes otherwise stay in [`ClientAccount::disputes`] for good.
+    /// Once pruned, both the dispute record and the deposit's ledger entry are
+    /// dropped, so a later claim on that tx ID is rejected as
+    /// [`RejectionReason::UnknownTransaction`] rather than
+    /// [`RejectionReason::AlreadyDisputed`]. There is no
+    /// `allow_redispute_after_resolve` option: a settled deposit can never be
+    /// disputed again, so pruning changes the rejection reason but never lets a
+    /// re-dispute through. Time is read from the engine's [`Clock`](crate::Clock).
+    pub settled_dispute_retention: Option<i64>,
+    /// What a chargeback does when the funds have already been spent (default:
+    /// [`NegativeTotalPolicy::Allow`]).
+    ///
+    /// Charging back a partially withdrawn deposit reverses more than the
+    /// account still holds. Under [`NegativeTotalPolicy::ClampZero`] the
+    /// shortfall is credited back to `available` so `total` ends at zero; only
+    /// the shortfall this chargeback caused is written off.
+    pub negative_total: NegativeTotalPolicy,
+    /// Lock the account after this many consecutive withdrawals rejected for
+    /// insufficient funds (default: never).
+    ///
+    /// Repeated over-withdrawals can indicate a client probing for its balance.
+    /// Any successful transaction on the account resets the count. Counted by
+    /// the engine, which logs the lock as [`LockReason::Administrative`].
+    pub lock_after_failed_withdrawals: Option<u32>,
+    /// Reject disputes on zero-amount deposits (default: `false`).
+    ///
+    /// Such a dispute holds nothing, so accepting it only adds an empty dispute
+    /// record. When enabled, it is logged as a no-op and rejected as
+    /// [`RejectionReason::ZeroAmountDeposit`] instead.
+    pub reject_zero_amount_disputes: bool,
+}
+
+impl Default for AccountPolicy {
+    fn default() -> Self {
+        Self {
+            store_withdrawals_in_ledger: true,
+            max_held: None,
+            allow_negative_deposits: false,
+            log_insufficient_funds: false,
+            allow_negative_available_on_dispute: true,
+            daily_withdrawal_limit: None,
+            settled_dispute_retention: None,
+            negative_total: NegativeTotalPolicy::Allow,
+            lock_after_failed_withdrawals: None,
+            reject_zero_amount_disputes: false,
+        }
+    }
+}
+
+/// Why an account was locked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockReason {
+    /// A chargeback reversed one of the account's deposits.
+    Chargeback,
+    /// Locked by policy rather than by a transaction against the account, e.g.
+    /// under [`AccountPolicy::lock_after_failed_withdrawals`].
+    Administrative,
+}
+
+impl fmt::Display for LockReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Chargeback => write!(f, "chargeback"),
+            Self::Administrative => write!(f, "administrative"),
+        }
+    }
+}
+
+/// Plain-data projection of a [`ClientAccount`], suitable for passing across FFI.
+///
+/// Balances are the raw [`Decimal`] representations (value in ten-thousandths).
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountView {
+    pub client_id: u16,
+    pub available: i64,
+    pub held: i64,
+    pub total: i64,
+    pub locked: bool,
+}
+
+/// Projected outcome of a transaction, as returned by [`ClientAccount::simulate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimResult {
+    /// Whether the account would accept the transaction, and if not, why.
+    pub outcome: Result<(), RejectionReason>,
+    /// Balances and lock state afterwards (unchanged if rejected).
+    pub available: Decimal,
+    pub held: Decimal,
+    pub total: Decimal,
+    pub locked: bool,
+}
+
+impl SimResult {
+    pub fn is_accepted(&self) -> bool {
+        self.outcome.is_ok()
+    }
+}
+
 /// Client account state.
 ///
 /// Maintains the invariant: `total = available + held`
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct ClientAccount {
     #[serde(rename = "client")]
     pub client_id: ClientId,
@@ -54,68 +280,551 @@ pub struct ClientAccount {
     pub total: Decimal,
     /// Transactions currently under dispute.
     #[serde(skip)]
-    disputes: HashMap<TransactionId, DisputeState>,
+    disputes: HashMap<TransactionId, DisputeRecord>,
     /// Records of completed fund transfers (deposits/withdrawals).
     #[serde(skip)]
     ledger: HashMap<TransactionId, TransactionHistoryEntry>,
+    /// Open order holds and their amounts, included in `held`.
+    #[serde(skip)]
+    reservations: HashMap<TransactionId, Decimal>,
     pub locked: bool,
+    /// Set by [`ClientAccount::close`]; closed accounts reject all further transactions.
+    #[serde(skip)]
+    closed: bool,
+    #[serde(skip)]
+    policy: AccountPolicy,
+    /// Sum of all successful deposits; unaffected by disputes and chargebacks.
+    #[serde(skip)]
+    total_deposited: Decimal,
+    /// Sum of all successful withdrawals.
+    #[serde(skip)]
+    total_withdrawn: Decimal,
+    /// Sum of chargeback shortfalls written off under `NegativeTotalPolicy::ClampZero`.
+    #[serde(skip)]
+    total_written_off: Decimal,
+    /// Day number and sum of that day's withdrawals, for the daily limit.
+    #[serde(skip)]
+    withdrawn_today: (i64, Decimal),
+    /// Settlement time and tx ID of each settled dispute awaiting pruning,
+    /// oldest first. Only tracked under a retention policy.
+    #[serde(skip)]
+    settled_disputes: VecDeque<(i64, TransactionId)>,
+    /// Consecutive withdrawals rejected for insufficient funds.
+    #[serde(skip)]
+    failed_withdrawals: u32,
+    #[serde(skip)]
+    sequence: Sequence,
+}
+
+/// Engine sequence numbers of an account's creation and last change.
+///
+/// These record processing order rather than account state, so they are
+/// ignored by equality: the same account reached through a different
+/// interleaving of other clients' transactions still compares equal.
+#[derive(Debug, Clone, Copy, Default)]
+struct Sequence {
+    created: u64,
+    last: u64,
+}
+
+impl PartialEq for Sequence {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
 }
 
+impl Eq for Sequence {}
+
 impl ClientAccount {
     /// Create a new client account with zero balances.
     pub fn new(client_id: ClientId) -> Self {
+        Self::with_policy(client_id, AccountPolicy::default())
+    }
+
+    /// Create a new client account with zero balances governed by `policy`.
+    pub fn with_policy(client_id: ClientId, policy: AccountPolicy) -> Self {
         Self {
             client_id,
-            available: Decimal::default(),
-            held: Decimal::default(),
-            total: Decimal::default(),
+            available: Decimal::ZERO,
+            held: Decimal::ZERO,
+            total: Decimal::ZERO,
             disputes: HashMap::new(),
             ledger: HashMap::new(),
+            reservations: HashMap::new(),
             locked: false,
+            closed: false,
+            policy,
+            total_deposited: Decimal::ZERO,
+            total_withdrawn: Decimal::ZERO,
+            total_written_off: Decimal::ZERO,
+            withdrawn_today: (0, Decimal::ZERO),
+            settled_disputes: VecDeque::new(),
+            failed_withdrawals: 0,
+            sequence: Sequence::default(),
+        }
+    }
+
+    /// Sequence number, among all transactions the engine has processed, of
+    /// the one that created this account (starting at 1).
+    ///
+    /// 0 for accounts created outside an engine, e.g. seeded through
+    /// [`PaymentsEngine::with_accounts`](crate::PaymentsEngine::with_accounts).
+    pub fn created_seq(&self) -> u64 {
+        self.sequence.created
+    }
+
+    /// Sequence number of the last transaction the engine successfully applied
+    /// to this account, or of its creation if none has been since.
+    ///
+    /// Rejected transactions don't count. 0 if the engine never touched the account.
+    pub fn last_seq(&self) -> u64 {
+        self.sequence.last
+    }
+
+    /// Restore the closed state of an account saved with
+    /// [`EngineState::save_state`](crate::EngineState::save_state).
+    pub(crate) fn mark_closed(&mut self) {
+        self.closed = true;
+    }
+
+    /// Disputes with open claims, sorted by tx ID, for saving.
+    pub(crate) fn open_disputes(&self) -> Vec<SavedDispute> {
+        let mut saved: Vec<_> = self
+            .disputes
+            .iter()
+            .filter(|(_, dispute)| dispute.state == DisputeState::Disputed)
+            .map(|(&tx, dispute)| SavedDispute {
+                tx,
+                deposit: self
+                    .ledger
+                    .get(&tx)
+                    .map_or(Decimal::ZERO, |entry| entry.amount),
+                claimed: dispute.claimed,
+                reason: dispute.reason.clone(),
+                charged_back: dispute.charged_back,
+                claims: dispute
+                    .open_claims
+                    .iter()
+                    .map(|claim| (claim.amount, claim.held))
+                    .collect(),
+            })
+            .collect();
+        saved.sort_unstable_by_key(|dispute| dispute.tx);
+        saved
+    }
+
+    /// Restore a dispute saved by [`open_disputes`](Self::open_disputes), and
+    /// the ledger entry of its deposit. Balances are restored separately.
+    pub(crate) fn restore_dispute(&mut self, saved: SavedDispute) {
+        let open_claims: VecDeque<Claim> = saved
+            .claims
+            .into_iter()
+            .map(|(amount, held)| Claim { amount, held })
+            .collect();
+        self.ledger.insert(
+            saved.tx,
+            TransactionHistoryEntry {
+                transaction_type: TransactionType::Deposit,
+                amount: saved.deposit,
+            },
+        );
+        self.disputes.insert(
+            saved.tx,
+            DisputeRecord {
+                state: DisputeState::Disputed,
+                held: open_claims.iter().map(|claim| claim.held).sum(),
+                claimed: saved.claimed,
+                reason: saved.reason,
+                open_claims,
+                charged_back: saved.charged_back,
+            },
+        );
+    }
+
+    /// Restore an order hold saved from [`reservations`](Self::reservations).
+    pub(crate) fn restore_reservation(&mut self, tx: TransactionId, amount: Decimal) {
+        self.reservations.insert(tx, amount);
+    }
+
+    /// Record that the engine created this account at sequence number `seq`.
+    pub(crate) fn mark_created(&mut self, seq: u64) {
+        self.sequence = Sequence {
+            created: seq,
+            last: seq,
+        };
+    }
+
+    /// Record that the engine applied a transaction at sequence number `seq`.
+    pub(crate) fn touch(&mut self, seq: u64) {
+        self.sequence.last = seq;
+    }
+
+    /// Flat copy of the account's balances and lock state.
+    pub fn as_view(&self) -> AccountView {
+        AccountView {
+            client_id: self.client_id,
+            available: self.available.0,
+            held: self.held.0,
+            total: self.total.0,
+            locked: self.locked,
+        }
+    }
+
+    /// Create an account with the given balances and lock state, and no history.
+    ///
+    /// `total` is derived as `available + held`. The ledger starts empty, so none
+    /// of the seeded funds can be disputed.
+    pub fn with_balances(
+        client_id: ClientId,
+        available: Decimal,
+        held: Decimal,
+        locked: bool,
+    ) -> Self {
+        let mut account = Self::new(client_id);
+        account.available = available;
+        account.held = held;
+        account.locked = locked;
+        account.recompute_total();
+        account
+    }
+
+    /// Set `total = available + held`, discarding whatever `total` held before.
+    pub fn recompute_total(&mut self) {
+        self.total = self.available + self.held;
+    }
+
+    /// Restore the `total = available + held` invariant, logging if it was broken.
+    ///
+    /// Useful after loading untrusted snapshots. Returns `true` if `total` had
+    /// to be changed.
+    pub fn repair(&mut self) -> bool {
+        let expected = self.available + self.held;
+        if self.total == expected {
+            return false;
+        }
+        error!(
+            "Repairing account {}: total was {}, expected {} (available {} + held {})",
+            self.client_id, self.total, expected, self.available, self.held
+        );
+        self.recompute_total();
+        true
+    }
+
+    /// Disputes ever raised on this account, keyed by the disputed deposit's tx ID.
+    ///
+    /// Resolved and charged-back disputes are kept so they cannot be re-disputed.
+    pub fn disputes(&self) -> &HashMap<TransactionId, DisputeRecord> {
+        &self.disputes
+    }
+
+    /// Open order holds keyed by the hold's tx ID.
+    pub fn reservations(&self) -> &HashMap<TransactionId, Decimal> {
+        &self.reservations
+    }
+
+    /// Successful deposits (and, by default, withdrawals) keyed by tx ID.
+    pub fn ledger(&self) -> &HashMap<TransactionId, TransactionHistoryEntry> {
+        &self.ledger
+    }
+
+    /// Sum of all deposits settled on this account, regardless of later disputes
+    /// or chargebacks. Refunds (negative deposits) reduce it.
+    pub fn total_deposited(&self) -> Decimal {
+        self.total_deposited
+    }
+
+    /// Sum of all withdrawals settled on this account.
+    pub fn total_withdrawn(&self) -> Decimal {
+        self.total_withdrawn
+    }
+
+    /// Sum of the chargeback shortfalls written off under
+    /// [`NegativeTotalPolicy::ClampZero`].
+    pub fn total_written_off(&self) -> Decimal {
+        self.total_written_off
+    }
+
+    /// Amount a chargeback of `tx` would reverse: the oldest open claim, both
+    /// its held part and any part left in `available`.
+    pub(crate) fn chargeback_amount(&self, tx: TransactionId) -> Option<Decimal> {
+        let dispute = self.disputes.get(&tx)?;
+        dispute.open_claims.front().map(|claim| claim.amount)
+    }
+
+    /// Whether the account has been closed via [`ClientAccount::close`].
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Close the account, sweeping out any remaining available balance.
+    ///
+    /// Requires that no funds are held and no disputes are open. Once closed,
+    /// all further transactions (including disputes) are rejected with
+    /// [`RejectionReason::AccountClosed`], distinct from a locked account.
+    ///
+    /// Returns the available balance that was swept out on close.
+    pub fn close(&mut self) -> Result<Decimal, RejectionReason> {
+        if self.closed {
+            error!(
+                "Received close for already closed account {}",
+                self.client_id
+            );
+            return Err(RejectionReason::AccountClosed);
+        }
+        if self.locked {
+            error!("Received close for locked account {}", self.client_id);
+            return Err(RejectionReason::AccountLocked);
+        }
+        if self.has_open_disputes() {
+            error!(
+                "Received close for account {} with open disputes",
+                self.client_id
+            );
+            return Err(RejectionReason::OpenDisputes);
+        }
+
+        let swept = self.available;
+        self.available = Decimal::ZERO;
+        self.total = Decimal::ZERO;
+        self.closed = true;
+        Ok(swept)
+    }
+
+    /// Lift the lock placed by a chargeback, e.g. once ops have settled the fraud.
+    ///
+    /// Requires that no disputes are still open (and no funds held), so that a
+    /// pending chargeback can't slip through an unlocked account.
+    pub fn unlock(&mut self) -> Result<(), RejectionReason> {
+        if self.closed {
+            error!("Received unlock for closed account {}", self.client_id);
+            return Err(RejectionReason::AccountClosed);
+        }
+        if !self.locked {
+            error!("Received unlock for unlocked account {}", self.client_id);
+            return Err(RejectionReason::NotLocked);
+        }
+        if self.has_open_disputes() {
+            error!(
+                "Received unlock for account {} with open disputes",
+                self.client_id
+            );
+            return Err(RejectionReason::OpenDisputes);
+        }
+
+        info!("Unlocking account {}", self.client_id);
+        self.locked = false;
+        Ok(())
+    }
+
+    fn lock(&mut self, reason: LockReason) {
+        info!("Locking account {} ({})", self.client_id, reason);
+        self.locked = true;
+    }
+
+    /// Count consecutive withdrawals rejected for insufficient funds given the
+    /// `result` of applying `tx`, locking the account once
+    /// [`AccountPolicy::lock_after_failed_withdrawals`] is reached. Returns
+    /// whether this locked the account.
+    pub(crate) fn track_failed_withdrawals(
+        &mut self,
+        tx: &Transaction,
+        result: Result<(), RejectionReason>,
+    ) -> bool {
+        match result {
+            Ok(()) => self.failed_withdrawals = 0,
+            Err(RejectionReason::InsufficientFunds)
+                if tx.transaction_type == TransactionType::Withdrawal =>
+            {
+                self.failed_withdrawals = self.failed_withdrawals.saturating_add(1);
+                if let Some(max) = self.policy.lock_after_failed_withdrawals
+                    && self.failed_withdrawals >= max
+                    && !self.locked
+                {
+                    error!(
+                        "Account {} had {} consecutive withdrawals rejected for insufficient funds",
+                        self.client_id, self.failed_withdrawals
+                    );
+                    self.lock(LockReason::Administrative);
+                    return true;
+                }
+            }
+            Err(_) => {}
+        }
+        false
+    }
+
+    /// Number of transactions currently under dispute (resolved and charged-back
+    /// disputes are not counted).
+    pub fn open_dispute_count(&self) -> usize {
+        self.disputes
+            .values()
+            .filter(|dispute| dispute.state == DisputeState::Disputed)
+            .count()
+    }
+
+    /// Whether any dispute is still open or any funds are still held.
+    fn has_open_disputes(&self) -> bool {
+        self.held != Decimal::ZERO
+            || self
+                .disputes
+                .values()
+                .any(|dispute| dispute.state == DisputeState::Disputed)
+    }
+
+    /// Release an order hold placed by a [`Hold`](TransactionType::Hold), returning
+    /// its funds to available.
+    ///
+    /// Allowed on locked accounts, like resolving a pre-freeze dispute; the
+    /// funds stay frozen in available.
+    pub fn release_hold(&mut self, tx: Transaction) -> Result<(), RejectionReason> {
+        if self.closed {
+            return Err(RejectionReason::AccountClosed);
         }
+        let Some(amount) = self.reservations.remove(&tx.tx) else {
+            error!("Received release for unknown order hold: {:?}", tx);
+            return Err(RejectionReason::UnknownTransaction);
+        };
+        self.held -= amount;
+        self.available += amount;
+        Ok(())
     }
 
-    /// Settle a deposit or withdrawal transaction.
+    /// Settle a deposit, withdrawal, full withdrawal, or order hold.
     ///
-    /// Updates available and total balances accordingly. The transaction is
-    /// recorded in the ledger only if successful (for potential future disputes).
+    /// Updates available and total balances accordingly. Deposits and
+    /// withdrawals are recorded in the ledger only if successful (for potential
+    /// future disputes); holds are tracked separately until released. A
+    /// [`WithdrawAll`](TransactionType::WithdrawAll) withdraws all of `available`
+    /// and is ledgered as a plain withdrawal of that amount.
     ///
-    /// # Ignored cases (logged as errors)
+    /// # Rejected cases
     /// - Locked accounts
-    /// - Missing or negative amounts
-    /// - Insufficient funds for withdrawals
-    pub fn settle_transaction(&mut self, tx: Transaction) {
+    /// - Tx IDs already recorded in this account's ledger
+    /// - Missing or negative amounts (logged as errors)
+    /// - Insufficient funds for withdrawals (including full withdrawals while
+    ///   `available` is negative)
+    pub fn settle_transaction(&mut self, tx: Transaction) -> Result<(), RejectionReason> {
+        self.settle_transaction_at(tx, SystemClock.now())
+    }
+
+    /// Like [`settle_transaction`](Self::settle_transaction), with `now` (Unix
+    /// seconds) as the current time for time-based policies.
+    pub fn settle_transaction_at(
+        &mut self,
+        tx: Transaction,
+        now: i64,
+    ) -> Result<(), RejectionReason> {
+        self.prune_settled_disputes(now);
+        let imbalance = self.imbalance();
+        let result = self.apply_settlement(&tx, now);
+        self.debug_assert_balanced(imbalance, &tx);
+        result
+    }
+
+    fn apply_settlement(&mut self, tx: &Transaction, now: i64) -> Result<(), RejectionReason> {
+        if self.closed {
+            return Err(RejectionReason::AccountClosed);
+        }
         if self.locked {
-            return; // Ignore all transactions on locked accounts
+            return Err(RejectionReason::AccountLocked); // Reject all transactions on locked accounts
         }
+        // The engine deduplicates tx IDs, but never let a reused ID overwrite a
+        // ledger entry that a dispute may already refer to
+        if self.ledger.contains_key(&tx.tx) || self.reservations.contains_key(&tx.tx) {
+            error!("Rejecting transaction reusing a ledgered tx ID: {:?}", tx);
+            return Err(RejectionReason::DuplicateTransaction);
+        }
+
+        // From here on a full withdrawal is an ordinary withdrawal of what is available
+        let full_withdrawal;
+        let tx = if tx.transaction_type == TransactionType::WithdrawAll {
+            full_withdrawal = Transaction {
+                transaction_type: TransactionType::Withdrawal,
+                amount: Some(self.available.max(Decimal::ZERO)),
+                ..tx.clone()
+            };
+            &full_withdrawal
+        } else {
+            tx
+        };
 
-        // Validate amount is present and non-negative, otherwise log error and ignore
+        // Validate amount is present and non-negative (unless a refund), otherwise log error and reject
+        let is_refund =
+            tx.transaction_type == TransactionType::Deposit && self.policy.allow_negative_deposits;
         let amount = match tx.amount {
-            Some(amt) if amt >= Decimal::default() => amt,
+            Some(amt) if amt >= Decimal::ZERO || is_refund => amt,
             Some(_) => {
                 error!("Rejecting transaction with negative amount: {:?}", tx);
-                return;
+                return Err(RejectionReason::NegativeAmount);
             }
             None => {
-                error!("Found malformed transaction entry: {:?}", tx);
-                return;
+                error!(
+                    "Rejecting tx {}: deposit/withdrawal missing required amount: {:?}",
+                    tx.tx, tx
+                );
+                return Err(RejectionReason::MissingAmount);
             }
         };
 
         match tx.transaction_type {
             TransactionType::Deposit => {
+                // A negative deposit (refund) debits the account like a withdrawal
+                if amount < Decimal::ZERO && self.available + amount < Decimal::ZERO {
+                    return Err(RejectionReason::InsufficientFunds);
+                }
                 self.available += amount;
                 self.total += amount;
+                self.total_deposited += amount;
             }
             TransactionType::Withdrawal => {
+                if let Some(limit) = self.policy.daily_withdrawal_limit {
+                    let today = now.div_euclid(SECONDS_PER_DAY);
+                    if self.withdrawn_today.0 != today {
+                        self.withdrawn_today = (today, Decimal::ZERO);
+                    }
+                    if self.withdrawn_today.1 + amount > limit {
+                        error!(
+                            "Rejecting withdrawal over daily limit ({} of {} already withdrawn) on account {}: {:?}",
+                            self.withdrawn_today.1, limit, self.client_id, tx
+                        );
+                        return Err(RejectionReason::DailyLimitExceeded);
+                    }
+                }
                 if self.available >= amount {
                     self.available -= amount;
                     self.total -= amount;
+                    self.total_withdrawn += amount;
+                    self.withdrawn_today.1 += amount;
+                    if !self.policy.store_withdrawals_in_ledger {
+                        return Ok(());
+                    }
                 } else {
-                    return; // Don't record failed withdrawals
+                    if self.policy.log_insufficient_funds {
+                        error!(
+                            "Rejecting withdrawal exceeding available funds ({}) on account {}: {:?}",
+                            self.available, self.client_id, tx
+                        );
+                    }
+                    return Err(RejectionReason::InsufficientFunds); // Don't record failed withdrawals
                 }
             }
-            TransactionType::Dispute | TransactionType::Resolve | TransactionType::Chargeback => {
+            TransactionType::Hold => {
+                if self.available < amount {
+                    return Err(RejectionReason::InsufficientFunds);
+                }
+                // Order holds never enter the ledger, so they can't be disputed
+                self.available -= amount;
+                self.held += amount;
+                self.reservations.insert(tx.tx, amount);
+                return Ok(());
+            }
+            TransactionType::Dispute
+            | TransactionType::Resolve
+            | TransactionType::Chargeback
+            | TransactionType::Close
+            | TransactionType::Unlock
+            | TransactionType::Release
+            | TransactionType::WithdrawAll => {
                 unreachable!()
             }
         }
@@ -124,6 +833,7 @@ impl ClientAccount {
         //         Neither of these cases reach here due to earlier checks.
         self.ledger
             .insert(tx.tx, TransactionHistoryEntry::try_from(tx).unwrap());
+        Ok(())
     }
 
     /// Adjudicate a dispute claim (dispute, resolve, or chargeback).
@@ -133,76 +843,278 @@ impl ClientAccount {
     /// When an account is locked (frozen) after a chargeback, we reject NEW disputes
     /// but allow existing disputes that were initiated before the freeze to be resolved
     /// or charged back.
-    pub fn adjudicate_claim(&mut self, tx: Transaction) {
-        if let Some(ledger_entry) = self.ledger.get(&tx.tx) {
-            match tx.transaction_type {
-                TransactionType::Dispute => {
-                    if self.locked {
-                        error!(
-                            "Received new dispute on locked account {}: {:?}",
-                            self.client_id, tx
-                        );
-                        return; // Reject NEW disputes on locked accounts
-                    }
-                    if self.disputes.contains_key(&tx.tx) {
+    ///
+    /// # Arithmetic
+    ///
+    /// Every claim moves balances by the same amounts regardless of sign, so
+    /// `total = available + held` holds even once `total` is negative:
+    /// - Dispute: `available -= hold`, `held += hold`
+    /// - Resolve: `held -= hold`, `available += hold`
+    /// - Chargeback: `held -= hold`, `available -= amount - hold`, `total -= amount`
+    ///
+    /// where `amount` is what the claim disputed (the whole deposit unless the
+    /// dispute row gave a partial amount) and `hold` is what it moved into `held`. A chained chargeback on
+    /// an already-negative account therefore simply deepens the negative total.
+    pub fn adjudicate_claim(&mut self, tx: Transaction) -> Result<(), RejectionReason> {
+        self.adjudicate_claim_at(tx, SystemClock.now())
+    }
+
+    /// Like [`adjudicate_claim`](Self::adjudicate_claim), with `now` (Unix
+    /// seconds) as the current time for time-based policies.
+    pub fn adjudicate_claim_at(
+        &mut self,
+        tx: Transaction,
+        now: i64,
+    ) -> Result<(), RejectionReason> {
+        self.prune_settled_disputes(now);
+        let imbalance = self.imbalance();
+        let result = self.apply_claim(&tx, now);
+        self.debug_assert_balanced(imbalance, &tx);
+        result
+    }
+
+    /// Project what applying `tx` would do, without changing this account.
+    ///
+    /// Dispatches like the engine: dispute-related rows are adjudicated,
+    /// account-level rows close or unlock, and everything else is settled.
+    /// Only this account's own rules are checked, so a tx ID the engine would
+    /// reject as a duplicate of another account's may still be projected as
+    /// accepted. Works on a copy of the account, ledger included.
+    pub fn simulate(&self, tx: &Transaction) -> SimResult {
+        let mut projected = self.clone();
+        let tx = tx.clone();
+        let outcome = match tx.transaction_type {
+            TransactionType::Close => projected.close().map(|_| ()),
+            TransactionType::Unlock => projected.unlock(),
+            TransactionType::Release => projected.release_hold(tx),
+            _ if tx.is_dispute_related() => projected.adjudicate_claim(tx),
+            _ => projected.settle_transaction(tx),
+        };
+        SimResult {
+            outcome,
+            available: projected.available,
+            held: projected.held,
+            total: projected.total,
+            locked: projected.locked,
+        }
+    }
+
+    /// Forget disputes settled at least [`AccountPolicy::settled_dispute_retention`]
+    /// seconds before `now`, along with their ledger entries.
+    fn prune_settled_disputes(&mut self, now: i64) {
+        let Some(retention) = self.policy.settled_dispute_retention else {
+            return;
+        };
+        while let Some(&(settled_at, tx)) = self.settled_disputes.front() {
+            if now.saturating_sub(settled_at) < retention {
+                break;
+            }
+            self.settled_disputes.pop_front();
+            // A later partial claim may have reopened the deposit since
+            if self
+                .disputes
+                .get(&tx)
+                .is_some_and(|dispute| dispute.state == DisputeState::Disputed)
+            {
+                continue;
+            }
+            self.disputes.remove(&tx);
+            self.ledger.remove(&tx);
+        }
+    }
+
+    fn apply_claim(&mut self, tx: &Transaction, now: i64) -> Result<(), RejectionReason> {
+        if self.closed {
+            error!(
+                "Received dispute-related request on closed account {}: {:?}",
+                self.client_id, tx
+            );
+            return Err(RejectionReason::AccountClosed);
+        }
+        let Some(ledger_entry) = self.ledger.get(&tx.tx) else {
+            error!(
+                "Received dispute-related request for unknown transaction: {:?}",
+                tx
+            );
+            return Err(RejectionReason::UnknownTransaction);
+        };
+        match tx.transaction_type {
+            TransactionType::Dispute => {
+                if self.locked {
+                    error!(
+                        "Received new dispute on locked account {}: {:?}",
+                        self.client_id, tx
+                    );
+                    return Err(RejectionReason::AccountLocked); // Reject NEW disputes on locked accounts
+                }
+                // Only deposits can be disputed
+                if ledger_entry.transaction_type != TransactionType::Deposit {
+                    error!(
+                        "Received request to dispute withdrawal transaction: {:?}",
+                        tx
+                    );
+                    return Err(RejectionReason::NotDisputable);
+                }
+                if ledger_entry.amount == Decimal::ZERO && self.policy.reject_zero_amount_disputes {
+                    error!("Rejecting no-op dispute on zero-amount deposit: {:?}", tx);
+                    return Err(RejectionReason::ZeroAmountDeposit);
+                }
+                let claimed = self
+                    .disputes
+                    .get(&tx.tx)
+                    .map_or(Decimal::ZERO, |dispute| dispute.claimed);
+                let amount = match tx.amount {
+                    // A full dispute claims the whole deposit, and so only once
+                    None if self.disputes.contains_key(&tx.tx) => {
                         error!("Received duplicate dispute for transaction: {:?}", tx);
-                        return; // Already disputed (or resolved/chargebacked)
+                        return Err(RejectionReason::AlreadyDisputed); // Already disputed (or resolved/chargebacked)
+                    }
+                    None => ledger_entry.amount,
+                    Some(amount) if amount < Decimal::ZERO => {
+                        error!("Rejecting dispute with negative amount: {:?}", tx);
+                        return Err(RejectionReason::NegativeAmount);
                     }
-                    // Only deposits can be disputed
-                    if ledger_entry.transaction_type == TransactionType::Deposit {
-                        self.available -= ledger_entry.amount;
-                        self.held += ledger_entry.amount;
-                        self.disputes.insert(tx.tx, DisputeState::Disputed);
-                    } else {
+                    Some(amount) if claimed + amount > ledger_entry.amount => {
                         error!(
-                            "Received request to dispute withdrawal transaction: {:?}",
-                            tx
+                            "Rejecting partial dispute exceeding deposit ({} of {} already claimed): {:?}",
+                            claimed, ledger_entry.amount, tx
                         );
+                        return Err(RejectionReason::DisputeExceedsDeposit);
                     }
+                    Some(amount) => amount,
+                };
+                // Refunds (negative deposits) have nothing left to hold
+                let disputed = amount.max(Decimal::ZERO);
+                let hold = self.capped_hold(disputed);
+                if !self.policy.allow_negative_available_on_dispute && hold > self.available {
+                    error!(
+                        "Dispute would make available negative for account {}: holding {} of {} available for tx {}",
+                        self.client_id, hold, self.available, tx.tx
+                    );
+                    return Err(RejectionReason::DisputeExceedsAvailable);
                 }
-                TransactionType::Resolve => {
-                    if let Some(state) = self.disputes.get_mut(&tx.tx) {
-                        if *state == DisputeState::Disputed {
-                            self.held -= ledger_entry.amount;
-                            self.available += ledger_entry.amount;
-                            *state = DisputeState::Resolved;
-                        } else {
-                            error!(
-                                "Received request to resolve non-disputed transaction: {:?}",
-                                tx
-                            );
-                        }
-                    } else {
-                        error!("Received request to resolve unknown transaction: {:?}", tx);
-                    }
+                if hold != disputed {
+                    info!(
+                        "Held funds capped for account {}: holding {} of {} for tx {}",
+                        self.client_id, hold, amount, tx.tx
+                    );
                 }
-                TransactionType::Chargeback => {
-                    if let Some(state) = self.disputes.get_mut(&tx.tx) {
-                        if *state == DisputeState::Disputed {
-                            self.held -= ledger_entry.amount;
-                            self.total -= ledger_entry.amount;
-                            self.locked = true;
-                            *state = DisputeState::ChargedBack;
-                        } else {
-                            error!(
-                                "Received request to chargeback non-disputed transaction: {:?}",
-                                tx
-                            );
-                        }
-                    } else {
-                        error!(
-                            "Received request to chargeback unknown transaction: {:?}",
-                            tx
-                        );
-                    }
+                self.available -= hold;
+                self.held += hold;
+                let dispute = self.disputes.entry(tx.tx).or_insert_with(|| DisputeRecord {
+                    state: DisputeState::Disputed,
+                    held: Decimal::ZERO,
+                    claimed: Decimal::ZERO,
+                    reason: None,
+                    open_claims: VecDeque::new(),
+                    charged_back: false,
+                });
+                dispute.state = DisputeState::Disputed;
+                dispute.held += hold;
+                dispute.claimed += amount;
+                dispute.open_claims.push_back(Claim { amount, held: hold });
+                if let Some(reason) = &tx.reason {
+                    dispute.reason = Some(reason.clone());
                 }
-                TransactionType::Deposit | TransactionType::Withdrawal => {}
+                return Ok(());
             }
-        } else {
-            error!(
-                "Received dispute-related request for unknown transaction: {:?}",
-                tx
-            );
+            TransactionType::Resolve => {
+                let Some(dispute) = self.disputes.get_mut(&tx.tx) else {
+                    error!("Received request to resolve unknown transaction: {:?}", tx);
+                    return Err(RejectionReason::NotUnderDispute);
+                };
+                let Some(claim) = dispute.settle_next(false) else {
+                    error!(
+                        "Received request to resolve non-disputed transaction: {:?}",
+                        tx
+                    );
+                    return Err(RejectionReason::NotUnderDispute);
+                };
+                self.held -= claim.held;
+                self.available += claim.held;
+                if dispute.state == DisputeState::Disputed {
+                    return Ok(()); // Other claims on the deposit are still open
+                }
+            }
+            TransactionType::Chargeback => {
+                let Some(dispute) = self.disputes.get_mut(&tx.tx) else {
+                    error!(
+                        "Received request to chargeback unknown transaction: {:?}",
+                        tx
+                    );
+                    return Err(RejectionReason::NotUnderDispute);
+                };
+                let Some(claim) = dispute.settle_next(true) else {
+                    error!(
+                        "Received request to chargeback non-disputed transaction: {:?}",
+                        tx
+                    );
+                    return Err(RejectionReason::NotUnderDispute);
+                };
+                let still_disputed = dispute.state == DisputeState::Disputed;
+                // Any portion not held (due to a cap) is reversed out of available
+                self.held -= claim.held;
+                self.available -= claim.amount - claim.held;
+                self.total -= claim.amount;
+                if self.policy.negative_total == NegativeTotalPolicy::ClampZero
+                    && self.total < Decimal::ZERO
+                {
+                    let written_off = (Decimal::ZERO - self.total).min(claim.amount);
+                    info!(
+                        "Writing off {} on account {} to keep total from going negative on chargeback of tx {}",
+                        written_off, self.client_id, tx.tx
+                    );
+                    self.available += written_off;
+                    self.total += written_off;
+                    self.total_written_off += written_off;
+                }
+                self.lock(LockReason::Chargeback);
+                if still_disputed {
+                    return Ok(()); // Other claims on the deposit are still open
+                }
+            }
+            TransactionType::Deposit
+            | TransactionType::Withdrawal
+            | TransactionType::Close
+            | TransactionType::Unlock
+            | TransactionType::Hold
+            | TransactionType::Release
+            | TransactionType::WithdrawAll => return Ok(()),
+        }
+        // Only a successful resolve or chargeback gets here
+        if tx.transaction_type != TransactionType::Dispute
+            && self.policy.settled_dispute_retention.is_some()
+        {
+            self.settled_disputes.push_back((now, tx.tx));
+        }
+        Ok(())
+    }
+
+    /// How far `total` is from `available + held` (zero for a consistent account).
+    fn imbalance(&self) -> Decimal {
+        self.total - (self.available + self.held)
+    }
+
+    /// Guard that a transaction moved `available`, `held`, and `total` consistently.
+    ///
+    /// Compares against the imbalance before the transaction rather than zero, so
+    /// accounts loaded with an inconsistent `total` (see [`ClientAccount::repair`])
+    /// don't trip it.
+    fn debug_assert_balanced(&self, imbalance_before: Decimal, tx: &Transaction) {
+        debug_assert_eq!(
+            self.imbalance(),
+            imbalance_before,
+            "account {} balances drifted applying {:?}",
+            self.client_id,
+            tx
+        );
+    }
+
+    /// Portion of `amount` that can be held without exceeding [`AccountPolicy::max_held`].
+    fn capped_hold(&self, amount: Decimal) -> Decimal {
+        match self.policy.max_held {
+            Some(cap) => amount.min((cap - self.held).max(Decimal::ZERO)),
+            None => amount,
         }
     }
 }
@@ -214,48 +1126,23 @@ mod tests {
     // ========== Test Helpers ==========
 
     fn make_deposit(tx: TransactionId, amount: f64) -> Transaction {
-        Transaction {
-            transaction_type: TransactionType::Deposit,
-            amount: Some(Decimal::from_f64(amount)),
-            tx,
-            client: 1,
-        }
+        Transaction::deposit(1, tx, Decimal::from_f64(amount))
     }
 
     fn make_withdrawal(tx: TransactionId, amount: f64) -> Transaction {
-        Transaction {
-            transaction_type: TransactionType::Withdrawal,
-            amount: Some(Decimal::from_f64(amount)),
-            tx,
-            client: 1,
-        }
+        Transaction::withdrawal(1, tx, Decimal::from_f64(amount))
     }
 
     fn make_dispute(tx: TransactionId) -> Transaction {
-        Transaction {
-            transaction_type: TransactionType::Dispute,
-            amount: None,
-            tx,
-            client: 1,
-        }
+        Transaction::dispute(1, tx)
     }
 
     fn make_resolve(tx: TransactionId) -> Transaction {
-        Transaction {
-            transaction_type: TransactionType::Resolve,
-            amount: None,
-            tx,
-            client: 1,
-        }
+        Transaction::resolve(1, tx)
     }
 
     fn make_chargeback(tx: TransactionId) -> Transaction {
-        Transaction {
-            transaction_type: TransactionType::Chargeback,
-            amount: None,
-            tx,
-            client: 1,
-        }
+        Transaction::chargeback(1, tx)
     }
 
     fn assert_balances(account: &ClientAccount, available: f64, held: f64, total: f64) {
@@ -268,426 +1155,1677 @@ mod tests {
         assert_eq!(account.total, Decimal::from_f64(total), "total mismatch");
     }
 
-    // ========== settle_transaction Tests ==========
+    // ========== settle_transaction Tests ==========
+
+    #[test]
+    fn test_deposit_updates_available_and_total() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        assert!(!account.locked);
+    }
+
+    #[test]
+    fn test_deposit_records_ledger() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+
+        assert!(account.ledger.contains_key(&1));
+        let entry = account.ledger.get(&1).unwrap();
+        assert_eq!(entry.transaction_type, TransactionType::Deposit);
+        assert_eq!(entry.amount, Decimal::from_f64(100.0));
+    }
+
+    #[test]
+    fn test_deposit_with_none_amount_ignored() {
+        let mut account = ClientAccount::new(1);
+        let tx = Transaction {
+            transaction_type: TransactionType::Deposit,
+            amount: None,
+            tx: 1,
+            client: 1,
+            timestamp: None,
+            reason: None,
+            source: None,
+        };
+        assert_eq!(
+            account.settle_transaction(tx),
+            Err(RejectionReason::MissingAmount)
+        );
+
+        assert_balances(&account, 0.0, 0.0, 0.0);
+        // Ledger should NOT be recorded for None amount
+        assert!(!account.ledger.contains_key(&1));
+    }
+
+    #[test]
+    fn test_withdrawal_sufficient_funds() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .settle_transaction(make_withdrawal(2, 30.0))
+            .unwrap();
+
+        assert_balances(&account, 70.0, 0.0, 70.0);
+    }
+
+    #[test]
+    fn test_withdrawal_insufficient_funds_rejected() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        assert_eq!(
+            account.settle_transaction(make_withdrawal(2, 150.0)),
+            Err(RejectionReason::InsufficientFunds)
+        );
+
+        // Balance unchanged
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        // Failed withdrawal NOT recorded in ledger (can't dispute what didn't happen)
+        assert!(!account.ledger.contains_key(&2));
+    }
+
+    #[test]
+    fn test_withdrawal_exact_balance() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .settle_transaction(make_withdrawal(2, 100.0))
+            .unwrap();
+
+        assert_balances(&account, 0.0, 0.0, 0.0);
+    }
+
+    #[test]
+    fn test_withdrawal_with_none_amount_ignored() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        let tx = Transaction {
+            transaction_type: TransactionType::Withdrawal,
+            amount: None,
+            tx: 2,
+            client: 1,
+            timestamp: None,
+            reason: None,
+            source: None,
+        };
+        assert_eq!(
+            account.settle_transaction(tx),
+            Err(RejectionReason::MissingAmount)
+        );
+
+        assert_balances(&account, 100.0, 0.0, 100.0);
+    }
+
+    #[test]
+    fn test_settle_transaction_on_locked_account_rejected() {
+        let mut account = ClientAccount::new(1);
+        account.locked = true;
+        assert_eq!(
+            account.settle_transaction(make_deposit(1, 100.0)),
+            Err(RejectionReason::AccountLocked)
+        );
+
+        assert_balances(&account, 0.0, 0.0, 0.0);
+        assert!(!account.ledger.contains_key(&1));
+    }
+
+    #[test]
+    fn test_duplicate_tx_id_rejected_by_ledger() {
+        // The engine deduplicates first, but the account also refuses to overwrite its ledger
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        assert_eq!(
+            account.settle_transaction(make_deposit(1, 50.0)), // Same tx ID
+            Err(RejectionReason::DuplicateTransaction)
+        );
+
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        let entry = account.ledger.get(&1).unwrap();
+        assert_eq!(entry.amount, Decimal::from_f64(100.0));
+    }
+
+    // ========== adjudicate_claim Tests ==========
+
+    #[test]
+    fn test_dispute_holds_funds() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+
+        assert_balances(&account, 0.0, 100.0, 100.0);
+        assert_eq!(
+            account.disputes.get(&1).map(|d| d.state),
+            Some(DisputeState::Disputed)
+        );
+    }
+
+    #[test]
+    fn test_dispute_unknown_tx_ignored() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        assert_eq!(
+            account.adjudicate_claim(make_dispute(999)),
+            Err(RejectionReason::UnknownTransaction)
+        ); // Unknown tx
+
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        assert!(!account.disputes.contains_key(&999));
+    }
+
+    #[test]
+    fn test_dispute_withdrawal_rejected() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .settle_transaction(make_withdrawal(2, 30.0))
+            .unwrap();
+        assert_eq!(
+            account.adjudicate_claim(make_dispute(2)),
+            Err(RejectionReason::NotDisputable)
+        ); // Try to dispute withdrawal
+
+        assert_balances(&account, 70.0, 0.0, 70.0);
+        assert!(!account.disputes.contains_key(&2));
+    }
+
+    #[test]
+    fn test_dispute_already_disputed_rejected() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        assert_eq!(
+            account.adjudicate_claim(make_dispute(1)),
+            Err(RejectionReason::AlreadyDisputed)
+        ); // Duplicate
+
+        // Should NOT double-hold
+        assert_balances(&account, 0.0, 100.0, 100.0);
+    }
+
+    fn make_partial_dispute(tx: TransactionId, amount: f64) -> Transaction {
+        Transaction {
+            amount: Some(Decimal::from_f64(amount)),
+            ..make_dispute(tx)
+        }
+    }
+
+    #[test]
+    fn test_sequential_partial_disputes_within_deposit() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .adjudicate_claim(make_partial_dispute(1, 30.0))
+            .unwrap();
+        account
+            .adjudicate_claim(make_partial_dispute(1, 20.0))
+            .unwrap();
+        assert_balances(&account, 50.0, 50.0, 100.0);
+        assert_eq!(account.disputes[&1].claimed, Decimal::from_f64(50.0));
+
+        // Each settlement takes the oldest open claim: resolve the 30, charge back the 20
+        account.adjudicate_claim(make_resolve(1)).unwrap();
+        assert_balances(&account, 80.0, 20.0, 100.0);
+        assert_eq!(account.disputes[&1].state, DisputeState::Disputed);
+        account.adjudicate_claim(make_chargeback(1)).unwrap();
+        assert_balances(&account, 80.0, 0.0, 80.0);
+        assert_eq!(account.disputes[&1].state, DisputeState::ChargedBack);
+        assert!(account.locked);
+        assert_eq!(
+            account.adjudicate_claim(make_resolve(1)),
+            Err(RejectionReason::NotUnderDispute)
+        );
+    }
+
+    #[test]
+    fn test_dispute_reason_retained_through_resolve() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .adjudicate_claim(make_dispute(1).with_reason("product-not-received"))
+            .unwrap();
+        account.adjudicate_claim(make_resolve(1)).unwrap();
+
+        let dispute = &account.disputes()[&1];
+        assert_eq!(dispute.state, DisputeState::Resolved);
+        assert_eq!(dispute.reason.as_deref(), Some("product-not-received"));
+    }
+
+    #[test]
+    fn test_partial_dispute_exceeding_deposit_rejected() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .adjudicate_claim(make_partial_dispute(1, 70.0))
+            .unwrap();
+        account.adjudicate_claim(make_resolve(1)).unwrap();
+
+        // Resolved claims still count toward the deposit
+        assert_eq!(
+            account.adjudicate_claim(make_partial_dispute(1, 40.0)),
+            Err(RejectionReason::DisputeExceedsDeposit)
+        );
+        assert_eq!(
+            account.adjudicate_claim(make_dispute(1)),
+            Err(RejectionReason::AlreadyDisputed)
+        );
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        account
+            .adjudicate_claim(make_partial_dispute(1, 30.0))
+            .unwrap();
+        assert_balances(&account, 70.0, 30.0, 100.0);
+    }
+
+    #[test]
+    fn test_dispute_on_locked_account_rejected() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.locked = true;
+        assert_eq!(
+            account.adjudicate_claim(make_dispute(1)),
+            Err(RejectionReason::AccountLocked)
+        );
+
+        // Dispute should be rejected
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        assert!(!account.disputes.contains_key(&1));
+    }
+
+    #[test]
+    fn test_resolve_releases_funds() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_resolve(1)).unwrap();
+
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        assert_eq!(
+            account.disputes.get(&1).map(|d| d.state),
+            Some(DisputeState::Resolved)
+        );
+        assert!(!account.locked);
+    }
+
+    #[test]
+    fn test_resolve_unknown_tx_ignored() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        assert_eq!(
+            account.adjudicate_claim(make_resolve(999)),
+            Err(RejectionReason::UnknownTransaction)
+        );
+
+        assert_balances(&account, 100.0, 0.0, 100.0);
+    }
+
+    #[test]
+    fn test_resolve_not_disputed_ignored() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        assert_eq!(
+            account.adjudicate_claim(make_resolve(1)),
+            Err(RejectionReason::NotUnderDispute)
+        ); // Not disputed
+
+        assert_balances(&account, 100.0, 0.0, 100.0);
+    }
+
+    #[test]
+    fn test_resolve_already_resolved_ignored() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_resolve(1)).unwrap();
+        assert_eq!(
+            account.adjudicate_claim(make_resolve(1)),
+            Err(RejectionReason::NotUnderDispute)
+        ); // Double resolve
+
+        // Should NOT double-release
+        assert_balances(&account, 100.0, 0.0, 100.0);
+    }
+
+    #[test]
+    fn test_resolve_on_locked_account_allowed() {
+        // Pre-freeze disputes can still be resolved
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.locked = true;
+        account.adjudicate_claim(make_resolve(1)).unwrap();
+
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        assert_eq!(
+            account.disputes.get(&1).map(|d| d.state),
+            Some(DisputeState::Resolved)
+        );
+    }
+
+    #[test]
+    fn test_chargeback_removes_funds_and_locks() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_chargeback(1)).unwrap();
+
+        assert_balances(&account, 0.0, 0.0, 0.0);
+        assert_eq!(
+            account.disputes.get(&1).map(|d| d.state),
+            Some(DisputeState::ChargedBack)
+        );
+        assert!(account.locked);
+    }
+
+    #[test]
+    fn test_chargeback_unknown_tx_ignored() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        assert_eq!(
+            account.adjudicate_claim(make_chargeback(999)),
+            Err(RejectionReason::UnknownTransaction)
+        );
+
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        assert!(!account.locked);
+    }
+
+    #[test]
+    fn test_chargeback_not_disputed_ignored() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        assert_eq!(
+            account.adjudicate_claim(make_chargeback(1)),
+            Err(RejectionReason::NotUnderDispute)
+        ); // Not disputed
+
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        assert!(!account.locked);
+    }
+
+    #[test]
+    fn test_chargeback_already_resolved_ignored() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_resolve(1)).unwrap();
+        assert_eq!(
+            account.adjudicate_claim(make_chargeback(1)),
+            Err(RejectionReason::NotUnderDispute)
+        ); // Already resolved
+
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        assert!(!account.locked);
+    }
+
+    #[test]
+    fn test_chargeback_already_chargedback_ignored() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.settle_transaction(make_deposit(2, 50.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_chargeback(1)).unwrap();
+        assert_eq!(
+            account.adjudicate_claim(make_chargeback(1)),
+            Err(RejectionReason::NotUnderDispute)
+        ); // Double chargeback
+
+        // Should NOT double-remove
+        assert_balances(&account, 50.0, 0.0, 50.0);
+    }
+
+    #[test]
+    fn test_chargeback_on_locked_account_allowed() {
+        // Pre-freeze disputes can still be charged back
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.settle_transaction(make_deposit(2, 50.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_dispute(2)).unwrap();
+        account.adjudicate_claim(make_chargeback(1)).unwrap(); // Locks account
+        account.adjudicate_claim(make_chargeback(2)).unwrap(); // Pre-freeze dispute
+
+        assert_balances(&account, 0.0, 0.0, 0.0);
+    }
+
+    #[test]
+    fn test_dispute_after_partial_spend_goes_negative() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .settle_transaction(make_withdrawal(2, 70.0))
+            .unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+
+        // Available goes negative
+        assert_balances(&account, -70.0, 100.0, 30.0);
+    }
+
+    fn with_dispute_retention(retention: i64) -> ClientAccount {
+        ClientAccount::with_policy(
+            1,
+            AccountPolicy {
+                settled_dispute_retention: Some(retention),
+                ..AccountPolicy::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_settled_disputes_pruned_after_retention() {
+        let mut account = with_dispute_retention(60);
+        for tx in 1..=3 {
+            account
+                .settle_transaction_at(make_deposit(tx, 10.0), 0)
+                .unwrap();
+        }
+        account.adjudicate_claim_at(make_dispute(1), 0).unwrap();
+        account.adjudicate_claim_at(make_resolve(1), 10).unwrap();
+        account.adjudicate_claim_at(make_dispute(2), 10).unwrap();
+        account.adjudicate_claim_at(make_resolve(2), 30).unwrap();
+        account.adjudicate_claim_at(make_dispute(3), 30).unwrap();
+        assert_eq!(account.disputes().len(), 3);
+        assert_eq!(account.ledger().len(), 3);
+
+        // Only the dispute settled at 10 is old enough at 70
+        account
+            .settle_transaction_at(make_deposit(4, 1.0), 70)
+            .unwrap();
+        assert_eq!(account.disputes().len(), 2);
+        assert!(!account.ledger().contains_key(&1));
+        assert!(account.ledger().contains_key(&2));
+
+        // Open disputes are never pruned, however old
+        account.adjudicate_claim_at(make_resolve(3), 1_000).unwrap();
+        assert_eq!(account.disputes().len(), 1);
+        assert_eq!(account.disputes()[&3].state, DisputeState::Resolved);
+        assert_balances(&account, 31.0, 0.0, 31.0);
+    }
+
+    #[test]
+    fn test_pruned_dispute_is_unknown() {
+        let mut account = with_dispute_retention(60);
+        account
+            .settle_transaction_at(make_deposit(1, 10.0), 0)
+            .unwrap();
+        account.adjudicate_claim_at(make_dispute(1), 0).unwrap();
+        account.adjudicate_claim_at(make_resolve(1), 0).unwrap();
+        assert_eq!(
+            account.adjudicate_claim_at(make_dispute(1), 59),
+            Err(RejectionReason::AlreadyDisputed)
+        );
+
+        assert_eq!(
+            account.adjudicate_claim_at(make_dispute(1), 60),
+            Err(RejectionReason::UnknownTransaction)
+        );
+        assert!(account.disputes().is_empty());
+        assert_balances(&account, 10.0, 0.0, 10.0);
+    }
+
+    #[test]
+    fn test_daily_withdrawal_limit_resets_next_day() {
+        let mut account = ClientAccount::with_policy(
+            1,
+            AccountPolicy {
+                daily_withdrawal_limit: Some(Decimal::from_f64(50.0)),
+                ..AccountPolicy::default()
+            },
+        );
+        let day = 24 * 60 * 60;
+        account.settle_transaction(make_deposit(1, 200.0)).unwrap();
+        account
+            .settle_transaction_at(make_withdrawal(2, 30.0), 0)
+            .unwrap();
+        assert_eq!(
+            account.settle_transaction_at(make_withdrawal(3, 30.0), day - 1),
+            Err(RejectionReason::DailyLimitExceeded)
+        );
+        // Exactly at the cap is allowed
+        account
+            .settle_transaction_at(make_withdrawal(4, 20.0), day - 1)
+            .unwrap();
+        account
+            .settle_transaction_at(make_withdrawal(5, 50.0), day)
+            .unwrap();
+        assert_balances(&account, 100.0, 0.0, 100.0);
+    }
+
+    #[test]
+    fn test_dispute_after_partial_spend_rejected_when_disallowed() {
+        let mut account = ClientAccount::with_policy(
+            1,
+            AccountPolicy {
+                allow_negative_available_on_dispute: false,
+                ..AccountPolicy::default()
+            },
+        );
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .settle_transaction(make_withdrawal(2, 70.0))
+            .unwrap();
+
+        assert_eq!(
+            account.adjudicate_claim(make_dispute(1)),
+            Err(RejectionReason::DisputeExceedsAvailable)
+        );
+        assert_balances(&account, 30.0, 0.0, 30.0);
+        assert!(account.disputes.is_empty());
+
+        // A dispute the available balance fully covers is still accepted
+        account.settle_transaction(make_deposit(3, 20.0)).unwrap();
+        account.adjudicate_claim(make_dispute(3)).unwrap();
+        assert_balances(&account, 30.0, 20.0, 50.0);
+    }
+
+    #[test]
+    fn test_chargeback_after_partial_spend_negative_total() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .settle_transaction(make_withdrawal(2, 70.0))
+            .unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_chargeback(1)).unwrap();
+
+        // Client owes money
+        assert_balances(&account, -70.0, 0.0, -70.0);
+        assert!(account.locked);
+    }
+
+    #[test]
+    fn test_chargeback_after_partial_spend_allowed_negative_by_default() {
+        assert_eq!(
+            AccountPolicy::default().negative_total,
+            NegativeTotalPolicy::Allow
+        );
+        let mut account = ClientAccount::with_policy(
+            1,
+            AccountPolicy {
+                negative_total: NegativeTotalPolicy::Allow,
+                ..AccountPolicy::default()
+            },
+        );
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .settle_transaction(make_withdrawal(2, 70.0))
+            .unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_chargeback(1)).unwrap();
+
+        assert_balances(&account, -70.0, 0.0, -70.0);
+    }
+
+    #[test]
+    fn test_chargeback_after_partial_spend_clamped_to_zero() {
+        let mut account = ClientAccount::with_policy(
+            1,
+            AccountPolicy {
+                negative_total: NegativeTotalPolicy::ClampZero,
+                ..AccountPolicy::default()
+            },
+        );
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.settle_transaction(make_deposit(3, 20.0)).unwrap();
+        account
+            .settle_transaction(make_withdrawal(2, 90.0))
+            .unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_chargeback(1)).unwrap();
+
+        // 30.0 remained, so 70.0 of the 100.0 reversal is written off
+        assert_balances(&account, 0.0, 0.0, 0.0);
+        assert!(account.locked);
+        assert_eq!(account.disputes()[&1].state, DisputeState::ChargedBack);
+    }
+
+    #[test]
+    fn test_clamp_zero_leaves_covered_chargeback_alone() {
+        let mut account = ClientAccount::with_policy(
+            1,
+            AccountPolicy {
+                negative_total: NegativeTotalPolicy::ClampZero,
+                ..AccountPolicy::default()
+            },
+        );
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.settle_transaction(make_deposit(2, 50.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_chargeback(1)).unwrap();
+
+        assert_balances(&account, 50.0, 0.0, 50.0);
+    }
+
+    /// Account whose total went negative from a chargeback while a second,
+    /// pre-freeze dispute (tx 3, 50.0) is still open. Tx 4 (5.0) is undisputed.
+    fn negative_account_with_open_dispute() -> ClientAccount {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.settle_transaction(make_deposit(3, 50.0)).unwrap();
+        account.settle_transaction(make_deposit(4, 5.0)).unwrap();
+        account
+            .settle_transaction(make_withdrawal(2, 125.0))
+            .unwrap();
+        account.adjudicate_claim(make_dispute(3)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        assert_balances(&account, -120.0, 150.0, 30.0);
+
+        account.adjudicate_claim(make_chargeback(1)).unwrap();
+        // Only the charged-back deposit leaves held; the other hold is untouched
+        assert_balances(&account, -120.0, 50.0, -70.0);
+        assert!(account.locked);
+        account
+    }
+
+    #[test]
+    fn test_pre_freeze_dispute_resolved_on_negative_total() {
+        let mut account = negative_account_with_open_dispute();
+        account.adjudicate_claim(make_resolve(3)).unwrap();
+
+        // Released funds return to available; total is unchanged by a resolve
+        assert_balances(&account, -70.0, 0.0, -70.0);
+    }
+
+    #[test]
+    fn test_pre_freeze_dispute_charged_back_on_negative_total() {
+        let mut account = negative_account_with_open_dispute();
+        account.adjudicate_claim(make_chargeback(3)).unwrap();
+
+        // The second chargeback deepens the negative total by the deposit amount
+        assert_balances(&account, -120.0, 0.0, -120.0);
+        assert!(account.locked);
+    }
+
+    #[test]
+    fn test_post_freeze_dispute_rejected_on_negative_total() {
+        let mut account = negative_account_with_open_dispute();
+        assert_eq!(
+            account.adjudicate_claim(make_dispute(4)),
+            Err(RejectionReason::AccountLocked)
+        );
+
+        assert_balances(&account, -120.0, 50.0, -70.0);
+    }
+
+    // ========== Edge Case Tests ==========
+
+    #[test]
+    fn test_negative_amount_deposit_rejected() {
+        let mut account = ClientAccount::new(1);
+        let tx = make_deposit(1, -100.0);
+        assert_eq!(
+            account.settle_transaction(tx),
+            Err(RejectionReason::NegativeAmount)
+        );
+
+        // Balance unchanged, not recorded in ledger
+        assert_balances(&account, 0.0, 0.0, 0.0);
+        assert!(!account.ledger.contains_key(&1));
+    }
+
+    #[test]
+    fn test_negative_amount_withdrawal_rejected() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        let tx = make_withdrawal(2, -50.0);
+        assert_eq!(
+            account.settle_transaction(tx),
+            Err(RejectionReason::NegativeAmount)
+        );
+
+        // Balance unchanged from deposit
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        assert!(!account.ledger.contains_key(&2));
+    }
+
+    #[test]
+    fn test_zero_amount_deposit() {
+        let mut account = ClientAccount::new(1);
+        let tx = make_deposit(1, 0.0);
+        account.settle_transaction(tx).unwrap();
+
+        // Zero deposit is valid, recorded in ledger
+        assert_balances(&account, 0.0, 0.0, 0.0);
+        assert!(account.ledger.contains_key(&1));
+    }
+
+    #[test]
+    fn test_zero_amount_deposit_can_be_disputed() {
+        let mut account = ClientAccount::new(1);
+        let tx = make_deposit(1, 0.0);
+        account.settle_transaction(tx).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+
+        // Zero held
+        assert_balances(&account, 0.0, 0.0, 0.0);
+        assert!(account.disputes.contains_key(&1));
+    }
+
+    #[test]
+    fn test_zero_amount_dispute_rejected_when_configured() {
+        let mut account = ClientAccount::with_policy(
+            1,
+            AccountPolicy {
+                reject_zero_amount_disputes: true,
+                ..AccountPolicy::default()
+            },
+        );
+        account.settle_transaction(make_deposit(1, 0.0)).unwrap();
+        account.settle_transaction(make_deposit(2, 5.0)).unwrap();
+        assert_eq!(
+            account.adjudicate_claim(make_dispute(1)),
+            Err(RejectionReason::ZeroAmountDeposit)
+        );
+        assert!(!account.disputes.contains_key(&1));
+
+        // Non-zero deposits are unaffected
+        account.adjudicate_claim(make_dispute(2)).unwrap();
+        assert_balances(&account, 0.0, 5.0, 5.0);
+    }
+
+    #[test]
+    fn test_boundary_tx_id_zero() {
+        let mut account = ClientAccount::new(1);
+        let tx = make_deposit(0, 100.0); // Minimum tx ID
+        account.settle_transaction(tx).unwrap();
+
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        assert!(account.ledger.contains_key(&0));
+    }
+
+    #[test]
+    fn test_boundary_tx_id_max() {
+        let mut account = ClientAccount::new(1);
+        let tx = make_deposit(u64::MAX, 100.0); // Maximum tx ID
+        account.settle_transaction(tx).unwrap();
+
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        assert!(account.ledger.contains_key(&u64::MAX));
+    }
+
+    #[test]
+    fn test_boundary_client_id_zero() {
+        let account = ClientAccount::new(0); // Minimum client ID
+        assert_eq!(account.client_id, 0);
+    }
+
+    #[test]
+    fn test_boundary_client_id_max() {
+        let account = ClientAccount::new(u16::MAX); // Maximum client ID
+        assert_eq!(account.client_id, u16::MAX);
+    }
+
+    // ========== close Tests ==========
+
+    #[test]
+    fn test_close_zero_balance_account() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .settle_transaction(make_withdrawal(2, 100.0))
+            .unwrap();
+
+        assert_eq!(account.close(), Ok(Decimal::ZERO));
+        assert!(account.is_closed());
+        assert!(!account.locked);
+        assert_balances(&account, 0.0, 0.0, 0.0);
+    }
+
+    #[test]
+    fn test_close_sweeps_remaining_available() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+
+        assert_eq!(account.close(), Ok(Decimal::from_f64(100.0)));
+        assert_balances(&account, 0.0, 0.0, 0.0);
+    }
+
+    #[test]
+    fn test_close_with_open_dispute_rejected() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+
+        assert_eq!(account.close(), Err(RejectionReason::OpenDisputes));
+        assert!(!account.is_closed());
+        assert_balances(&account, 0.0, 100.0, 100.0);
+    }
+
+    #[test]
+    fn test_close_after_resolved_dispute_allowed() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_resolve(1)).unwrap();
+
+        assert_eq!(account.close(), Ok(Decimal::from_f64(100.0)));
+        assert!(account.is_closed());
+    }
+
+    #[test]
+    fn test_close_locked_account_rejected() {
+        let mut account = ClientAccount::new(1);
+        account.locked = true;
+
+        assert_eq!(account.close(), Err(RejectionReason::AccountLocked));
+        assert!(!account.is_closed());
+    }
+
+    #[test]
+    fn test_closed_account_rejects_further_transactions() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.close().unwrap();
+
+        assert_eq!(
+            account.settle_transaction(make_deposit(2, 50.0)),
+            Err(RejectionReason::AccountClosed)
+        );
+        assert_eq!(
+            account.adjudicate_claim(make_dispute(1)),
+            Err(RejectionReason::AccountClosed)
+        );
+        assert_eq!(account.close(), Err(RejectionReason::AccountClosed));
+        assert_balances(&account, 0.0, 0.0, 0.0);
+    }
+
+    // ========== Unlock Tests ==========
+
+    #[test]
+    fn test_unlock_after_chargeback() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.settle_transaction(make_deposit(2, 20.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_chargeback(1)).unwrap();
+        assert!(account.locked);
+
+        account.unlock().unwrap();
+
+        assert!(!account.locked);
+        assert_balances(&account, 20.0, 0.0, 20.0);
+        account.settle_transaction(make_deposit(3, 5.0)).unwrap();
+        assert_balances(&account, 25.0, 0.0, 25.0);
+    }
+
+    #[test]
+    fn test_unlock_with_open_dispute_rejected() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.settle_transaction(make_deposit(2, 20.0)).unwrap();
+        account.adjudicate_claim(make_dispute(2)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_chargeback(1)).unwrap();
+
+        assert_eq!(account.unlock(), Err(RejectionReason::OpenDisputes));
+        assert!(account.locked);
+
+        // Once the pre-freeze dispute is settled, the unlock goes through
+        account.adjudicate_claim(make_resolve(2)).unwrap();
+        account.unlock().unwrap();
+        assert!(!account.locked);
+    }
+
+    #[test]
+    fn test_unlock_unlocked_or_closed_account_rejected() {
+        let mut account = ClientAccount::new(1);
+        assert_eq!(account.unlock(), Err(RejectionReason::NotLocked));
+
+        account.close().unwrap();
+        assert_eq!(account.unlock(), Err(RejectionReason::AccountClosed));
+    }
+
+    // ========== Order Hold Tests ==========
+
+    fn make_hold(tx: TransactionId, amount: f64) -> Transaction {
+        Transaction::hold(1, tx, Decimal::from_f64(amount))
+    }
+
+    #[test]
+    fn test_hold_then_release() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.settle_transaction(make_hold(2, 40.0)).unwrap();
+        assert_balances(&account, 60.0, 40.0, 100.0);
+        assert_eq!(account.reservations()[&2], Decimal::from_f64(40.0));
+
+        account.release_hold(Transaction::release(1, 2)).unwrap();
+        assert_balances(&account, 100.0, 0.0, 100.0);
+        assert!(account.reservations().is_empty());
+        assert_eq!(
+            account.release_hold(Transaction::release(1, 2)),
+            Err(RejectionReason::UnknownTransaction)
+        );
+    }
+
+    #[test]
+    fn test_held_for_order_cannot_be_withdrawn() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.settle_transaction(make_hold(2, 70.0)).unwrap();
+
+        assert_eq!(
+            account.settle_transaction(make_withdrawal(3, 50.0)),
+            Err(RejectionReason::InsufficientFunds)
+        );
+        assert_eq!(
+            account.settle_transaction(make_hold(4, 31.0)),
+            Err(RejectionReason::InsufficientFunds)
+        );
+        account
+            .settle_transaction(make_withdrawal(5, 30.0))
+            .unwrap();
+        assert_balances(&account, 0.0, 70.0, 70.0);
+    }
+
+    #[test]
+    fn test_chargeback_leaves_order_hold_intact() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.settle_transaction(make_deposit(2, 50.0)).unwrap();
+        account.settle_transaction(make_hold(3, 30.0)).unwrap();
+        account.adjudicate_claim(make_dispute(2)).unwrap();
+        account.adjudicate_claim(make_chargeback(2)).unwrap();
+
+        assert_balances(&account, 70.0, 30.0, 100.0);
+        assert!(account.locked);
+        // The hold can't be disputed, but can still be released while locked
+        assert_eq!(
+            account.adjudicate_claim(make_dispute(3)),
+            Err(RejectionReason::UnknownTransaction)
+        );
+        account.release_hold(Transaction::release(1, 3)).unwrap();
+        assert_balances(&account, 100.0, 0.0, 100.0);
+    }
+
+    #[test]
+    fn test_open_order_hold_blocks_close() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 10.0)).unwrap();
+        account.settle_transaction(make_hold(2, 5.0)).unwrap();
+
+        assert_eq!(account.close(), Err(RejectionReason::OpenDisputes));
+        account.release_hold(Transaction::release(1, 2)).unwrap();
+        assert_eq!(account.close(), Ok(Decimal::from_f64(10.0)));
+    }
+
+    // ========== WithdrawAll Tests ==========
+
+    #[test]
+    fn test_withdraw_all_leaves_disputed_funds_held() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 70.0)).unwrap();
+        account.settle_transaction(make_deposit(2, 30.0)).unwrap();
+        account.adjudicate_claim(make_dispute(2)).unwrap();
+        assert_balances(&account, 70.0, 30.0, 100.0);
+
+        account
+            .settle_transaction(Transaction::withdraw_all(1, 3))
+            .unwrap();
+        assert_balances(&account, 0.0, 30.0, 30.0);
+        assert_eq!(account.total_withdrawn(), Decimal::from_f64(70.0));
+        // Ledgered as the amount actually withdrawn, and like any withdrawal not disputable
+        let entry = &account.ledger()[&3];
+        assert_eq!(entry.transaction_type, TransactionType::Withdrawal);
+        assert_eq!(entry.amount, Decimal::from_f64(70.0));
+        assert_eq!(
+            account.adjudicate_claim(make_dispute(3)),
+            Err(RejectionReason::NotDisputable)
+        );
+    }
+
+    #[test]
+    fn test_withdraw_all_with_nothing_available() {
+        let mut account = ClientAccount::new(1);
+        account.settle_transaction(make_deposit(1, 10.0)).unwrap();
+        account.settle_transaction(make_withdrawal(2, 4.0)).unwrap();
+        // Disputing the partially spent deposit drives available negative
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        assert_balances(&account, -4.0, 10.0, 6.0);
+
+        assert_eq!(
+            account.settle_transaction(Transaction::withdraw_all(1, 3)),
+            Err(RejectionReason::InsufficientFunds)
+        );
+        assert_balances(&account, -4.0, 10.0, 6.0);
+        assert!(!account.ledger().contains_key(&3));
+    }
+
+    // ========== simulate Tests ==========
 
     #[test]
-    fn test_deposit_updates_available_and_total() {
+    fn test_simulate_over_withdrawal_rejected() {
         let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-
-        assert_balances(&account, 100.0, 0.0, 100.0);
-        assert!(!account.locked);
+        account.settle_transaction(make_deposit(1, 50.0)).unwrap();
+        let before = account.clone();
+
+        let result = account.simulate(&make_withdrawal(2, 80.0));
+        assert!(!result.is_accepted());
+        assert_eq!(result.outcome, Err(RejectionReason::InsufficientFunds));
+        assert_eq!(result.available, Decimal::from_f64(50.0));
+        assert_eq!(result.total, Decimal::from_f64(50.0));
+        assert_eq!(account, before);
     }
 
     #[test]
-    fn test_deposit_records_ledger() {
+    fn test_simulate_deposit_projects_balances() {
         let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
+        account.settle_transaction(make_deposit(1, 50.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        let before = account.clone();
 
-        assert!(account.ledger.contains_key(&1));
-        let entry = account.ledger.get(&1).unwrap();
-        assert_eq!(entry.transaction_type, TransactionType::Deposit);
-        assert_eq!(entry.amount, Decimal::from_f64(100.0));
+        let result = account.simulate(&make_deposit(2, 25.5));
+        assert_eq!(
+            result,
+            SimResult {
+                outcome: Ok(()),
+                available: Decimal::from_f64(25.5),
+                held: Decimal::from_f64(50.0),
+                total: Decimal::from_f64(75.5),
+                locked: false,
+            }
+        );
+        // Nothing was committed: the tx ID is still free and the dispute still open
+        assert_eq!(account, before);
+        assert!(!account.ledger().contains_key(&2));
+        assert!(account.simulate(&make_chargeback(1)).locked);
+        assert!(!account.locked);
     }
 
-    #[test]
-    fn test_deposit_with_none_amount_ignored() {
-        let mut account = ClientAccount::new(1);
-        let tx = Transaction {
-            transaction_type: TransactionType::Deposit,
-            amount: None,
-            tx: 1,
-            client: 1,
-        };
-        account.settle_transaction(tx);
+    // ========== Withdrawal Ledger Policy Tests ==========
 
-        assert_balances(&account, 0.0, 0.0, 0.0);
-        // Ledger should NOT be recorded for None amount
-        assert!(!account.ledger.contains_key(&1));
+    fn no_withdrawal_ledger() -> AccountPolicy {
+        AccountPolicy {
+            store_withdrawals_in_ledger: false,
+            ..AccountPolicy::default()
+        }
+    }
+
+    /// Runs every dispute-related operation against a withdrawal and asserts
+    /// that none of them moves funds into `held`.
+    fn assert_withdrawal_never_held(account: &mut ClientAccount, withdrawal_tx: TransactionId) {
+        let before = (account.available, account.held, account.total);
+        for claim in [
+            make_dispute(withdrawal_tx),
+            make_resolve(withdrawal_tx),
+            make_chargeback(withdrawal_tx),
+        ] {
+            assert!(account.adjudicate_claim(claim).is_err());
+            assert_eq!(account.held, Decimal::ZERO);
+        }
+        assert_eq!((account.available, account.held, account.total), before);
+        assert!(!account.disputes.contains_key(&withdrawal_tx));
+        assert!(!account.locked);
     }
 
     #[test]
-    fn test_withdrawal_sufficient_funds() {
+    fn test_withdrawal_stored_in_ledger_by_default() {
         let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.settle_transaction(make_withdrawal(2, 30.0));
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .settle_transaction(make_withdrawal(2, 30.0))
+            .unwrap();
 
-        assert_balances(&account, 70.0, 0.0, 70.0);
+        assert!(account.ledger.contains_key(&2));
+        assert_eq!(
+            account.adjudicate_claim(make_dispute(2)),
+            Err(RejectionReason::NotDisputable)
+        );
+        assert_withdrawal_never_held(&mut account, 2);
     }
 
     #[test]
-    fn test_withdrawal_insufficient_funds_rejected() {
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.settle_transaction(make_withdrawal(2, 150.0));
+    fn test_withdrawal_not_stored_in_ledger_when_disabled() {
+        let mut account = ClientAccount::with_policy(1, no_withdrawal_ledger());
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .settle_transaction(make_withdrawal(2, 30.0))
+            .unwrap();
 
-        // Balance unchanged
-        assert_balances(&account, 100.0, 0.0, 100.0);
-        // Failed withdrawal NOT recorded in ledger (can't dispute what didn't happen)
+        assert_balances(&account, 70.0, 0.0, 70.0);
         assert!(!account.ledger.contains_key(&2));
+        assert_eq!(
+            account.adjudicate_claim(make_dispute(2)),
+            Err(RejectionReason::UnknownTransaction)
+        );
+        assert_withdrawal_never_held(&mut account, 2);
     }
 
     #[test]
-    fn test_withdrawal_exact_balance() {
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.settle_transaction(make_withdrawal(2, 100.0));
+    fn test_deposits_still_disputable_without_withdrawal_ledger() {
+        let mut account = ClientAccount::with_policy(1, no_withdrawal_ledger());
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .settle_transaction(make_withdrawal(2, 30.0))
+            .unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+
+        assert_balances(&account, -30.0, 100.0, 70.0);
+    }
 
-        assert_balances(&account, 0.0, 0.0, 0.0);
+    // ========== max_held Policy Tests ==========
+
+    fn capped_account(cap: f64) -> ClientAccount {
+        ClientAccount::with_policy(
+            1,
+            AccountPolicy {
+                max_held: Some(Decimal::from_f64(cap)),
+                ..AccountPolicy::default()
+            },
+        )
     }
 
     #[test]
-    fn test_withdrawal_with_none_amount_ignored() {
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        let tx = Transaction {
-            transaction_type: TransactionType::Withdrawal,
-            amount: None,
-            tx: 2,
-            client: 1,
-        };
-        account.settle_transaction(tx);
-
-        assert_balances(&account, 100.0, 0.0, 100.0);
+    fn test_dispute_into_cap_splits_hold() {
+        let mut account = capped_account(50.0);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+
+        // Only 50 held; the excess 50 stays available
+        assert_balances(&account, 50.0, 50.0, 100.0);
+        assert_eq!(account.disputes[&1].held, Decimal::from_f64(50.0));
     }
 
     #[test]
-    fn test_settle_transaction_on_locked_account_rejected() {
-        let mut account = ClientAccount::new(1);
-        account.locked = true;
-        account.settle_transaction(make_deposit(1, 100.0));
+    fn test_dispute_under_cap_holds_fully() {
+        let mut account = capped_account(50.0);
+        account.settle_transaction(make_deposit(1, 30.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
 
-        assert_balances(&account, 0.0, 0.0, 0.0);
-        assert!(!account.ledger.contains_key(&1));
+        assert_balances(&account, 0.0, 30.0, 30.0);
     }
 
     #[test]
-    fn test_duplicate_tx_id_overwrites_ledger() {
-        // Note: Engine prevents this, but at ClientAccount level it overwrites
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.settle_transaction(make_deposit(1, 50.0)); // Same tx ID
-
-        // Both deposits apply (ClientAccount doesn't check for duplicates)
-        assert_balances(&account, 150.0, 0.0, 150.0);
-        // Ledger has the second entry
-        let entry = account.ledger.get(&1).unwrap();
-        assert_eq!(entry.amount, Decimal::from_f64(50.0));
+    fn test_second_dispute_holds_only_remaining_cap() {
+        let mut account = capped_account(50.0);
+        account.settle_transaction(make_deposit(1, 30.0)).unwrap();
+        account.settle_transaction(make_deposit(2, 40.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap(); // held: 30
+        account.adjudicate_claim(make_dispute(2)).unwrap(); // held: 30 + 20 (capped)
+
+        assert_balances(&account, 20.0, 50.0, 70.0);
+
+        // Resolving releases only what was held for tx 2
+        account.adjudicate_claim(make_resolve(2)).unwrap();
+        assert_balances(&account, 40.0, 30.0, 70.0);
     }
 
-    // ========== adjudicate_claim Tests ==========
+    #[test]
+    fn test_dispute_at_cap_holds_nothing() {
+        let mut account = capped_account(30.0);
+        account.settle_transaction(make_deposit(1, 30.0)).unwrap();
+        account.settle_transaction(make_deposit(2, 40.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_dispute(2)).unwrap();
+
+        assert_balances(&account, 40.0, 30.0, 70.0);
+        assert_eq!(account.disputes[&2].held, Decimal::ZERO);
+    }
 
     #[test]
-    fn test_dispute_holds_funds() {
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.adjudicate_claim(make_dispute(1));
+    fn test_resolve_capped_dispute_releases_held_portion() {
+        let mut account = capped_account(50.0);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_resolve(1)).unwrap();
 
-        assert_balances(&account, 0.0, 100.0, 100.0);
-        assert_eq!(account.disputes.get(&1), Some(&DisputeState::Disputed));
+        assert_balances(&account, 100.0, 0.0, 100.0);
     }
 
     #[test]
-    fn test_dispute_unknown_tx_ignored() {
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.adjudicate_claim(make_dispute(999)); // Unknown tx
+    fn test_chargeback_capped_dispute_reverses_full_deposit() {
+        let mut account = capped_account(50.0);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.settle_transaction(make_deposit(2, 10.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap(); // available: 60, held: 50
+        account.adjudicate_claim(make_chargeback(1)).unwrap();
+
+        // 50 from held and the 50 excess from available
+        assert_balances(&account, 10.0, 0.0, 10.0);
+        assert!(account.locked);
+    }
 
+    #[test]
+    fn test_resolve_partial_capped_claims_release_exactly_what_was_held() {
+        let mut account = capped_account(40.0);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .adjudicate_claim(make_partial_dispute(1, 30.0))
+            .unwrap(); // held: 30
+        account
+            .adjudicate_claim(make_partial_dispute(1, 30.0))
+            .unwrap(); // held: 30 + 10 (capped)
+        assert_balances(&account, 60.0, 40.0, 100.0);
+
+        // Each resolve releases its own claim's held portion, not the claim or deposit amount
+        account.adjudicate_claim(make_resolve(1)).unwrap();
+        assert_balances(&account, 90.0, 10.0, 100.0);
+        assert_eq!(account.disputes[&1].held, Decimal::from_f64(10.0));
+        account.adjudicate_claim(make_resolve(1)).unwrap();
         assert_balances(&account, 100.0, 0.0, 100.0);
-        assert!(!account.disputes.contains_key(&999));
+        assert_eq!(account.disputes[&1].held, Decimal::ZERO);
     }
 
     #[test]
-    fn test_dispute_withdrawal_rejected() {
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.settle_transaction(make_withdrawal(2, 30.0));
-        account.adjudicate_claim(make_dispute(2)); // Try to dispute withdrawal
-
+    fn test_chargeback_partial_capped_claim_reverses_only_its_claim() {
+        let mut account = capped_account(40.0);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .adjudicate_claim(make_partial_dispute(1, 30.0))
+            .unwrap(); // held: 30
+        account
+            .adjudicate_claim(make_partial_dispute(1, 30.0))
+            .unwrap(); // held: 30 + 10 (capped)
+        account.adjudicate_claim(make_resolve(1)).unwrap();
+
+        // 10 from held and the 20 excess from available
+        account.adjudicate_claim(make_chargeback(1)).unwrap();
         assert_balances(&account, 70.0, 0.0, 70.0);
-        assert!(!account.disputes.contains_key(&2));
+        assert!(account.locked);
     }
 
+    // ========== repair Tests ==========
+
     #[test]
-    fn test_dispute_already_disputed_rejected() {
+    fn test_recompute_total() {
         let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.adjudicate_claim(make_dispute(1));
-        account.adjudicate_claim(make_dispute(1)); // Duplicate
+        account.available = Decimal::from_f64(10.0);
+        account.held = Decimal::from_f64(5.0);
+        account.recompute_total();
 
-        // Should NOT double-hold
-        assert_balances(&account, 0.0, 100.0, 100.0);
+        assert_balances(&account, 10.0, 5.0, 15.0);
     }
 
     #[test]
-    fn test_dispute_on_locked_account_rejected() {
+    fn test_repair_fixes_corrupted_total() {
         let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.locked = true;
-        account.adjudicate_claim(make_dispute(1));
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.settle_transaction(make_deposit(2, 20.0)).unwrap();
+        account.total = Decimal::from_f64(999.0); // Corrupt
 
-        // Dispute should be rejected
-        assert_balances(&account, 100.0, 0.0, 100.0);
-        assert!(!account.disputes.contains_key(&1));
+        assert!(account.repair());
+        assert_balances(&account, 20.0, 100.0, 120.0);
     }
 
     #[test]
-    fn test_resolve_releases_funds() {
+    fn test_repair_consistent_account_is_noop() {
         let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.adjudicate_claim(make_dispute(1));
-        account.adjudicate_claim(make_resolve(1));
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
 
+        assert!(!account.repair());
         assert_balances(&account, 100.0, 0.0, 100.0);
-        assert_eq!(account.disputes.get(&1), Some(&DisputeState::Resolved));
-        assert!(!account.locked);
     }
 
-    #[test]
-    fn test_resolve_unknown_tx_ignored() {
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.adjudicate_claim(make_resolve(999));
+    // ========== allow_negative_deposits Policy Tests ==========
 
-        assert_balances(&account, 100.0, 0.0, 100.0);
+    fn refund_account() -> ClientAccount {
+        ClientAccount::with_policy(
+            1,
+            AccountPolicy {
+                allow_negative_deposits: true,
+                ..AccountPolicy::default()
+            },
+        )
     }
 
     #[test]
-    fn test_resolve_not_disputed_ignored() {
+    fn test_negative_deposit_rejected_by_default() {
         let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.adjudicate_claim(make_resolve(1)); // Not disputed
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
 
+        assert_eq!(
+            account.settle_transaction(make_deposit(2, -30.0)),
+            Err(RejectionReason::NegativeAmount)
+        );
         assert_balances(&account, 100.0, 0.0, 100.0);
     }
 
     #[test]
-    fn test_resolve_already_resolved_ignored() {
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.adjudicate_claim(make_dispute(1));
-        account.adjudicate_claim(make_resolve(1));
-        account.adjudicate_claim(make_resolve(1)); // Double resolve
+    fn test_negative_deposit_debits_as_refund_when_allowed() {
+        let mut account = refund_account();
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.settle_transaction(make_deposit(2, -30.0)).unwrap();
 
-        // Should NOT double-release
-        assert_balances(&account, 100.0, 0.0, 100.0);
+        assert_balances(&account, 70.0, 0.0, 70.0);
+        let entry = account.ledger[&2];
+        assert_eq!(entry.transaction_type, TransactionType::Deposit);
+        assert_eq!(entry.amount, Decimal::from_f64(-30.0));
     }
 
     #[test]
-    fn test_resolve_on_locked_account_allowed() {
-        // Pre-freeze disputes can still be resolved
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.adjudicate_claim(make_dispute(1));
-        account.locked = true;
-        account.adjudicate_claim(make_resolve(1));
+    fn test_refund_exceeding_available_rejected() {
+        let mut account = refund_account();
+        account.settle_transaction(make_deposit(1, 10.0)).unwrap();
 
-        assert_balances(&account, 100.0, 0.0, 100.0);
-        assert_eq!(account.disputes.get(&1), Some(&DisputeState::Resolved));
+        assert_eq!(
+            account.settle_transaction(make_deposit(2, -30.0)),
+            Err(RejectionReason::InsufficientFunds)
+        );
+        assert_balances(&account, 10.0, 0.0, 10.0);
+        assert!(!account.ledger.contains_key(&2));
     }
 
     #[test]
-    fn test_chargeback_removes_funds_and_locks() {
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.adjudicate_claim(make_dispute(1));
-        account.adjudicate_claim(make_chargeback(1));
+    fn test_refund_negative_withdrawal_still_rejected() {
+        let mut account = refund_account();
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
 
-        assert_balances(&account, 0.0, 0.0, 0.0);
-        assert_eq!(account.disputes.get(&1), Some(&DisputeState::ChargedBack));
-        assert!(account.locked);
+        assert_eq!(
+            account.settle_transaction(make_withdrawal(2, -30.0)),
+            Err(RejectionReason::NegativeAmount)
+        );
     }
 
     #[test]
-    fn test_chargeback_unknown_tx_ignored() {
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.adjudicate_claim(make_chargeback(999));
+    fn test_refund_dispute_and_chargeback_reverse_refund() {
+        let mut account = refund_account();
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.settle_transaction(make_deposit(2, -30.0)).unwrap();
+
+        // Nothing to hold: the refunded funds already left the account
+        account.adjudicate_claim(make_dispute(2)).unwrap();
+        assert_balances(&account, 70.0, 0.0, 70.0);
 
+        // Chargeback reverses the refund, crediting it back
+        account.adjudicate_claim(make_chargeback(2)).unwrap();
         assert_balances(&account, 100.0, 0.0, 100.0);
-        assert!(!account.locked);
+        assert!(account.locked);
     }
 
     #[test]
-    fn test_chargeback_not_disputed_ignored() {
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.adjudicate_claim(make_chargeback(1)); // Not disputed
+    fn test_refund_dispute_resolve_is_noop() {
+        let mut account = refund_account();
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account.settle_transaction(make_deposit(2, -30.0)).unwrap();
+        account.adjudicate_claim(make_dispute(2)).unwrap();
+        account.adjudicate_claim(make_resolve(2)).unwrap();
 
-        assert_balances(&account, 100.0, 0.0, 100.0);
-        assert!(!account.locked);
+        assert_balances(&account, 70.0, 0.0, 70.0);
     }
 
+    // ========== with_balances Tests ==========
+
     #[test]
-    fn test_chargeback_already_resolved_ignored() {
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.adjudicate_claim(make_dispute(1));
-        account.adjudicate_claim(make_resolve(1));
-        account.adjudicate_claim(make_chargeback(1)); // Already resolved
+    fn test_with_balances_derives_total() {
+        let account =
+            ClientAccount::with_balances(3, Decimal::from_f64(12.5), Decimal::from_f64(2.5), false);
 
-        assert_balances(&account, 100.0, 0.0, 100.0);
+        assert_eq!(account.client_id, 3);
+        assert_balances(&account, 12.5, 2.5, 15.0);
         assert!(!account.locked);
+        assert!(!account.is_closed());
+        assert!(account.ledger().is_empty());
+        assert!(account.disputes().is_empty());
     }
 
     #[test]
-    fn test_chargeback_already_chargedback_ignored() {
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.settle_transaction(make_deposit(2, 50.0));
-        account.adjudicate_claim(make_dispute(1));
-        account.adjudicate_claim(make_chargeback(1));
-        account.adjudicate_claim(make_chargeback(1)); // Double chargeback
+    fn test_with_balances_negative_and_locked() {
+        let mut account = ClientAccount::with_balances(
+            1,
+            Decimal::from_f64(-50.0),
+            Decimal::from_f64(25.0),
+            true,
+        );
 
-        // Should NOT double-remove
-        assert_balances(&account, 50.0, 0.0, 50.0);
+        assert_balances(&account, -50.0, 25.0, -25.0);
+        assert!(account.locked);
+        assert!(!account.repair()); // Already consistent
+        assert_eq!(
+            account.settle_transaction(make_deposit(1, 1.0)),
+            Err(RejectionReason::AccountLocked)
+        );
     }
 
     #[test]
-    fn test_chargeback_on_locked_account_allowed() {
-        // Pre-freeze disputes can still be charged back
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.settle_transaction(make_deposit(2, 50.0));
-        account.adjudicate_claim(make_dispute(1));
-        account.adjudicate_claim(make_dispute(2));
-        account.adjudicate_claim(make_chargeback(1)); // Locks account
-        account.adjudicate_claim(make_chargeback(2)); // Pre-freeze dispute
+    fn test_with_balances_seeded_funds_not_disputable() {
+        let mut account =
+            ClientAccount::with_balances(1, Decimal::from_f64(10.0), Decimal::ZERO, false);
+        account.settle_transaction(make_withdrawal(1, 4.0)).unwrap();
 
-        assert_balances(&account, 0.0, 0.0, 0.0);
+        assert_eq!(
+            account.adjudicate_claim(make_dispute(2)),
+            Err(RejectionReason::UnknownTransaction)
+        );
+        assert_balances(&account, 6.0, 0.0, 6.0);
     }
 
+    // ========== Cashflow Totals Tests ==========
+
     #[test]
-    fn test_dispute_after_partial_spend_goes_negative() {
+    fn test_cashflow_totals_ignore_chargebacks() {
         let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.settle_transaction(make_withdrawal(2, 70.0));
-        account.adjudicate_claim(make_dispute(1));
-
-        // Available goes negative
-        assert_balances(&account, -70.0, 100.0, 30.0);
+        account.settle_transaction(make_deposit(1, 100.0)).unwrap();
+        account
+            .settle_transaction(make_withdrawal(2, 30.0))
+            .unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        account.adjudicate_claim(make_chargeback(1)).unwrap();
+
+        assert_balances(&account, -30.0, 0.0, -30.0);
+        assert_eq!(account.total_deposited(), Decimal::from_f64(100.0));
+        assert_eq!(account.total_withdrawn(), Decimal::from_f64(30.0));
     }
 
     #[test]
-    fn test_chargeback_after_partial_spend_negative_total() {
+    fn test_cashflow_totals_skip_rejected_transactions() {
         let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        account.settle_transaction(make_withdrawal(2, 70.0));
-        account.adjudicate_claim(make_dispute(1));
-        account.adjudicate_claim(make_chargeback(1));
+        account.settle_transaction(make_deposit(1, 10.0)).unwrap();
+        assert_eq!(
+            account.settle_transaction(make_withdrawal(2, 20.0)),
+            Err(RejectionReason::InsufficientFunds)
+        );
+        assert_eq!(
+            account.settle_transaction(make_deposit(3, -5.0)),
+            Err(RejectionReason::NegativeAmount)
+        );
 
-        // Client owes money
-        assert_balances(&account, -70.0, 0.0, -70.0);
-        assert!(account.locked);
+        assert_eq!(account.total_deposited(), Decimal::from_f64(10.0));
+        assert_eq!(account.total_withdrawn(), Decimal::ZERO);
     }
 
-    // ========== Edge Case Tests ==========
+    // ========== Failed Withdrawal Tracking Tests ==========
 
     #[test]
-    fn test_negative_amount_deposit_rejected() {
+    fn test_failed_withdrawal_count_saturates() {
         let mut account = ClientAccount::new(1);
-        let tx = Transaction {
-            transaction_type: TransactionType::Deposit,
-            amount: Some(Decimal::from_f64(-100.0)),
-            tx: 1,
-            client: 1,
-        };
-        account.settle_transaction(tx);
+        account.failed_withdrawals = u32::MAX;
+        let tx = make_withdrawal(1, 5.0);
+        let result = account.settle_transaction(tx.clone());
+        assert_eq!(result, Err(RejectionReason::InsufficientFunds));
 
-        // Balance unchanged, not recorded in ledger
-        assert_balances(&account, 0.0, 0.0, 0.0);
-        assert!(!account.ledger.contains_key(&1));
+        assert!(!account.track_failed_withdrawals(&tx, result));
+        assert_eq!(account.failed_withdrawals, u32::MAX);
     }
 
-    #[test]
-    fn test_negative_amount_withdrawal_rejected() {
-        let mut account = ClientAccount::new(1);
-        account.settle_transaction(make_deposit(1, 100.0));
-        let tx = Transaction {
-            transaction_type: TransactionType::Withdrawal,
-            amount: Some(Decimal::from_f64(-50.0)),
-            tx: 2,
-            client: 1,
-        };
-        account.settle_transaction(tx);
+    // ========== AccountView Tests ==========
 
-        // Balance unchanged from deposit
-        assert_balances(&account, 100.0, 0.0, 100.0);
-        assert!(!account.ledger.contains_key(&2));
+    fn assert_view_matches(account: &ClientAccount) {
+        let view = account.as_view();
+        assert_eq!(view.client_id, account.client_id);
+        assert_eq!(view.available, account.available.0);
+        assert_eq!(view.held, account.held.0);
+        assert_eq!(view.total, account.total.0);
+        assert_eq!(view.locked, account.locked);
     }
 
     #[test]
-    fn test_zero_amount_deposit() {
-        let mut account = ClientAccount::new(1);
-        let tx = Transaction {
-            transaction_type: TransactionType::Deposit,
-            amount: Some(Decimal::from_f64(0.0)),
-            tx: 1,
-            client: 1,
-        };
-        account.settle_transaction(tx);
-
-        // Zero deposit is valid, recorded in ledger
-        assert_balances(&account, 0.0, 0.0, 0.0);
-        assert!(account.ledger.contains_key(&1));
+    fn test_as_view_tracks_account_states() {
+        let mut account = ClientAccount::new(7);
+        assert_view_matches(&account);
+
+        account.settle_transaction(make_deposit(1, 10.5)).unwrap();
+        account.settle_transaction(make_deposit(2, 0.0001)).unwrap();
+        assert_view_matches(&account);
+        assert_eq!(account.as_view().available, 105001);
+
+        account.adjudicate_claim(make_dispute(1)).unwrap();
+        assert_view_matches(&account);
+        assert_eq!(account.as_view().held, 105000);
+
+        account.adjudicate_claim(make_chargeback(1)).unwrap();
+        assert_view_matches(&account);
+        assert!(account.as_view().locked);
     }
 
     #[test]
-    fn test_zero_amount_deposit_can_be_disputed() {
+    fn test_as_view_negative_balances() {
         let mut account = ClientAccount::new(1);
-        let tx = Transaction {
-            transaction_type: TransactionType::Deposit,
-            amount: Some(Decimal::from_f64(0.0)),
-            tx: 1,
-            client: 1,
-        };
-        account.settle_transaction(tx);
-        account.adjudicate_claim(make_dispute(1));
+        account.settle_transaction(make_deposit(1, 10.0)).unwrap();
+        account.settle_transaction(make_withdrawal(2, 8.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
 
-        // Zero held
-        assert_balances(&account, 0.0, 0.0, 0.0);
-        assert!(account.disputes.get(&1).is_some());
+        assert_view_matches(&account);
+        assert_eq!(
+            account.as_view(),
+            AccountView {
+                client_id: 1,
+                available: -80000,
+                held: 100000,
+                total: 20000,
+                locked: false,
+            }
+        );
     }
 
+    // ========== Ledger Tx ID Reuse Tests ==========
+
     #[test]
-    fn test_boundary_tx_id_zero() {
+    fn test_reused_tx_id_does_not_overwrite_disputed_deposit() {
         let mut account = ClientAccount::new(1);
-        let tx = Transaction {
-            transaction_type: TransactionType::Deposit,
-            amount: Some(Decimal::from_f64(100.0)),
-            tx: 0, // Minimum tx ID
-            client: 1,
-        };
-        account.settle_transaction(tx);
+        account.settle_transaction(make_deposit(1, 10.0)).unwrap();
+        account.adjudicate_claim(make_dispute(1)).unwrap();
 
-        assert_balances(&account, 100.0, 0.0, 100.0);
-        assert!(account.ledger.contains_key(&0));
+        assert_eq!(
+            account.settle_transaction(make_withdrawal(1, 0.0)),
+            Err(RejectionReason::DuplicateTransaction)
+        );
+        assert_eq!(
+            account.settle_transaction(make_deposit(1, 5.0)),
+            Err(RejectionReason::DuplicateTransaction)
+        );
+
+        account.adjudicate_claim(make_resolve(1)).unwrap();
+        assert_balances(&account, 10.0, 0.0, 10.0);
     }
+}
 
-    #[test]
-    fn test_boundary_tx_id_max() {
-        let mut account = ClientAccount::new(1);
-        let tx = Transaction {
-            transaction_type: TransactionType::Deposit,
-            amount: Some(Decimal::from_f64(100.0)),
-            tx: u32::MAX, // Maximum tx ID
-            client: 1,
-        };
-        account.settle_transaction(tx);
+#[cfg(test)]
+mod invariant_tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// A randomly generated account operation. Tx IDs come from a small range so
+    /// that disputes regularly hit real deposits, withdrawals, and duplicates.
+    #[derive(Debug, Clone, Copy)]
+    enum Op {
+        Deposit(TransactionId, i64),
+        Withdrawal(TransactionId, i64),
+        Dispute(TransactionId),
+        PartialDispute(TransactionId, i64),
+        Resolve(TransactionId),
+        Chargeback(TransactionId),
+    }
 
-        assert_balances(&account, 100.0, 0.0, 100.0);
-        assert!(account.ledger.contains_key(&u32::MAX));
+    impl Op {
+        fn to_transaction(self) -> Transaction {
+            match self {
+                Op::Deposit(tx, raw) => Transaction::deposit(1, tx, Decimal(raw)),
+                Op::Withdrawal(tx, raw) => Transaction::withdrawal(1, tx, Decimal(raw)),
+                Op::Dispute(tx) => Transaction::dispute(1, tx),
+                Op::PartialDispute(tx, raw) => Transaction {
+                    amount: Some(Decimal(raw)),
+                    ..Transaction::dispute(1, tx)
+                },
+                Op::Resolve(tx) => Transaction::resolve(1, tx),
+                Op::Chargeback(tx) => Transaction::chargeback(1, tx),
+            }
+        }
     }
 
-    #[test]
-    fn test_boundary_client_id_zero() {
-        let account = ClientAccount::new(0); // Minimum client ID
-        assert_eq!(account.client_id, 0);
+    fn op() -> impl Strategy<Value = Op> {
+        let tx = 0..16 as TransactionId;
+        let amount = -50_000i64..1_000_000;
+        prop_oneof![
+            (tx.clone(), amount.clone()).prop_map(|(tx, raw)| Op::Deposit(tx, raw)),
+            (tx.clone(), amount.clone()).prop_map(|(tx, raw)| Op::Withdrawal(tx, raw)),
+            tx.clone().prop_map(Op::Dispute),
+            (tx.clone(), amount).prop_map(|(tx, raw)| Op::PartialDispute(tx, raw)),
+            tx.clone().prop_map(Op::Resolve),
+            tx.prop_map(Op::Chargeback),
+        ]
     }
 
-    #[test]
-    fn test_boundary_client_id_max() {
-        let account = ClientAccount::new(u16::MAX); // Maximum client ID
-        assert_eq!(account.client_id, u16::MAX);
+    fn policy() -> impl Strategy<Value = AccountPolicy> {
+        (
+            any::<bool>(),
+            proptest::option::of(0i64..2_000_000),
+            any::<bool>(),
+            any::<bool>(),
+            prop_oneof![
+                Just(NegativeTotalPolicy::Allow),
+                Just(NegativeTotalPolicy::ClampZero)
+            ],
+        )
+            .prop_map(
+                |(
+                    store_withdrawals,
+                    max_held,
+                    allow_negative,
+                    allow_negative_available,
+                    negative_total,
+                )| AccountPolicy {
+                    store_withdrawals_in_ledger: store_withdrawals,
+                    max_held: max_held.map(Decimal),
+                    allow_negative_deposits: allow_negative,
+                    allow_negative_available_on_dispute: allow_negative_available,
+                    negative_total,
+                    ..AccountPolicy::default()
+                },
+            )
+    }
+
+    fn apply(account: &mut ClientAccount, op: Op) {
+        let tx = op.to_transaction();
+        let _ = if tx.is_dispute_related() {
+            account.adjudicate_claim(tx)
+        } else {
+            account.settle_transaction(tx)
+        };
+    }
+
+    proptest! {
+        #[test]
+        fn prop_total_is_available_plus_held(
+            policy in policy(),
+            ops in proptest::collection::vec(op(), 0..64),
+        ) {
+            let mut account = ClientAccount::with_policy(1, policy);
+            for op in ops {
+                apply(&mut account, op);
+                prop_assert_eq!(account.total, account.available + account.held, "after {:?}", op);
+                prop_assert!(account.held >= Decimal::ZERO, "negative held after {:?}", op);
+                if let Some(cap) = policy.max_held {
+                    prop_assert!(account.held <= cap, "held above cap after {:?}", op);
+                }
+                if policy.negative_total == NegativeTotalPolicy::ClampZero {
+                    prop_assert!(account.total >= Decimal::ZERO, "negative total after {:?}", op);
+                }
+            }
+        }
+
+        #[test]
+        fn prop_held_matches_open_disputes(ops in proptest::collection::vec(op(), 0..64)) {
+            let mut account = ClientAccount::new(1);
+            for op in ops {
+                apply(&mut account, op);
+                let open_held = account
+                    .disputes()
+                    .values()
+                    .filter(|dispute| dispute.state == DisputeState::Disputed)
+                    .map(|dispute| dispute.held)
+                    .sum::<Decimal>();
+                prop_assert_eq!(account.held, open_held, "after {:?}", op);
+            }
+        }
     }
 }
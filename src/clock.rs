@@ -0,0 +1,75 @@
+//! Time source for time-based policies.
+//!
+//! The engine reads the current time through a [`Clock`] rather than calling
+//! [`SystemTime::now`] directly, so policies such as
+//! [`AccountPolicy::daily_withdrawal_limit`](crate::AccountPolicy::daily_withdrawal_limit)
+//! can be tested by swapping in a [`MockClock`].
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::SystemTime;
+
+/// Source of the current time, in Unix seconds.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> i64;
+}
+
+/// The real wall clock (the engine's default).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_secs() as i64,
+            Err(before_epoch) => -(before_epoch.duration().as_secs() as i64),
+        }
+    }
+}
+
+/// Manually driven clock for tests. Clones share the same time, so keep one
+/// to advance time after handing another to the engine.
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    now: Arc<AtomicI64>,
+}
+
+impl MockClock {
+    /// Create a clock stopped at `now`.
+    pub fn new(now: i64) -> Self {
+        Self {
+            now: Arc::new(AtomicI64::new(now)),
+        }
+    }
+
+    /// Move the clock to `now`.
+    pub fn set(&self, now: i64) {
+        self.now.store(now, Ordering::SeqCst);
+    }
+
+    /// Move the clock forward by `seconds`.
+    pub fn advance(&self, seconds: i64) {
+        self.now.fetch_add(seconds, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> i64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_clones_share_time() {
+        let clock = MockClock::new(100);
+        let shared = clock.clone();
+        clock.advance(50);
+        assert_eq!(shared.now(), 150);
+        shared.set(10);
+        assert_eq!(clock.now(), 10);
+    }
+}
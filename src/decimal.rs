@@ -2,10 +2,19 @@
 //!
 //! Avoids floating-point precision issues by storing values as integers
 //! with 4 decimal places of precision (i.e., value × 10,000).
+//!
+//! # Canonical format
+//!
+//! [`Decimal`] is serialized as a string in its canonical form: the [`Display`](fmt::Display)
+//! representation (trailing fractional zeros trimmed), with `.0` appended to
+//! whole numbers. E.g., `1.5`, `-0.0001`, `100.0`. Both are produced by the same
+//! code, so CSV output can never drift from `Display`.
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
+use std::iter::Sum;
 use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
 
 /// Fixed-point decimal with 4 decimal places.
 ///
@@ -18,9 +27,7 @@ impl Serialize for Decimal {
     where
         S: Serializer,
     {
-        // Serialize as f64 for CSV output
-        let value = self.0 as f64 / 10000.0;
-        serializer.serialize_f64(value)
+        serializer.serialize_str(&self.to_canonical_string())
     }
 }
 
@@ -29,33 +36,372 @@ impl<'de> Deserialize<'de> for Decimal {
     where
         D: Deserializer<'de>,
     {
-        let value = f64::deserialize(deserializer)?;
-        Ok(Decimal::from_f64(value))
+        // Ask for a string: self-describing CSV would otherwise infer a number
+        // and hand over an f64, bypassing the exact parser
+        deserializer.deserialize_str(DecimalVisitor)
     }
 }
 
-impl fmt::Display for Decimal {
+/// Parses numeric strings (the canonical serialized form) exactly. Numbers are
+/// still accepted from formats that hand them over regardless.
+struct DecimalVisitor;
+
+impl DecimalVisitor {
+    fn from_f64<E: serde::de::Error>(value: f64) -> Result<Decimal, E> {
+        Decimal::checked_from_f64(value)
+            .ok_or_else(|| E::custom(format!("amount out of range for Decimal: {}", value)))
+    }
+}
+
+impl serde::de::Visitor<'_> for DecimalVisitor {
+    type Value = Decimal;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a decimal number")
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<Decimal, E> {
+        Self::from_f64(value)
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Decimal, E> {
+        Self::from_f64(value as f64)
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Decimal, E> {
+        Self::from_f64(value as f64)
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Decimal, E> {
+        match value.parse::<Decimal>() {
+            Ok(decimal) => Ok(decimal),
+            // Fall back to f64 for any form the exact parser doesn't take
+            Err(exact_err) => match value.parse::<f64>() {
+                Ok(parsed) => Self::from_f64(parsed),
+                Err(_) => Err(E::custom(exact_err)),
+            },
+        }
+    }
+}
+
+/// Deserialize an optional amount, treating a missing, empty, or blank field as
+/// `None` so the row reaches the engine's missing-amount handling instead of
+/// failing to parse. CSV readers only map empty fields to `None` by themselves,
+/// which leaves whitespace-only fields to fail unless the reader trims.
+pub(crate) fn deserialize_optional_amount<'de, D>(
+    deserializer: D,
+) -> Result<Option<Decimal>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_option(OptionalAmountVisitor)
+}
+
+struct OptionalAmountVisitor;
+
+impl<'de> serde::de::Visitor<'de> for OptionalAmountVisitor {
+    type Value = Option<Decimal>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a decimal number or nothing")
+    }
+
+    fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        if value.trim().is_empty() {
+            return Ok(None);
+        }
+        DecimalVisitor.visit_str(value).map(Some)
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        DecimalVisitor.visit_f64(value).map(Some)
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        DecimalVisitor.visit_i64(value).map(Some)
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        DecimalVisitor.visit_u64(value).map(Some)
+    }
+}
+
+/// Error returned when parsing a [`Decimal`] from a string fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDecimalError {
+    input: String,
+    reason: &'static str,
+}
+
+impl fmt::Display for ParseDecimalError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let abs = self.0.abs();
-        let whole = abs / 10000;
-        let frac = abs % 10000;
+        write!(f, "invalid decimal {:?}: {}", self.input, self.reason)
+    }
+}
+
+impl std::error::Error for ParseDecimalError {}
 
-        if self.0 < 0 {
-            write!(f, "-")?;
+/// Parses plain decimal notation exactly, without going through `f64`.
+///
+/// Accepts an optional leading `-` or `+`, commas as thousands separators in the
+/// whole part (e.g., `1,234.56`, which must be quoted in CSV), and scientific
+/// notation (e.g., `1e-4`, `5E3`), applied by shifting the decimal point before
+/// scaling. Digits beyond the fourth decimal place are rounded, with ties
+/// rounded away from zero.
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |reason| ParseDecimalError {
+            input: s.to_string(),
+            reason,
+        };
+
+        let (negative, unsigned) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+            None => (unsigned, None),
+        };
+        let (whole, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if whole.is_empty() && frac.is_empty() {
+            return Err(err("no digits"));
         }
+        if !frac.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(err("invalid fractional part"));
+        }
+        let whole = strip_thousands_separators(whole).ok_or_else(|| err("invalid whole part"))?;
+        let (whole, frac) = match exponent {
+            Some(exponent) => {
+                let exponent = exponent
+                    .parse::<i32>()
+                    .map_err(|_| err("invalid exponent"))?;
+                if exponent.unsigned_abs() > MAX_EXPONENT {
+                    return Err(err("exponent out of range"));
+                }
+                shift_point(&whole, frac, exponent)
+            }
+            None => (whole, frac.to_string()),
+        };
 
-        if frac == 0 {
-            write!(f, "{}", whole)
-        } else {
-            // Remove trailing zeros from fraction
-            let mut frac_str = format!("{:04}", frac);
-            frac_str = frac_str.trim_end_matches('0').to_string();
-            write!(f, "{}.{}", whole, frac_str)
+        let mut raw: i128 = 0;
+        for digit in whole
+            .bytes()
+            .chain(frac.bytes().chain("0000".bytes()).take(4))
+        {
+            raw = raw
+                .checked_mul(10)
+                .and_then(|r| r.checked_add((digit - b'0') as i128))
+                .ok_or_else(|| err("out of range"))?;
+        }
+        // Round half away from zero on the first dropped digit
+        if frac.as_bytes().get(4).is_some_and(|&digit| digit >= b'5') {
+            raw += 1;
+        }
+        if negative {
+            raw = -raw;
+        }
+        i64::try_from(raw)
+            .map(Self)
+            .map_err(|_| err("out of range"))
+    }
+}
+
+/// Error returned by the strict `TryFrom<&str>` parser for [`Decimal`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrictDecimalError {
+    /// The input was empty.
+    Empty,
+    /// Only a sign and/or decimal point, without any digits.
+    NoDigits,
+    /// A second `.` at this byte offset.
+    MultipleDecimalPoints { position: usize },
+    /// Something other than a digit, a leading `-`, or a single `.`.
+    InvalidCharacter { character: char, position: usize },
+    /// Well-formed, but too large for a [`Decimal`].
+    OutOfRange,
+}
+
+impl fmt::Display for StrictDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty amount"),
+            Self::NoDigits => write!(f, "amount has no digits"),
+            Self::MultipleDecimalPoints { position } => {
+                write!(f, "second decimal point at position {}", position)
+            }
+            Self::InvalidCharacter {
+                character,
+                position,
+            } => write!(
+                f,
+                "invalid character {:?} at position {}",
+                character, position
+            ),
+            Self::OutOfRange => write!(f, "amount out of range"),
+        }
+    }
+}
+
+impl std::error::Error for StrictDecimalError {}
+
+/// Strict parser for amounts typed by hand (e.g., CLI arguments).
+///
+/// Only plain notation is accepted: an optional leading `-`, digits, and at
+/// most one `.`. Unlike [`FromStr`] (and so the CSV path), thousands
+/// separators, scientific notation, and a leading `+` are rejected, each with
+/// a [`StrictDecimalError`] pointing at the offending character. Digits beyond
+/// the fourth decimal place are rounded as by [`FromStr`].
+impl TryFrom<&str> for Decimal {
+    type Error = StrictDecimalError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        if s.is_empty() {
+            return Err(StrictDecimalError::Empty);
+        }
+        let mut point = None;
+        let mut digits = false;
+        for (position, character) in s.char_indices() {
+            match character {
+                '0'..='9' => digits = true,
+                '-' if position == 0 => {}
+                '.' if point.is_none() => point = Some(position),
+                '.' => return Err(StrictDecimalError::MultipleDecimalPoints { position }),
+                _ => {
+                    return Err(StrictDecimalError::InvalidCharacter {
+                        character,
+                        position,
+                    });
+                }
+            }
+        }
+        if !digits {
+            return Err(StrictDecimalError::NoDigits);
+        }
+        // Plain notation can only fail the lenient parser by overflowing
+        s.parse().map_err(|_| StrictDecimalError::OutOfRange)
+    }
+}
+
+/// Largest exponent magnitude accepted in scientific notation. Anything beyond
+/// this is out of range (or rounds to zero) for a 4-decimal `i64`, whatever the mantissa.
+const MAX_EXPONENT: u32 = 64;
+
+/// Move the decimal point of `whole.frac` right by `exponent` places (left if
+/// negative), returning the new whole and fractional digits.
+fn shift_point(whole: &str, frac: &str, exponent: i32) -> (String, String) {
+    let digits = format!("{}{}", whole, frac);
+    let point = whole.len() as i64 + exponent as i64;
+    if point <= 0 {
+        (String::new(), "0".repeat(-point as usize) + &digits)
+    } else if point as usize >= digits.len() {
+        let zeros = "0".repeat(point as usize - digits.len());
+        (digits + &zeros, String::new())
+    } else {
+        let (whole, frac) = digits.split_at(point as usize);
+        (whole.to_string(), frac.to_string())
+    }
+}
+
+/// Remove commas from `whole` if they are well-placed thousands separators
+/// (groups of three after a leading group of one to three digits).
+fn strip_thousands_separators(whole: &str) -> Option<String> {
+    let mut groups = whole.split(',');
+    let first = groups.next()?;
+    let grouped = whole.contains(',');
+    if grouped && !(1..=3).contains(&first.len()) {
+        return None;
+    }
+    let mut digits = first.to_string();
+    for group in groups {
+        if group.len() != 3 {
+            return None;
+        }
+        digits.push_str(group);
+    }
+    digits.bytes().all(|b| b.is_ascii_digit()).then_some(digits)
+}
+
+/// Whether `s`, in any form [`FromStr`] accepts, has nonzero digits beyond the
+/// fourth decimal place, which parsing rounds away. Malformed input is left
+/// for the parser to report.
+pub(crate) fn exceeds_precision(s: &str) -> bool {
+    let unsigned = s.trim_start_matches(['-', '+']);
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (whole, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let frac = match exponent.map(str::parse::<i32>) {
+        None => frac.to_string(),
+        Some(Ok(exponent)) if exponent.unsigned_abs() <= MAX_EXPONENT => {
+            shift_point(&whole.replace(',', ""), frac, exponent).1
         }
+        Some(_) => return false,
+    };
+    frac.get(4..)
+        .is_some_and(|dropped| dropped.bytes().any(|b| b != b'0'))
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // abs() would overflow on i64::MIN
+        fmt_scaled(self.0 < 0, self.0.unsigned_abs() as u128, f)
+    }
+}
+
+/// Write `abs` (in ten-thousandths) with trailing fractional zeros trimmed.
+fn fmt_scaled(negative: bool, abs: u128, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let whole = abs / 10000;
+    let frac = abs % 10000;
+
+    if negative {
+        write!(f, "-")?;
+    }
+
+    if frac == 0 {
+        write!(f, "{}", whole)
+    } else {
+        // Remove trailing zeros from fraction
+        let mut frac_str = format!("{:04}", frac);
+        frac_str = frac_str.trim_end_matches('0').to_string();
+        write!(f, "{}.{}", whole, frac_str)
     }
 }
 
 impl Decimal {
+    /// Zero, the same as `Decimal::default()`.
+    pub const ZERO: Decimal = Decimal(0);
+    /// Largest representable value, 922337203685477.5807.
+    pub const MAX: Decimal = Decimal(i64::MAX);
+    /// Smallest representable value, -922337203685477.5808.
+    pub const MIN: Decimal = Decimal(i64::MIN);
+
+    /// Canonical string form used for serialization: `Display`, plus `.0` for whole numbers.
+    ///
+    /// E.g., `Decimal(15000)` => "1.5", `Decimal(10000)` => "1.0"
+    pub fn to_canonical_string(&self) -> String {
+        if self.0 % 10000 == 0 {
+            format!("{}.0", self)
+        } else {
+            self.to_string()
+        }
+    }
+
     /// Create from raw internal representation (value in ten-thousandths).
     ///
     /// E.g., `Decimal::new(15000)` represents 1.5
@@ -63,11 +409,176 @@ impl Decimal {
         Self(value)
     }
 
+    /// Create from a whole number.
+    ///
+    /// E.g., `Decimal::from_int(2)` => Decimal(20000)
+    ///
+    /// Like [`Decimal::from_f64`], values too large to scale by 10,000 saturate
+    /// to [`Decimal::MAX`]/[`Decimal::MIN`].
+    pub fn from_int(value: i64) -> Self {
+        Self(value.saturating_mul(10000))
+    }
+
     /// Create from a float.
     /// E.g., from_f64(1.5) => Decimal(15000)
+    ///
+    /// Out-of-range values saturate to `i64::MIN`/`i64::MAX` (and NaN becomes 0);
+    /// use [`Decimal::checked_from_f64`] when the input is untrusted.
     pub fn from_f64(value: f64) -> Self {
         Self((value * 10000.0).round() as i64)
     }
+
+    /// Create from a float, returning `None` if the value is NaN, infinite, or
+    /// too large to be represented once scaled by 10,000.
+    ///
+    /// E.g., checked_from_f64(1.5) => Some(Decimal(15000)), checked_from_f64(1e20) => None
+    pub fn checked_from_f64(value: f64) -> Option<Self> {
+        let scaled = (value * 10000.0).round();
+        // i64::MAX as f64 rounds up to 2^63, so the upper bound must be exclusive.
+        if scaled.is_finite() && scaled >= i64::MIN as f64 && scaled < i64::MAX as f64 {
+            Some(Self(scaled as i64))
+        } else {
+            None
+        }
+    }
+
+    /// Compute `percent`% of this amount, i.e. `self * percent / 100`.
+    ///
+    /// The exact result is rounded to the nearest ten-thousandth, with ties
+    /// rounded away from zero, and saturates at `i64::MIN`/`i64::MAX`.
+    ///
+    /// E.g., `Decimal::from_int(200).percent_of(Decimal::from_f64(2.5))` => 5.0
+    pub fn percent_of(self, percent: Decimal) -> Decimal {
+        // percent.0 carries a scale of 10000; divide that back out along with the 100
+        self.mul_ratio(percent.0, 100 * 10000)
+    }
+
+    /// Compute `self * numerator / denominator`, e.g. one share of a split.
+    ///
+    /// The product is taken in 128 bits, so it cannot overflow; the exact
+    /// result is rounded to the nearest ten-thousandth, with ties rounded away
+    /// from zero, and saturates at `i64::MIN`/`i64::MAX`.
+    ///
+    /// E.g., `Decimal::from_int(100).mul_ratio(1, 3)` => 33.3333
+    ///
+    /// # Panics
+    ///
+    /// Panics if `denominator` is zero.
+    pub fn mul_ratio(self, numerator: i64, denominator: i64) -> Decimal {
+        assert!(denominator != 0, "mul_ratio denominator must be non-zero");
+        let mut product = self.0 as i128 * numerator as i128;
+        let mut denominator = denominator as i128;
+        // div_round expects a positive denominator
+        if denominator < 0 {
+            (product, denominator) = (-product, -denominator);
+        }
+        Self(saturate(div_round(product, denominator)))
+    }
+
+    /// Create from an integer count of minor units with `decimals` places,
+    /// e.g. cents with `decimals == 2`.
+    ///
+    /// Units finer than the internal four places are rounded to the nearest
+    /// ten-thousandth, with ties rounded away from zero. Saturates at `i64::MIN`/`i64::MAX`.
+    ///
+    /// E.g., `Decimal::from_minor_units(123, 2)` => 1.23
+    pub fn from_minor_units(units: i64, decimals: u32) -> Self {
+        Self(saturate(rescale(units as i128, decimals, SCALE_DECIMALS)))
+    }
+
+    /// Convert to an integer count of minor units with `decimals` places.
+    ///
+    /// When `decimals < 4` the value is rounded to the nearest unit, with ties
+    /// rounded away from zero. Saturates at `i64::MIN`/`i64::MAX`.
+    ///
+    /// E.g., `Decimal::new(12345).to_minor_units(2)` => 123
+    pub fn to_minor_units(self, decimals: u32) -> i64 {
+        saturate(rescale(self.0 as i128, SCALE_DECIMALS, decimals))
+    }
+
+    /// Round to `decimals` decimal places, half-up (ties away from zero).
+    ///
+    /// The result keeps the internal four-place representation. Values with
+    /// `decimals >= 4` are returned unchanged. Saturates at `i64::MIN`/`i64::MAX`.
+    ///
+    /// E.g., `Decimal::new(12345).round_to(2)` => 1.23 (stored as 12300)
+    pub fn round_to(self, decimals: u32) -> Decimal {
+        if decimals >= SCALE_DECIMALS {
+            return self;
+        }
+        let rounded = rescale(self.0 as i128, SCALE_DECIMALS, decimals);
+        Self(saturate(rescale(rounded, decimals, SCALE_DECIMALS)))
+    }
+
+    /// Mean of `values`, or `None` if there are none.
+    ///
+    /// The sum is taken in 128 bits, so it cannot overflow; the mean is rounded
+    /// to the nearest ten-thousandth, with ties rounded away from zero.
+    ///
+    /// E.g., `Decimal::average(&[Decimal::from_int(1), Decimal::from_int(2)])` => `Some(1.5)`
+    pub fn average(values: &[Decimal]) -> Option<Decimal> {
+        if values.is_empty() {
+            return None;
+        }
+        let sum: i128 = values.iter().map(|value| value.0 as i128).sum();
+        Some(Self(saturate(div_round(sum, values.len() as i128))))
+    }
+
+    /// Format with exactly `decimals` fractional digits, for aligned display.
+    ///
+    /// Unlike `Display`, which trims trailing zeros, this pads with zeros, or
+    /// rounds as [`round_to`](Self::round_to) when `decimals < 4`.
+    ///
+    /// E.g., `Decimal::from_f64(1.5).format_fixed(2)` => `"1.50"`
+    pub fn format_fixed(self, decimals: u32) -> String {
+        let rounded = self.round_to(decimals);
+        let sign = if rounded.0 < 0 { "-" } else { "" };
+        let abs = rounded.0.unsigned_abs(); // abs() would overflow on i64::MIN
+        let whole = abs / 10000;
+        if decimals == 0 {
+            return format!("{}{}", sign, whole);
+        }
+        let frac = format!("{:04}", abs % 10000);
+        let shown = decimals.min(SCALE_DECIMALS);
+        let padding = "0".repeat((decimals - shown) as usize);
+        format!("{}{}.{}{}", sign, whole, &frac[..shown as usize], padding)
+    }
+}
+
+/// Number of decimal places in the internal representation.
+const SCALE_DECIMALS: u32 = 4;
+
+/// Convert `value` with `from` decimal places to `to` decimal places, rounding
+/// half away from zero. Scaling factors beyond `i128` saturate (or round to zero).
+fn rescale(value: i128, from: u32, to: u32) -> i128 {
+    if to >= from {
+        match 10i128.checked_pow(to - from) {
+            Some(factor) => value.saturating_mul(factor),
+            None if value == 0 => 0,
+            None => i128::MAX * value.signum(),
+        }
+    } else {
+        match 10i128.checked_pow(from - to) {
+            Some(factor) => div_round(value, factor),
+            None => 0,
+        }
+    }
+}
+
+/// `numerator / denominator` rounded to the nearest integer, ties away from zero.
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    // Compare |r| >= d - |r| rather than 2|r| >= d to avoid overflow
+    if remainder.abs() >= denominator - remainder.abs() {
+        quotient + numerator.signum()
+    } else {
+        quotient
+    }
+}
+
+fn saturate(value: i128) -> i64 {
+    value.clamp(i64::MIN as i128, i64::MAX as i128) as i64
 }
 
 impl AddAssign for Decimal {
@@ -98,10 +609,99 @@ impl Sub for Decimal {
     }
 }
 
+/// Saturating sum: totals beyond the representable range clamp to
+/// [`Decimal::MAX`]/[`Decimal::MIN`] instead of overflowing.
+impl Sum for Decimal {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        // Accumulate in i128 so intermediate overflow can't mask a total that fits
+        Self(saturate(iter.map(|d| d.0 as i128).sum()))
+    }
+}
+
+impl<'a> Sum<&'a Decimal> for Decimal {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+/// [`Decimal`] widened to 128 bits, for aggregates that can exceed
+/// [`Decimal::MAX`], e.g. summing `total` across millions of accounts.
+///
+/// Same four-place scale and `Display` format as [`Decimal`]. Unlike the
+/// saturating `Sum` for [`Decimal`], summing into a `DecimalWide` is exact.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DecimalWide(pub i128);
+
+impl DecimalWide {
+    pub const ZERO: DecimalWide = DecimalWide(0);
+
+    /// Narrow back to a [`Decimal`], or `None` if out of its range.
+    pub fn to_decimal(self) -> Option<Decimal> {
+        i64::try_from(self.0).ok().map(Decimal)
+    }
+}
+
+impl From<Decimal> for DecimalWide {
+    fn from(value: Decimal) -> Self {
+        Self(value.0 as i128)
+    }
+}
+
+impl fmt::Display for DecimalWide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_scaled(self.0 < 0, self.0.unsigned_abs(), f)
+    }
+}
+
+/// Exact sum; `i128` holds the sum of more `Decimal`s than could ever be stored.
+impl Sum<Decimal> for DecimalWide {
+    fn sum<I: Iterator<Item = Decimal>>(iter: I) -> Self {
+        Self(iter.map(|d| d.0 as i128).sum())
+    }
+}
+
+impl<'a> Sum<&'a Decimal> for DecimalWide {
+    fn sum<I: Iterator<Item = &'a Decimal>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+impl AddAssign<Decimal> for DecimalWide {
+    fn add_assign(&mut self, other: Decimal) {
+        self.0 += other.0 as i128;
+    }
+}
+
+impl Add for DecimalWide {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl Sub for DecimalWide {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 - other.0)
+    }
+}
+
 #[cfg(test)]
 mod display_tests {
     use super::*;
 
+    #[test]
+    fn test_display_wide() {
+        assert_eq!(DecimalWide(15000).to_string(), "1.5");
+        assert_eq!(DecimalWide(-1).to_string(), "-0.0001");
+        let doubled = DecimalWide::from(Decimal::MAX).0 * 2;
+        assert_eq!(DecimalWide(doubled).to_string(), "1844674407370955.1614");
+        assert_eq!(DecimalWide(doubled).to_decimal(), None);
+        assert_eq!(DecimalWide(15000).to_decimal(), Some(Decimal(15000)));
+    }
+
     #[test]
     fn test_display_whole_numbers() {
         assert_eq!(Decimal(0).to_string(), "0");
@@ -134,6 +734,7 @@ mod display_tests {
         assert_eq!(Decimal(-12345).to_string(), "-1.2345");
         assert_eq!(Decimal(-5000).to_string(), "-0.5");
         assert_eq!(Decimal(-1).to_string(), "-0.0001");
+        assert_eq!(Decimal(i64::MIN).to_string(), "-922337203685477.5808");
     }
 
     #[test]
@@ -141,14 +742,108 @@ mod display_tests {
         assert_eq!(Decimal(99999999990000).to_string(), "9999999999");
         assert_eq!(Decimal(123456789012345).to_string(), "12345678901.2345");
     }
+
+    #[test]
+    fn test_format_fixed() {
+        let amount = Decimal::from_f64(1.5);
+        assert_eq!(amount.format_fixed(4), "1.5000");
+        assert_eq!(amount.format_fixed(2), "1.50");
+        assert_eq!(amount.format_fixed(0), "2"); // Rounded
+        assert_eq!(amount.format_fixed(6), "1.500000");
+        assert_eq!(Decimal(12345).format_fixed(3), "1.235");
+        assert_eq!(Decimal(-12345).format_fixed(1), "-1.2");
+        assert_eq!(Decimal(-40).format_fixed(2), "0.00"); // No negative zero
+    }
+
+    #[test]
+    fn test_average() {
+        let values = [Decimal::from_f64(1.0), Decimal::from_f64(2.0)];
+        assert_eq!(Decimal::average(&values), Some(Decimal::from_f64(1.5)));
+        assert_eq!(Decimal::average(&[]), None);
+        // Rounded half away from zero
+        assert_eq!(
+            Decimal::average(&[Decimal(1), Decimal(2), Decimal(2), Decimal(2)]),
+            Some(Decimal(2))
+        );
+        assert_eq!(
+            Decimal::average(&[Decimal(-1), Decimal(-2)]),
+            Some(Decimal(-2))
+        );
+        // No overflow in the sum
+        assert_eq!(
+            Decimal::average(&[Decimal::MAX, Decimal::MAX]),
+            Some(Decimal::MAX)
+        );
+    }
+}
+
+#[cfg(test)]
+mod serialize_tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Serialize a single decimal as a headerless CSV record, without the newline.
+    fn csv_serialize(d: Decimal) -> String {
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(vec![]);
+        writer.serialize([d]).unwrap();
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        output.trim_end_matches('\n').to_string()
+    }
+
+    /// `Display` with the `.0` normalization for whole numbers.
+    fn normalized_display(d: Decimal) -> String {
+        let display = d.to_string();
+        if display.contains('.') {
+            display
+        } else {
+            format!("{}.0", display)
+        }
+    }
+
+    #[test]
+    fn test_canonical_string() {
+        assert_eq!(Decimal(0).to_canonical_string(), "0.0");
+        assert_eq!(Decimal(10000).to_canonical_string(), "1.0");
+        assert_eq!(Decimal(15000).to_canonical_string(), "1.5");
+        assert_eq!(Decimal(-1).to_canonical_string(), "-0.0001");
+        assert_eq!(Decimal(-10000).to_canonical_string(), "-1.0");
+    }
+
+    #[test]
+    fn test_csv_serialize_extremes() {
+        // These diverged under f64 formatting (precision loss / exponent notation)
+        assert_eq!(csv_serialize(Decimal(i64::MAX)), "922337203685477.5807");
+        assert_eq!(csv_serialize(Decimal(i64::MIN)), "-922337203685477.5808");
+        assert_eq!(
+            csv_serialize(Decimal(100000000000000000)),
+            "10000000000000.0"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_csv_serialize_matches_display(raw in any::<i64>()) {
+            let d = Decimal(raw);
+            prop_assert_eq!(csv_serialize(d), normalized_display(d));
+        }
+
+        #[test]
+        fn prop_csv_serialize_matches_display_small(raw in -100_000_000i64..100_000_000) {
+            let d = Decimal(raw);
+            prop_assert_eq!(csv_serialize(d), normalized_display(d));
+        }
+    }
 }
 
 #[cfg(test)]
 mod deserialize_tests {
     use super::*;
 
+    /// Deserialize `s` from a JSON string, as [`Decimal`] serializes itself.
     fn deserialize(s: &str) -> Decimal {
-        serde_json::from_str(s).unwrap()
+        serde_json::from_str(&format!("\"{s}\"")).unwrap()
     }
 
     #[test]
@@ -205,7 +900,7 @@ mod deserialize_tests {
 
     #[test]
     fn test_deserialize_five_plus_decimal_places_rounded() {
-        // 5+ decimal places are rounded to 4 (via f64 * 10000 then round)
+        // 5+ decimal places are rounded to 4, ties away from zero
         assert_eq!(deserialize("1.23456"), Decimal(12346)); // rounds up
         assert_eq!(deserialize("1.23454"), Decimal(12345)); // rounds down
         assert_eq!(deserialize("1.234567890"), Decimal(12346));
@@ -213,12 +908,296 @@ mod deserialize_tests {
         assert_eq!(deserialize("0.00005"), Decimal(1)); // rounds up to 0.0001
         assert_eq!(deserialize("0.00004"), Decimal(0)); // rounds down to 0
     }
+
+    #[test]
+    fn test_deserialize_out_of_range_rejected() {
+        assert!(serde_json::from_str::<Decimal>("\"1e20\"").is_err());
+        assert!(serde_json::from_str::<Decimal>("\"-1e20\"").is_err());
+    }
+
+    #[test]
+    fn test_deserialize_csv_field_parsed_exactly() {
+        // An unquoted CSV field, which f64 would round to ...0992
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader("900719925474.0993\n".as_bytes());
+        let (value,): (Decimal,) = reader.deserialize().next().unwrap().unwrap();
+        assert_eq!(value.to_string(), "900719925474.0993");
+    }
+}
+
+#[cfg(test)]
+mod from_str_tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str_exact() {
+        assert_eq!("1.5".parse(), Ok(Decimal(15000)));
+        assert_eq!("-0.0001".parse(), Ok(Decimal(-1)));
+        assert_eq!("100".parse(), Ok(Decimal(1000000)));
+        assert_eq!(".5".parse(), Ok(Decimal(5000)));
+        assert_eq!("2.".parse(), Ok(Decimal(20000)));
+        // Exact where f64 would not be: the raw value is 2^53 + 1
+        assert_eq!("900719925474.0993".parse(), Ok(Decimal(9007199254740993)));
+    }
+
+    #[test]
+    fn test_from_str_leading_plus() {
+        assert_eq!("+100.0".parse(), Ok(Decimal(1000000)));
+        assert_eq!("+0.0001".parse(), Ok(Decimal(1)));
+        assert_eq!("+1,000".parse(), Ok(Decimal(10000000)));
+        for input in ["++1", "+-1", "-+1", "+"] {
+            assert!(
+                input.parse::<Decimal>().is_err(),
+                "{:?} should be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_exceeds_precision() {
+        for input in ["1.234567", "-0.00001", "1.23451", "12345e-8", "1.5e-4"] {
+            assert!(
+                exceeds_precision(input),
+                "{:?} should lose precision",
+                input
+            );
+        }
+        for input in [
+            "1.2345",
+            "1.234500",
+            "100",
+            "1,000.5",
+            "1.23456e2",
+            "+0.0001",
+        ] {
+            assert!(!exceeds_precision(input), "{:?} should be exact", input);
+        }
+    }
+
+    #[test]
+    fn test_from_str_thousands_separators() {
+        assert_eq!("1,000.0000".parse(), Ok(Decimal(10000000)));
+        assert_eq!("1,234.56".parse(), Ok(Decimal(12345600)));
+        assert_eq!("-12,345,678".parse(), Ok(Decimal(-123456780000)));
+        assert_eq!("999".parse(), Ok(Decimal(9990000)));
+    }
+
+    #[test]
+    fn test_from_str_scientific_notation() {
+        assert_eq!("1e-4".parse(), Ok(Decimal(1)));
+        assert_eq!("5E3".parse(), Ok(Decimal(50000000)));
+        assert_eq!("1.25e2".parse(), Ok(Decimal(1250000)));
+        assert_eq!("-2.5E+1".parse(), Ok(Decimal(-250000)));
+        assert_eq!("12345e-8".parse(), Ok(Decimal(1))); // 0.00012345 rounds to 0.0001
+        assert_eq!("1.5e-4".parse(), Ok(Decimal(2))); // Tie rounds away from zero
+        assert_eq!("1e-9".parse(), Ok(Decimal(0)));
+    }
+
+    #[test]
+    fn test_from_str_scientific_notation_rejected() {
+        for input in ["1e20", "1e999", "1e-999", "1e", "e5", "1e2.5", "1ee2"] {
+            assert!(
+                input.parse::<Decimal>().is_err(),
+                "{:?} should be rejected",
+                input
+            );
+        }
+        let err = "1e999".parse::<Decimal>().unwrap_err();
+        assert_eq!(err.reason, "exponent out of range");
+        assert_eq!(
+            "1e20".parse::<Decimal>().unwrap_err().reason,
+            "out of range"
+        );
+    }
+
+    #[test]
+    fn test_from_str_misplaced_separators_rejected() {
+        for input in [
+            "1,00.0", "1000,000", ",100", "100,", "1,,000", "1.000,5", "1,0000",
+        ] {
+            assert!(
+                input.parse::<Decimal>().is_err(),
+                "{:?} should be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_str_rounds_extra_digits() {
+        assert_eq!("1.23456".parse(), Ok(Decimal(12346)));
+        assert_eq!("1.23454".parse(), Ok(Decimal(12345)));
+        assert_eq!("0.00005".parse(), Ok(Decimal(1)));
+        assert_eq!("-0.00005".parse(), Ok(Decimal(-1)));
+    }
+
+    #[test]
+    fn test_from_str_invalid() {
+        for input in [
+            "",
+            "-",
+            ".",
+            "abc",
+            "1.2.3",
+            "--1",
+            "1.-2",
+            "99999999999999999999",
+        ] {
+            assert!(
+                input.parse::<Decimal>().is_err(),
+                "{:?} should be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_from_str_accepts_plain_notation() {
+        assert_eq!(Decimal::try_from("1.5"), Ok(Decimal(15000)));
+        assert_eq!(Decimal::try_from("-0.0001"), Ok(Decimal(-1)));
+        assert_eq!(Decimal::try_from("100"), Ok(Decimal(1000000)));
+        assert_eq!(Decimal::try_from(".5"), Ok(Decimal(5000)));
+        assert_eq!(Decimal::try_from("1.23456"), Ok(Decimal(12346)));
+    }
+
+    #[test]
+    fn test_try_from_str_rejects_empty() {
+        assert_eq!(Decimal::try_from(""), Err(StrictDecimalError::Empty));
+        for input in ["-", ".", "-."] {
+            assert_eq!(
+                Decimal::try_from(input),
+                Err(StrictDecimalError::NoDigits),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_from_str_rejects_multiple_decimal_points() {
+        assert_eq!(
+            Decimal::try_from("1.2.3"),
+            Err(StrictDecimalError::MultipleDecimalPoints { position: 3 })
+        );
+        assert_eq!(
+            Decimal::try_from("..5"),
+            Err(StrictDecimalError::MultipleDecimalPoints { position: 1 })
+        );
+    }
+
+    #[test]
+    fn test_try_from_str_rejects_non_digits() {
+        let invalid = |character, position| StrictDecimalError::InvalidCharacter {
+            character,
+            position,
+        };
+        // Forms the lenient parser accepts are still rejected
+        assert_eq!(Decimal::try_from("1,000"), Err(invalid(',', 1)));
+        assert_eq!(Decimal::try_from("1e5"), Err(invalid('e', 1)));
+        assert_eq!(Decimal::try_from("+1"), Err(invalid('+', 0)));
+        assert_eq!(Decimal::try_from("--1"), Err(invalid('-', 1)));
+        assert_eq!(Decimal::try_from("1.5 "), Err(invalid(' ', 3)));
+        assert_eq!(Decimal::try_from("12abc"), Err(invalid('a', 2)));
+        assert_eq!(
+            Decimal::try_from("1.2x").unwrap_err().to_string(),
+            "invalid character 'x' at position 3"
+        );
+    }
+
+    #[test]
+    fn test_try_from_str_rejects_out_of_range() {
+        assert_eq!(
+            Decimal::try_from("99999999999999999999"),
+            Err(StrictDecimalError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_csv_quoted_amount_with_thousands_separator() {
+        let input = "amount\n\"1,000.0000\"\n1000.5\n";
+        let mut reader = csv::Reader::from_reader(input.as_bytes());
+        let amounts: Vec<Decimal> = reader
+            .deserialize::<(Decimal,)>()
+            .map(|row| row.unwrap().0)
+            .collect();
+
+        assert_eq!(amounts, vec![Decimal(10000000), Decimal(10005000)]);
+    }
+}
+
+#[cfg(test)]
+mod conversion_tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_from_f64_in_range() {
+        assert_eq!(Decimal::checked_from_f64(1.5), Some(Decimal(15000)));
+        assert_eq!(Decimal::checked_from_f64(-1.2345), Some(Decimal(-12345)));
+        assert_eq!(Decimal::checked_from_f64(0.0), Some(Decimal(0)));
+        assert_eq!(Decimal::checked_from_f64(1e14), Some(Decimal(1e18 as i64)));
+    }
+
+    #[test]
+    fn test_checked_from_f64_saturation_rejected() {
+        // Plain `as` casts would silently clamp these to i64::MAX / i64::MIN
+        assert_eq!(Decimal::checked_from_f64(1e20), None);
+        assert_eq!(Decimal::checked_from_f64(-1e20), None);
+        assert_eq!(Decimal::from_f64(1e20), Decimal(i64::MAX));
+        assert_eq!(Decimal::from_f64(-1e20), Decimal(i64::MIN));
+    }
+
+    #[test]
+    fn test_checked_from_f64_non_finite_rejected() {
+        assert_eq!(Decimal::checked_from_f64(f64::NAN), None);
+        assert_eq!(Decimal::checked_from_f64(f64::INFINITY), None);
+        assert_eq!(Decimal::checked_from_f64(f64::NEG_INFINITY), None);
+    }
 }
 
 #[cfg(test)]
 mod arithmetic_tests {
     use super::*;
 
+    #[test]
+    fn test_sum() {
+        let values = vec![Decimal(10000), Decimal(5000), Decimal(-2500), Decimal(1)];
+        assert_eq!(values.iter().sum::<Decimal>(), Decimal(12501));
+        assert_eq!(values.into_iter().sum::<Decimal>(), Decimal(12501));
+        assert_eq!(
+            std::iter::empty::<Decimal>().sum::<Decimal>(),
+            Decimal::ZERO
+        );
+    }
+
+    #[test]
+    fn test_sum_saturates() {
+        assert_eq!(
+            [Decimal::MAX, Decimal(1)].iter().sum::<Decimal>(),
+            Decimal::MAX
+        );
+        assert_eq!(
+            [Decimal::MIN, Decimal(-1)].iter().sum::<Decimal>(),
+            Decimal::MIN
+        );
+        // Intermediate overflow that cancels out still yields the exact total
+        assert_eq!(
+            [Decimal::MAX, Decimal::MAX, Decimal::MIN, Decimal::MIN]
+                .iter()
+                .sum::<Decimal>(),
+            Decimal(-2)
+        );
+    }
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(Decimal::ZERO, Decimal::default());
+        assert_eq!(Decimal::MAX.0, i64::MAX);
+        assert_eq!(Decimal::MIN.0, i64::MIN);
+        assert!(Decimal::MIN < Decimal::ZERO && Decimal::ZERO < Decimal::MAX);
+    }
+
     #[test]
     fn test_add() {
         assert_eq!(Decimal(10000) + Decimal(5000), Decimal(15000));
@@ -255,3 +1234,185 @@ mod arithmetic_tests {
         assert_eq!(d, Decimal(-3000));
     }
 }
+
+#[cfg(test)]
+mod percent_tests {
+    use super::*;
+
+    #[test]
+    fn test_from_int() {
+        assert_eq!(Decimal::from_int(200), Decimal(2_000_000));
+        assert_eq!(Decimal::from_int(-3), Decimal(-30000));
+        assert_eq!(Decimal::from_int(0), Decimal(0));
+    }
+
+    #[test]
+    fn test_from_int_saturates_at_range_boundary() {
+        let max_whole = i64::MAX / 10000;
+        assert_eq!(Decimal::from_int(max_whole), Decimal(max_whole * 10000));
+        assert_eq!(Decimal::from_int(max_whole + 1), Decimal::MAX);
+        assert_eq!(Decimal::from_int(-max_whole - 1), Decimal::MIN);
+        assert_eq!(Decimal::from_int(i64::MIN), Decimal::MIN);
+    }
+
+    #[test]
+    fn test_percent_of() {
+        assert_eq!(
+            Decimal::from_int(200).percent_of(Decimal::from_f64(2.5)),
+            Decimal::from_f64(5.0)
+        );
+        assert_eq!(
+            Decimal::from_int(50).percent_of(Decimal::from_int(100)),
+            Decimal::from_int(50)
+        );
+        assert_eq!(
+            Decimal::from_int(50).percent_of(Decimal::from_int(0)),
+            Decimal(0)
+        );
+        assert_eq!(
+            Decimal::from_int(-80).percent_of(Decimal::from_int(25)),
+            Decimal::from_int(-20)
+        );
+    }
+
+    #[test]
+    fn test_mul_ratio_splits_into_thirds() {
+        let third = Decimal::from_int(100).mul_ratio(1, 3);
+        assert_eq!(third, Decimal::from_f64(33.3333));
+        assert_eq!(third.to_string(), "33.3333");
+        // 66.66666... rounds up; the two shares still don't sum past the whole
+        let two_thirds = Decimal::from_int(100).mul_ratio(2, 3);
+        assert_eq!(two_thirds, Decimal::from_f64(66.6667));
+        assert_eq!(third + two_thirds, Decimal::from_int(100));
+        // Signs on either side of the ratio
+        assert_eq!(
+            Decimal::from_int(-100).mul_ratio(1, 3),
+            Decimal::from_f64(-33.3333)
+        );
+        assert_eq!(
+            Decimal::from_int(100).mul_ratio(2, -3),
+            Decimal::from_f64(-66.6667)
+        );
+        assert_eq!(Decimal::from_int(100).mul_ratio(-1, -3), third);
+    }
+
+    #[test]
+    fn test_mul_ratio_rounding_and_range() {
+        // 0.0001 / 2 is exactly halfway; ties round away from zero
+        assert_eq!(Decimal(1).mul_ratio(1, 2), Decimal(1));
+        assert_eq!(Decimal(-1).mul_ratio(1, 2), Decimal(-1));
+        assert_eq!(Decimal(1).mul_ratio(1, 3), Decimal(0));
+        // The intermediate product would overflow i64
+        assert_eq!(Decimal::MAX.mul_ratio(i64::MAX, i64::MAX), Decimal::MAX);
+        assert_eq!(Decimal::MAX.mul_ratio(2, 1), Decimal::MAX);
+        assert_eq!(Decimal::MIN.mul_ratio(2, 1), Decimal::MIN);
+    }
+
+    #[test]
+    fn test_percent_of_rounds_half_away_from_zero() {
+        // 0.0001 * 50% = 0.00005, exactly halfway between 0.0000 and 0.0001
+        assert_eq!(Decimal(1).percent_of(Decimal::from_int(50)), Decimal(1));
+        assert_eq!(Decimal(-1).percent_of(Decimal::from_int(50)), Decimal(-1));
+        // 0.0001 * 49% = 0.000049 rounds down
+        assert_eq!(Decimal(1).percent_of(Decimal::from_int(49)), Decimal(0));
+        // 1.2345 * 3.3333% = 0.04114958... rounds to 0.0411
+        assert_eq!(
+            Decimal(12345).percent_of(Decimal(33333)),
+            Decimal::from_f64(0.0411)
+        );
+    }
+
+    #[test]
+    fn test_percent_of_saturates() {
+        assert_eq!(
+            Decimal::MAX.percent_of(Decimal::from_int(1000)),
+            Decimal::MAX
+        );
+        assert_eq!(
+            Decimal::MIN.percent_of(Decimal::from_int(1000)),
+            Decimal::MIN
+        );
+    }
+}
+
+#[cfg(test)]
+mod minor_units_tests {
+    use super::*;
+
+    #[test]
+    fn test_to_minor_units_cents_round_trip() {
+        let d = Decimal(12345); // 1.2345
+        let cents = d.to_minor_units(2);
+        assert_eq!(cents, 123);
+        assert_eq!(Decimal::from_minor_units(cents, 2), Decimal(12300));
+    }
+
+    #[test]
+    fn test_to_minor_units_rounds_half_away_from_zero() {
+        assert_eq!(Decimal(12350).to_minor_units(2), 124);
+        assert_eq!(Decimal(12349).to_minor_units(2), 123);
+        assert_eq!(Decimal(-12350).to_minor_units(2), -124);
+        assert_eq!(Decimal(-12349).to_minor_units(2), -123);
+        assert_eq!(Decimal(5000).to_minor_units(0), 1);
+    }
+
+    #[test]
+    fn test_minor_units_same_or_finer_scale() {
+        assert_eq!(Decimal(12345).to_minor_units(4), 12345);
+        assert_eq!(Decimal::from_minor_units(12345, 4), Decimal(12345));
+        assert_eq!(Decimal(12345).to_minor_units(6), 1234500);
+        // 1.234567 rounds to 1.2346
+        assert_eq!(Decimal::from_minor_units(1234567, 6), Decimal(12346));
+        assert_eq!(Decimal::from_minor_units(-1234550, 6), Decimal(-12346));
+    }
+
+    #[test]
+    fn test_minor_units_saturate() {
+        assert_eq!(Decimal(i64::MAX).to_minor_units(8), i64::MAX);
+        assert_eq!(Decimal(i64::MIN).to_minor_units(8), i64::MIN);
+        assert_eq!(Decimal::from_minor_units(i64::MAX, 0), Decimal(i64::MAX));
+        assert_eq!(Decimal::from_minor_units(i64::MAX, 80), Decimal(0));
+        assert_eq!(Decimal(1).to_minor_units(80), i64::MAX);
+        assert_eq!(Decimal::from_minor_units(i64::MAX, 42), Decimal(0));
+    }
+}
+
+#[cfg(test)]
+mod round_to_tests {
+    use super::*;
+
+    #[test]
+    fn test_round_to_down() {
+        assert_eq!(Decimal(12345).round_to(2), Decimal(12300));
+        assert_eq!(Decimal(12349).round_to(2), Decimal(12300));
+        assert_eq!(Decimal(-12345).round_to(2), Decimal(-12300));
+    }
+
+    #[test]
+    fn test_round_to_up() {
+        assert_eq!(Decimal(12351).round_to(2), Decimal(12400));
+        assert_eq!(Decimal(19999).round_to(3), Decimal(20000));
+        assert_eq!(Decimal(-12351).round_to(2), Decimal(-12400));
+    }
+
+    #[test]
+    fn test_round_to_half_boundary() {
+        assert_eq!(Decimal(12350).round_to(2), Decimal(12400));
+        assert_eq!(Decimal(15000).round_to(0), Decimal(20000));
+        assert_eq!(Decimal(-12350).round_to(2), Decimal(-12400));
+        assert_eq!(Decimal(5).round_to(3), Decimal(10));
+    }
+
+    #[test]
+    fn test_round_to_no_op_scales() {
+        assert_eq!(Decimal(12345).round_to(4), Decimal(12345));
+        assert_eq!(Decimal(12345).round_to(10), Decimal(12345));
+        assert_eq!(Decimal(12300).round_to(2), Decimal(12300));
+    }
+
+    #[test]
+    fn test_round_to_saturates() {
+        assert_eq!(Decimal::MAX.round_to(0), Decimal::MAX);
+        assert_eq!(Decimal::MIN.round_to(0), Decimal::MIN);
+    }
+}
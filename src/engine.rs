@@ -3,21 +3,154 @@
 //! [`PaymentsEngine`] receives transactions via an async channel and maintains
 //! the state of all client accounts.
 
-use std::collections::{BTreeMap, HashSet};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Instant;
 
+use futures::{Stream, StreamExt};
 use tokio::{
-    sync::mpsc::{Receiver, Sender},
+    sync::mpsc::{
+        self, Receiver, Sender, UnboundedReceiver, UnboundedSender,
+        error::{SendError, TrySendError},
+    },
     task::JoinHandle,
 };
 
-use crate::client_account::ClientAccount;
-use crate::transaction::Transaction;
+use crate::audit::{AuditEvent, AuditSink, ClientHistory};
+use crate::client_account::{AccountPolicy, ClientAccount};
+use crate::clock::{Clock, SystemClock};
+use crate::decimal::{Decimal, DecimalWide};
+use crate::error::EngineError;
+use crate::interceptor::{InterceptDecision, TransactionInterceptor};
+use crate::limits::{ResourceLimit, ResourceLimits};
+use crate::output::ClosedAccountSink;
+use crate::rejection::{RejectedTransaction, RejectionCollector, RejectionReason, RejectionSink};
+use crate::reorder::ReorderBuffer;
+use crate::state::{EngineState, TxIdSet};
+use crate::stats::{LockedSet, ProcessStats, StatsHandle};
+use crate::store::AccountStore;
+use crate::transaction::{Transaction, TransactionType};
 use crate::{ClientId, TransactionId, error};
 
+/// Scope within which deposit/withdrawal tx IDs must be unique.
+///
+/// Rows that refer to an earlier tx ID (disputes, resolves, chargebacks, and
+/// releases) always match by `(client, tx)` under either scope: they are looked
+/// up in the referring client's own account and never consult the deduplication
+/// set, so a client can only ever dispute its own transactions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TxIdScope {
+    /// Tx IDs are unique across all clients (the spec's default).
+    #[default]
+    Global,
+    /// Tx IDs are only unique per client; the same ID may appear for different clients.
+    PerClient,
+}
+
+/// What the engine does when a deposit/withdrawal reuses a tx ID.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Log and reject the duplicate, then keep processing.
+    #[default]
+    Skip,
+    /// Treat the feed as corrupt: stop processing and resolve the
+    /// [`serve`](PaymentsEngine::serve) handle with [`EngineError::DuplicateTransaction`].
+    Abort,
+}
+
+/// Deduplication key for a processed tx ID under a given [`TxIdScope`].
+pub(crate) type TxKey = (Option<ClientId>, TransactionId);
+
+impl TxIdScope {
+    pub(crate) fn key(self, client: ClientId, tx: TransactionId) -> TxKey {
+        match self {
+            Self::Global => (None, tx),
+            Self::PerClient => (Some(client), tx),
+        }
+    }
+}
+
+/// Sending half of the engine's transaction channel.
+///
+/// Bounded senders wait for buffer space when the engine falls behind; unbounded
+/// senders never wait. See [`PaymentsEngine::unbounded`].
+#[derive(Debug, Clone)]
+pub enum TransactionSender {
+    Bounded(Sender<Transaction>),
+    Unbounded(UnboundedSender<Transaction>),
+}
+
+impl TransactionSender {
+    /// Submit a transaction, waiting for buffer space if the channel is bounded and full.
+    ///
+    /// Fails only if the engine has stopped receiving.
+    pub async fn send(&self, tx: Transaction) -> Result<(), SendError<Transaction>> {
+        match self {
+            Self::Bounded(sender) => sender.send(tx).await,
+            Self::Unbounded(sender) => sender.send(tx),
+        }
+    }
+
+    /// Blocking version of [`send`](Self::send), for producers on non-async threads
+    /// (e.g., inside `tokio::task::spawn_blocking`).
+    ///
+    /// # Panics
+    /// If called from within an async execution context (see
+    /// [`Sender::blocking_send`]).
+    pub fn blocking_send(&self, tx: Transaction) -> Result<(), SendError<Transaction>> {
+        match self {
+            Self::Bounded(sender) => sender.blocking_send(tx),
+            Self::Unbounded(sender) => sender.send(tx),
+        }
+    }
+
+    /// Submit a transaction without waiting, failing with [`TrySendError::Full`]
+    /// if the channel is bounded and full, or [`TrySendError::Closed`] if the
+    /// engine has stopped receiving.
+    pub fn try_send(&self, tx: Transaction) -> Result<(), TrySendError<Transaction>> {
+        match self {
+            Self::Bounded(sender) => sender.try_send(tx),
+            Self::Unbounded(sender) => sender
+                .send(tx)
+                .map_err(|SendError(tx)| TrySendError::Closed(tx)),
+        }
+    }
+}
+
+/// Receiving half of the engine's transaction channel.
+enum TransactionReceiver {
+    Bounded(Receiver<Transaction>),
+    Unbounded(UnboundedReceiver<Transaction>),
+}
+
+impl TransactionReceiver {
+    async fn recv(&mut self) -> Option<Transaction> {
+        match self {
+            Self::Bounded(receiver) => receiver.recv().await,
+            Self::Unbounded(receiver) => receiver.recv().await,
+        }
+    }
+}
+
 /// Payments processing engine.
 pub struct PaymentsEngine {
-    channel: (Sender<Transaction>, Receiver<Transaction>),
-    processed_tx_ids: HashSet<TransactionId>,
+    channel: (TransactionSender, TransactionReceiver),
+    processed_tx_ids: TxIdSet,
+    tx_id_scope: TxIdScope,
+    duplicate_policy: DuplicatePolicy,
+    reorder_window: Option<i64>,
+    rejection_sink: Option<Box<dyn RejectionSink>>,
+    audit_sink: Option<Box<dyn AuditSink>>,
+    close_sink: Option<Box<dyn ClosedAccountSink>>,
+    interceptor: Option<Box<dyn TransactionInterceptor>>,
+    limits: ResourceLimits,
+    account_policy: AccountPolicy,
+    clock: Arc<dyn Clock>,
+    accounts: BTreeMap<ClientId, ClientAccount>,
+    stats: StatsHandle,
+    locked_set: LockedSet,
+    history: Option<ClientHistory>,
+    create_account_on_any_reference: bool,
 }
 
 impl Default for PaymentsEngine {
@@ -26,59 +159,890 @@ impl Default for PaymentsEngine {
     }
 }
 
+/// Channel buffer size used by [`PaymentsEngine::new`].
+const DEFAULT_BUFFER_SIZE: usize = 100; // arbitrary
+
 impl PaymentsEngine {
+    /// Create an engine with every tunable at its default; shorthand for
+    /// `PaymentsEngine::builder().build()`.
     pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    /// Start configuring an engine. See [`PaymentsEngineBuilder`].
+    pub fn builder() -> PaymentsEngineBuilder {
+        PaymentsEngineBuilder::default()
+    }
+
+    fn bounded(buffer_size: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(buffer_size);
+        Self::with_channel(
+            TransactionSender::Bounded(sender),
+            TransactionReceiver::Bounded(receiver),
+        )
+    }
+
+    /// Create an engine whose channel has no buffer limit.
+    ///
+    /// Producers never wait on the engine, which suits batch loads where
+    /// backpressure is unwanted. The tradeoff is memory: every transaction sent
+    /// but not yet processed is queued in memory, so a producer that outpaces the
+    /// engine grows the queue without bound instead of the O(1) buffer of [`PaymentsEngine::new`].
+    pub fn unbounded() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self::with_channel(
+            TransactionSender::Unbounded(sender),
+            TransactionReceiver::Unbounded(receiver),
+        )
+    }
+
+    fn with_channel(sender: TransactionSender, receiver: TransactionReceiver) -> Self {
         Self {
-            channel: tokio::sync::mpsc::channel(100), // arbitrary buffer size
-            processed_tx_ids: HashSet::new(),
+            channel: (sender, receiver),
+            processed_tx_ids: TxIdSet::default(),
+            tx_id_scope: TxIdScope::default(),
+            duplicate_policy: DuplicatePolicy::default(),
+            reorder_window: None,
+            rejection_sink: None,
+            audit_sink: None,
+            close_sink: None,
+            interceptor: None,
+            limits: ResourceLimits::default(),
+            account_policy: AccountPolicy::default(),
+            clock: Arc::new(SystemClock),
+            accounts: BTreeMap::new(),
+            stats: StatsHandle::default(),
+            locked_set: LockedSet::default(),
+            history: None,
+            create_account_on_any_reference: false,
         }
     }
 
+    /// Resume from `state` saved by an earlier run: seed its accounts, and
+    /// reject tx IDs it had already seen as duplicates.
+    ///
+    /// Also adopts the state's [`TxIdScope`], which its tx IDs were recorded under.
+    pub fn with_state(mut self, state: EngineState) -> Self {
+        self.accounts = state.accounts;
+        self.tx_id_scope = state.tx_id_scope;
+        self.processed_tx_ids = state.tx_ids;
+        self
+    }
+
+    /// Seed the engine with existing accounts (e.g., from [`load_accounts_csv`](crate::load_accounts_csv)).
+    ///
+    /// Seeded accounts keep their own policy; the engine's account policy only
+    /// applies to accounts it creates.
+    pub fn with_accounts(mut self, accounts: BTreeMap<ClientId, ClientAccount>) -> Self {
+        self.accounts = accounts;
+        self
+    }
+
+    /// Set the scope in which tx IDs are deduplicated (default: [`TxIdScope::Global`]).
+    pub fn with_tx_id_scope(mut self, scope: TxIdScope) -> Self {
+        self.tx_id_scope = scope;
+        self
+    }
+
+    /// Set how duplicate tx IDs are handled (default: [`DuplicatePolicy::Skip`]).
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = policy;
+        self
+    }
+
+    /// Apply transactions in timestamp order rather than arrival order, tolerating
+    /// arrivals up to `window` timestamp units out of order.
+    ///
+    /// Each transaction is held back until one at least `window` later arrives
+    /// (or the input ends), so a dispute that precedes its deposit in the feed
+    /// still applies correctly if its timestamp follows the deposit's. Precisely,
+    /// with `max_ts` the largest timestamp seen so far:
+    /// - A buffered transaction at `t` is applied once `t <= max_ts - window`,
+    ///   in timestamp order; ties keep their arrival order at the engine.
+    /// - A transaction without a timestamp is treated as happening at `max_ts`.
+    /// - A transaction already older than `max_ts - window` on arrival is applied
+    ///   immediately (late, and so out of timestamp order), and logged.
+    /// - Once every sender is dropped, everything still buffered is applied in order.
+    pub fn with_reorder_window(mut self, window: i64) -> Self {
+        self.reorder_window = Some(window);
+        self
+    }
+
+    /// Apply `policy` to every client account the engine creates.
+    pub fn with_account_policy(mut self, policy: AccountPolicy) -> Self {
+        self.account_policy = policy;
+        self
+    }
+
+    /// Report every rejected transaction to `sink` in addition to logging it.
+    pub fn with_rejection_sink(mut self, sink: impl RejectionSink + 'static) -> Self {
+        self.rejection_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// Record every processed transaction to `sink`, e.g., an [`AuditLog`](crate::AuditLog)
+    /// for [`replay_audit`](crate::replay_audit).
+    pub fn with_audit_sink(mut self, sink: impl AuditSink + 'static) -> Self {
+        self.audit_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// Hand each account to `sink` as soon as a `close` transaction closes it,
+    /// e.g., a [`StreamingAccountsWriter`](crate::StreamingAccountsWriter) to
+    /// write it out without waiting for the end of the input.
+    pub fn with_close_sink(mut self, sink: impl ClosedAccountSink + 'static) -> Self {
+        self.close_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// Run `interceptor` on every transaction before it is applied.
+    pub fn with_interceptor(mut self, interceptor: impl TransactionInterceptor + 'static) -> Self {
+        self.interceptor = Some(Box::new(interceptor));
+        self
+    }
+
+    /// Abort with [`EngineError::LimitExceeded`] once the input exceeds any of `limits`.
+    pub fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Read the current time for time-based policies from `clock` (default:
+    /// [`SystemClock`]). Inject a [`MockClock`](crate::MockClock) to test them.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Retain every transaction processed for each client, for
+    /// [`replay_client`](crate::replay_client). Read it back through
+    /// [`history`](Self::history).
+    ///
+    /// Off by default: the history grows with the input.
+    pub fn with_retained_history(mut self) -> Self {
+        self.history.get_or_insert_with(ClientHistory::default);
+        self
+    }
+
+    /// Create a zero-balance account for a client first seen in a row that
+    /// only refers to its account or earlier transactions (a dispute, resolve,
+    /// chargeback, release, close or unlock), so every referenced client
+    /// appears in the output. The row itself is still rejected as before.
+    ///
+    /// Off by default: such rows create nothing.
+    pub fn with_account_on_any_reference(mut self) -> Self {
+        self.create_account_on_any_reference = true;
+        self
+    }
+
+    /// Handle to the per-client history this engine retains while serving, if
+    /// built with [`with_retained_history`](Self::with_retained_history).
+    pub fn history(&self) -> Option<ClientHistory> {
+        self.history.clone()
+    }
+
+    /// Handle to the counters this engine updates while serving.
+    pub fn stats(&self) -> StatsHandle {
+        self.stats.clone()
+    }
+
+    /// Handle to the set of locked clients, which this engine keeps current
+    /// while serving (including any locked accounts it was seeded with).
+    pub fn locked_set(&self) -> LockedSet {
+        self.locked_set.clone()
+    }
+
     /// Get a sender to submit transactions to the engine.
-    pub fn sender(&self) -> Sender<Transaction> {
+    ///
+    /// Senders can be cloned and moved to independent tasks to feed the engine
+    /// from multiple producers.
+    ///
+    /// # Ordering guarantees
+    ///
+    /// Transactions from a single sender are processed in the order they were
+    /// sent. There is no ordering guarantee *between* independent senders: their
+    /// transactions are interleaved in whatever order the sends complete. Any
+    /// transactions whose outcome depends on their relative order (e.g., a
+    /// deposit and its dispute, or a deposit and a withdrawal on the same
+    /// account) must therefore be sent through the same sender.
+    pub fn sender(&self) -> TransactionSender {
         self.channel.0.clone()
     }
 
     /// Start processing transactions on a background task.
     ///
-    /// Returns a JoinHandle that resolves to the final state of all client accounts.
+    /// Returns a JoinHandle that resolves to the final state of all client accounts,
+    /// or an [`EngineError`] if processing was aborted (see [`DuplicatePolicy::Abort`]).
+    /// Once aborted, the engine stops receiving and further sends fail.
     ///
     /// Client accounts are returned as a BTreeMap to maintain sorted order by ClientId.
-    pub async fn serve(self) -> JoinHandle<BTreeMap<ClientId, ClientAccount>> {
-        let mut receiver = self.channel.1;
-        let mut processed_tx_ids = self.processed_tx_ids;
-        tokio::spawn(async move {
-            let mut accounts: BTreeMap<ClientId, ClientAccount> = BTreeMap::new();
+    pub async fn serve(self) -> JoinHandle<Result<BTreeMap<ClientId, ClientAccount>, EngineError>> {
+        self.serve_with_store(BTreeMap::new()).await
+    }
+
+    /// Like [`serve`](Self::serve), but keeps accounts in `store` (e.g., a
+    /// disk-backed [`AccountStore`]) and resolves to it once processing ends.
+    ///
+    /// Accounts seeded through [`with_accounts`](Self::with_accounts) are
+    /// inserted into `store` first. Any accounts `store` already holds are
+    /// kept and treated like seeded ones.
+    pub async fn serve_with_store<S>(self, store: S) -> JoinHandle<Result<S, EngineError>>
+    where
+        S: AccountStore + Send + 'static,
+    {
+        self.spawn(store, |core| core.accounts)
+    }
+
+    /// Like [`serve`](Self::serve), but resolves to the full [`EngineState`],
+    /// including every tx ID seen, for [`EngineState::save_state`].
+    pub async fn serve_state(self) -> JoinHandle<Result<EngineState, EngineError>> {
+        self.spawn(BTreeMap::new(), |core| EngineState {
+            accounts: core.accounts,
+            tx_id_scope: core.tx_id_scope,
+            tx_ids: core.processed_tx_ids,
+        })
+    }
 
+    /// Start the processing task over `store`, resolving to `finish` of its
+    /// final state.
+    fn spawn<S, T>(
+        self,
+        store: S,
+        finish: fn(EngineCore<S>) -> T,
+    ) -> JoinHandle<Result<T, EngineError>>
+    where
+        S: AccountStore + Send + 'static,
+        T: Send + 'static,
+    {
+        let mut reorder = self.reorder_window.map(ReorderBuffer::new);
+        let (mut receiver, mut core) = self.into_core_with(store);
+        tokio::spawn(async move {
             while let Some(tx) = receiver.recv().await {
-                if tx.is_dispute_related() {
-                    if let Some(account) = accounts.get_mut(&tx.client) {
-                        account.adjudicate_claim(tx);
-                    } else {
-                        error!(
-                            "Dispute-related transaction for non-existent account: {:?}",
-                            tx
-                        );
+                match reorder.as_mut() {
+                    Some(buffer) => {
+                        buffer.push(tx);
+                        while let Some(tx) = buffer.pop_ready() {
+                            core.process(tx)?;
+                        }
                     }
-                } else if processed_tx_ids.insert(tx.tx) {
-                    accounts
-                        .entry(tx.client)
-                        .or_insert_with(|| ClientAccount::new(tx.client))
-                        .settle_transaction(tx);
-                } else {
-                    error!("Duplicate transaction ID received: {}", tx.tx);
+                    None => core.process(tx)?,
                 }
             }
+            for tx in reorder.into_iter().flat_map(ReorderBuffer::drain) {
+                core.process(tx)?;
+            }
 
-            accounts
+            Ok(finish(core))
         })
     }
+
+    /// Split into the receiving half of the channel and the state the processing task owns.
+    fn into_core(self) -> (TransactionReceiver, EngineCore) {
+        self.into_core_with(BTreeMap::new())
+    }
+
+    /// Like [`into_core`](Self::into_core), keeping accounts in `store`.
+    fn into_core_with<S: AccountStore>(self, mut store: S) -> (TransactionReceiver, EngineCore<S>) {
+        for account in self.accounts.into_values() {
+            store.insert(account);
+        }
+        self.locked_set.reset(
+            store
+                .iter()
+                .filter(|account| account.locked)
+                .map(|account| account.client_id),
+        );
+        let opening_total: DecimalWide = store.iter().map(|account| account.total).sum();
+        self.stats
+            .update(|stats| stats.opening_total = opening_total);
+        let core = EngineCore {
+            accounts: store,
+            processed_tx_ids: self.processed_tx_ids,
+            tx_id_scope: self.tx_id_scope,
+            duplicate_policy: self.duplicate_policy,
+            rejection_sink: self.rejection_sink,
+            audit_sink: self.audit_sink,
+            close_sink: self.close_sink,
+            interceptor: self.interceptor,
+            limits: self.limits,
+            rows_seen: 0,
+            seq: 0,
+            account_policy: self.account_policy,
+            clock: self.clock,
+            stats: self.stats,
+            locked_set: self.locked_set,
+            history: self.history,
+            create_account_on_any_reference: self.create_account_on_any_reference,
+        };
+        (self.channel.1, core)
+    }
+
+    /// Drive every transaction from `stream` through the engine and return the
+    /// final account states, bypassing the CSV layer entirely.
+    ///
+    /// Transactions are applied in stream order. Like [`serve`](Self::serve),
+    /// this resolves to an [`EngineError`] if processing is aborted. A panic
+    /// in the engine's task is resumed in the caller.
+    pub async fn process_stream<S>(
+        self,
+        stream: S,
+    ) -> Result<BTreeMap<ClientId, ClientAccount>, EngineError>
+    where
+        S: Stream<Item = Transaction>,
+    {
+        let sender = self.sender();
+        let handle = self.serve().await;
+
+        let mut stream = std::pin::pin!(stream);
+        while let Some(tx) = stream.next().await {
+            if sender.send(tx).await.is_err() {
+                break; // Engine stopped early; its handle reports why
+            }
+        }
+        drop(sender); // Close the channel
+
+        match handle.await {
+            Ok(result) => result,
+            Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+            Err(_) => Err(EngineError::Cancelled),
+        }
+    }
+
+    /// Apply every transaction in `txs` and return the final account states
+    /// together with every rejected transaction (in arrival order) and the
+    /// engine's counters, in one call.
+    ///
+    /// Rejections are still reported to any sink set with
+    /// [`with_rejection_sink`](Self::with_rejection_sink). Like
+    /// [`process_stream`](Self::process_stream), this resolves to an
+    /// [`EngineError`] if processing is aborted.
+    pub async fn process_all_detailed(
+        mut self,
+        txs: impl IntoIterator<Item = Transaction>,
+    ) -> Result<
+        (
+            BTreeMap<ClientId, ClientAccount>,
+            Vec<RejectedTransaction>,
+            ProcessStats,
+        ),
+        EngineError,
+    > {
+        let collector = RejectionCollector::new();
+        self.rejection_sink = Some(Box::new(TeeSink {
+            inner: self.rejection_sink.take(),
+            collector: collector.clone(),
+        }));
+        let stats = self.stats();
+
+        let accounts = self.process_stream(futures::stream::iter(txs)).await?;
+        Ok((accounts, collector.rejections(), stats.snapshot()))
+    }
+}
+
+/// Reports rejections to an embedder's own sink (if any) as well as the
+/// collector behind [`PaymentsEngine::process_all_detailed`].
+struct TeeSink {
+    inner: Option<Box<dyn RejectionSink>>,
+    collector: RejectionCollector,
+}
+
+impl RejectionSink for TeeSink {
+    fn record(&mut self, rejection: RejectedTransaction) {
+        if let Some(inner) = self.inner.as_mut() {
+            inner.record(rejection.clone());
+        }
+        self.collector.record(rejection);
+    }
+}
+
+/// Fluent configuration for a [`PaymentsEngine`], gathering every tunable in one place.
+///
+/// ```
+/// use crypto_events::{DuplicatePolicy, PaymentsEngine, TxIdScope};
+///
+/// let engine = PaymentsEngine::builder()
+///     .buffer_size(1_000)
+///     .tx_id_scope(TxIdScope::PerClient)
+///     .duplicate_policy(DuplicatePolicy::Abort)
+///     .build();
+/// # drop(engine);
+/// ```
+pub struct PaymentsEngineBuilder {
+    /// `None` for an unbounded channel.
+    buffer_size: Option<usize>,
+    tx_id_scope: TxIdScope,
+    duplicate_policy: DuplicatePolicy,
+    reorder_window: Option<i64>,
+    rejection_sink: Option<Box<dyn RejectionSink>>,
+    audit_sink: Option<Box<dyn AuditSink>>,
+    close_sink: Option<Box<dyn ClosedAccountSink>>,
+    interceptor: Option<Box<dyn TransactionInterceptor>>,
+    limits: ResourceLimits,
+    account_policy: AccountPolicy,
+    clock: Arc<dyn Clock>,
+    accounts: BTreeMap<ClientId, ClientAccount>,
+    retain_history: bool,
+    create_account_on_any_reference: bool,
+}
+
+impl Default for PaymentsEngineBuilder {
+    fn default() -> Self {
+        Self {
+            buffer_size: Some(DEFAULT_BUFFER_SIZE),
+            tx_id_scope: TxIdScope::default(),
+            duplicate_policy: DuplicatePolicy::default(),
+            reorder_window: None,
+            rejection_sink: None,
+            audit_sink: None,
+            close_sink: None,
+            interceptor: None,
+            limits: ResourceLimits::default(),
+            account_policy: AccountPolicy::default(),
+            clock: Arc::new(SystemClock),
+            accounts: BTreeMap::new(),
+            retain_history: false,
+            create_account_on_any_reference: false,
+        }
+    }
+}
+
+impl PaymentsEngineBuilder {
+    /// Bound the channel to `size` queued transactions (default: 100).
+    ///
+    /// # Panics
+    /// [`build`](Self::build) panics if `size` is zero.
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = Some(size);
+        self
+    }
+
+    /// Use a channel with no buffer limit. See [`PaymentsEngine::unbounded`].
+    pub fn unbounded(mut self) -> Self {
+        self.buffer_size = None;
+        self
+    }
+
+    /// See [`PaymentsEngine::with_tx_id_scope`].
+    pub fn tx_id_scope(mut self, scope: TxIdScope) -> Self {
+        self.tx_id_scope = scope;
+        self
+    }
+
+    /// See [`PaymentsEngine::with_duplicate_policy`].
+    pub fn duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = policy;
+        self
+    }
+
+    /// See [`PaymentsEngine::with_reorder_window`].
+    pub fn reorder_window(mut self, window: i64) -> Self {
+        self.reorder_window = Some(window);
+        self
+    }
+
+    /// See [`PaymentsEngine::with_account_policy`].
+    pub fn account_policy(mut self, policy: AccountPolicy) -> Self {
+        self.account_policy = policy;
+        self
+    }
+
+    /// See [`PaymentsEngine::with_rejection_sink`].
+    pub fn rejection_sink(mut self, sink: impl RejectionSink + 'static) -> Self {
+        self.rejection_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// See [`PaymentsEngine::with_audit_sink`].
+    pub fn audit_sink(mut self, sink: impl AuditSink + 'static) -> Self {
+        self.audit_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// See [`PaymentsEngine::with_close_sink`].
+    pub fn close_sink(mut self, sink: impl ClosedAccountSink + 'static) -> Self {
+        self.close_sink = Some(Box::new(sink));
+        self
+    }
+
+    /// See [`PaymentsEngine::with_interceptor`].
+    pub fn interceptor(mut self, interceptor: impl TransactionInterceptor + 'static) -> Self {
+        self.interceptor = Some(Box::new(interceptor));
+        self
+    }
+
+    /// See [`PaymentsEngine::with_resource_limits`].
+    pub fn resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// See [`PaymentsEngine::with_clock`].
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// See [`PaymentsEngine::with_accounts`].
+    pub fn accounts(mut self, accounts: BTreeMap<ClientId, ClientAccount>) -> Self {
+        self.accounts = accounts;
+        self
+    }
+
+    /// See [`PaymentsEngine::with_retained_history`].
+    pub fn retain_history(mut self, retain: bool) -> Self {
+        self.retain_history = retain;
+        self
+    }
+
+    /// See [`PaymentsEngine::with_account_on_any_reference`].
+    pub fn create_account_on_any_reference(mut self, create: bool) -> Self {
+        self.create_account_on_any_reference = create;
+        self
+    }
+
+    /// Create the configured engine.
+    pub fn build(self) -> PaymentsEngine {
+        let mut engine = match self.buffer_size {
+            Some(size) => PaymentsEngine::bounded(size),
+            None => PaymentsEngine::unbounded(),
+        };
+        engine.tx_id_scope = self.tx_id_scope;
+        engine.duplicate_policy = self.duplicate_policy;
+        engine.reorder_window = self.reorder_window;
+        engine.rejection_sink = self.rejection_sink;
+        engine.audit_sink = self.audit_sink;
+        engine.close_sink = self.close_sink;
+        engine.interceptor = self.interceptor;
+        engine.limits = self.limits;
+        engine.account_policy = self.account_policy;
+        engine.clock = self.clock;
+        engine.accounts = self.accounts;
+        engine.history = self.retain_history.then(ClientHistory::default);
+        engine.create_account_on_any_reference = self.create_account_on_any_reference;
+        engine
+    }
+}
+
+/// Log and build the error for `tx` exceeding `limit`.
+fn limit_exceeded(tx: &Transaction, limit: ResourceLimit) -> Result<(), EngineError> {
+    error!("Aborting on tx {}: exceeded {}", tx.tx, limit);
+    Err(EngineError::LimitExceeded { limit, tx: tx.tx })
+}
+
+/// State owned by the background processing task.
+struct EngineCore<S = BTreeMap<ClientId, ClientAccount>> {
+    accounts: S,
+    processed_tx_ids: TxIdSet,
+    tx_id_scope: TxIdScope,
+    duplicate_policy: DuplicatePolicy,
+    rejection_sink: Option<Box<dyn RejectionSink>>,
+    audit_sink: Option<Box<dyn AuditSink>>,
+    close_sink: Option<Box<dyn ClosedAccountSink>>,
+    interceptor: Option<Box<dyn TransactionInterceptor>>,
+    limits: ResourceLimits,
+    /// Transactions received so far, for [`ResourceLimits::max_rows`].
+    rows_seen: u64,
+    /// Sequence number of the transaction being applied; see
+    /// [`ClientAccount::created_seq`] and [`ClientAccount::last_seq`].
+    seq: u64,
+    account_policy: AccountPolicy,
+    clock: Arc<dyn Clock>,
+    stats: StatsHandle,
+    locked_set: LockedSet,
+    history: Option<ClientHistory>,
+    create_account_on_any_reference: bool,
+}
+
+impl<S: AccountStore> EngineCore<S> {
+    /// Apply a single transaction, counting and forwarding any rejection to the sink.
+    ///
+    /// Errors only if the rejection should stop the engine, or a resource limit is exceeded.
+    fn process(&mut self, tx: Transaction) -> Result<(), EngineError> {
+        let started = Instant::now();
+        self.rows_seen += 1;
+        let result = self
+            .check_input_limits(&tx)
+            .and_then(|()| self.process_within_limits(&tx))
+            .and_then(|()| self.check_state_limits(&tx));
+        let elapsed = started.elapsed();
+        self.stats.update(|stats| stats.process_duration += elapsed);
+        result
+    }
+
+    /// Limits checked before `tx` is applied.
+    fn check_input_limits(&self, tx: &Transaction) -> Result<(), EngineError> {
+        if self.limits.max_rows.is_some_and(|max| self.rows_seen > max) {
+            return limit_exceeded(tx, ResourceLimit::Rows);
+        }
+        // A full withdrawal has no amount of its own; it moves what is available
+        let amount = match tx.transaction_type {
+            TransactionType::WithdrawAll => self
+                .accounts
+                .get(tx.client)
+                .map(|account| account.available.max(Decimal::ZERO)),
+            _ => tx.amount,
+        };
+        if let (Some(max), Some(amount)) = (self.limits.max_amount, amount)
+            && amount.0.unsigned_abs() > max.0.unsigned_abs()
+        {
+            return limit_exceeded(tx, ResourceLimit::Amount);
+        }
+        Ok(())
+    }
+
+    /// Limits on the state `tx` left behind. The engine aborts, so exceeding
+    /// state is never returned.
+    fn check_state_limits(&self, tx: &Transaction) -> Result<(), EngineError> {
+        if self
+            .limits
+            .max_accounts
+            .is_some_and(|max| self.accounts.len() > max)
+        {
+            return limit_exceeded(tx, ResourceLimit::Accounts);
+        }
+        let Some(account) = self.accounts.get(tx.client) else {
+            return Ok(());
+        };
+        if self
+            .limits
+            .max_disputes_per_account
+            .is_some_and(|max| account.disputes().len() > max)
+        {
+            return limit_exceeded(tx, ResourceLimit::DisputesPerAccount);
+        }
+        if self
+            .limits
+            .max_ledger_per_account
+            .is_some_and(|max| account.ledger().len() > max)
+        {
+            return limit_exceeded(tx, ResourceLimit::LedgerPerAccount);
+        }
+        Ok(())
+    }
+
+    fn process_within_limits(&mut self, tx: &Transaction) -> Result<(), EngineError> {
+        let decision = match self.interceptor.as_mut() {
+            Some(interceptor) => interceptor.before(tx, self.accounts.get(tx.client)),
+            None => InterceptDecision::Accept,
+        };
+        let now = self.clock.now();
+        let modified;
+        let (tx, result) = match decision {
+            InterceptDecision::Accept => (tx, self.apply(tx, now)),
+            InterceptDecision::Modify(substitute) => {
+                modified = substitute;
+                (&modified, self.apply(&modified, now))
+            }
+            InterceptDecision::Reject => {
+                error!("Transaction rejected by interceptor: {:?}", tx);
+                (tx, Err(RejectionReason::Intercepted))
+            }
+        };
+        if let Some(sink) = self.audit_sink.as_mut() {
+            sink.record(AuditEvent {
+                tx: tx.clone(),
+                at: now,
+                rejection: result.err(),
+                tx_id_scope: self.tx_id_scope,
+            });
+        }
+        // Like `replay_audit`, interceptor rejections are left out: replaying
+        // them would apply what the interceptor refused. So are duplicates: a
+        // tx ID claimed by another client would look fresh in a per-client replay
+        if let Some(history) = &self.history
+            && !matches!(
+                result,
+                Err(RejectionReason::Intercepted | RejectionReason::DuplicateTransaction)
+            )
+        {
+            history.record(tx);
+        }
+        // Only chargebacks lock an account (besides repeated failed withdrawals,
+        // handled in `apply`) and only unlocks reinstate one
+        if result.is_ok()
+            && matches!(
+                tx.transaction_type,
+                TransactionType::Chargeback | TransactionType::Unlock
+            )
+            && let Some(account) = self.accounts.get(tx.client)
+        {
+            self.locked_set.set(tx.client, account.locked);
+        }
+        let Err(reason) = result else {
+            return Ok(());
+        };
+        match reason {
+            RejectionReason::InsufficientFunds => {
+                self.stats.update(|stats| stats.insufficient_funds += 1)
+            }
+            RejectionReason::MissingAmount => self.stats.update(|stats| stats.missing_amount += 1),
+            _ => {}
+        }
+        if let Some(sink) = self.rejection_sink.as_mut() {
+            sink.record(RejectedTransaction {
+                tx: tx.clone(),
+                reason,
+            });
+        }
+        if reason == RejectionReason::DuplicateTransaction
+            && self.duplicate_policy == DuplicatePolicy::Abort
+        {
+            error!("Aborting on duplicate transaction ID: {}", tx.tx);
+            return Err(EngineError::DuplicateTransaction(tx.tx));
+        }
+        Ok(())
+    }
+
+    /// Apply `tx` under the next sequence number, stamping it on the account
+    /// if it succeeds.
+    fn apply(&mut self, tx: &Transaction, now: i64) -> Result<(), RejectionReason> {
+        self.seq += 1;
+        // What a successful transaction of this type must move, worked out from
+        // the state beforehand so the stats don't just echo the account's change
+        let before = self.accounts.get(tx.client).map(|account| {
+            let moved = match tx.transaction_type {
+                TransactionType::WithdrawAll | TransactionType::Close => account.available,
+                TransactionType::Chargeback => {
+                    account.chargeback_amount(tx.tx).unwrap_or(Decimal::ZERO)
+                }
+                _ => tx.amount.unwrap_or(Decimal::ZERO),
+            };
+            (moved, account.total_written_off())
+        });
+        let result = self.dispatch(tx, now);
+        if let Some(account) = self.accounts.get_mut(tx.client)
+            && account.track_failed_withdrawals(tx, result)
+        {
+            self.locked_set.set(tx.client, true);
+        }
+        if result.is_ok()
+            && let Some(account) = self.accounts.get_mut(tx.client)
+        {
+            account.touch(self.seq);
+            // A deposit creates the account, so there may be no `before`
+            let amount = tx.amount.unwrap_or(Decimal::ZERO);
+            let (moved, written_off_before) = before.unwrap_or((amount, Decimal::ZERO));
+            let written_off = account.total_written_off() - written_off_before;
+            match tx.transaction_type {
+                TransactionType::Deposit => self.stats.update(|stats| stats.deposited += moved),
+                TransactionType::Withdrawal
+                | TransactionType::WithdrawAll
+                | TransactionType::Close => self.stats.update(|stats| stats.withdrawn += moved),
+                TransactionType::Chargeback => self.stats.update(|stats| {
+                    stats.charged_back += moved;
+                    stats.written_off += written_off;
+                }),
+                _ => {}
+            }
+        }
+        result
+    }
+
+    /// Route `tx` to the account operation for its type.
+    fn dispatch(&mut self, tx: &Transaction, now: i64) -> Result<(), RejectionReason> {
+        let placeholder =
+            self.create_account_on_any_reference && self.accounts.get(tx.client).is_none();
+        if placeholder {
+            let mut account = ClientAccount::with_policy(tx.client, self.account_policy);
+            account.mark_created(self.seq);
+            self.accounts.insert(account);
+        }
+        if matches!(
+            tx.transaction_type,
+            TransactionType::Close | TransactionType::Unlock
+        ) {
+            // Account-level operation: the tx ID is not recorded for deduplication
+            let account = match self.accounts.get_mut(tx.client) {
+                // A placeholder created by this very row doesn't make it valid
+                Some(account) if !placeholder => account,
+                _ => {
+                    error!("Account-level operation for non-existent account: {:?}", tx);
+                    return Err(RejectionReason::UnknownAccount);
+                }
+            };
+            if tx.transaction_type == TransactionType::Close {
+                account.close()?;
+                if let Some(sink) = self.close_sink.as_mut() {
+                    sink.account_closed(account);
+                }
+                Ok(())
+            } else {
+                account.unlock()
+            }
+        } else if tx.transaction_type == TransactionType::Release {
+            // Like a dispute, a release refers to an earlier tx ID rather than claiming one
+            let Some(account) = self.accounts.get_mut(tx.client) else {
+                error!("Release for non-existent account: {:?}", tx);
+                return Err(RejectionReason::UnknownAccount);
+            };
+            account.release_hold(tx.clone())
+        } else if tx.is_dispute_related() {
+            // Matched in the client's own ledger, so by (client, tx) whatever the scope
+            if let Some(account) = self.accounts.get_mut(tx.client) {
+                account.adjudicate_claim_at(tx.clone(), now)
+            } else {
+                error!(
+                    "Dispute-related transaction for non-existent account: {:?}",
+                    tx
+                );
+                Err(RejectionReason::UnknownAccount)
+            }
+        } else if self
+            .processed_tx_ids
+            .insert(self.tx_id_scope.key(tx.client, tx.tx))
+        {
+            self.accounts
+                .get_or_insert_with(tx.client, || {
+                    let mut account = ClientAccount::with_policy(tx.client, self.account_policy);
+                    account.mark_created(self.seq);
+                    account
+                })
+                .settle_transaction_at(tx.clone(), now)
+        } else {
+            error!("Duplicate transaction ID received: {}", tx.tx);
+            Err(RejectionReason::DuplicateTransaction)
+        }
+    }
+}
+
+/// An engine driven synchronously, one transaction at a time, for harnesses
+/// that can't run it on an async runtime.
+#[cfg(feature = "fuzzing")]
+pub(crate) struct SyncEngine(EngineCore);
+
+#[cfg(feature = "fuzzing")]
+impl SyncEngine {
+    pub(crate) fn new(engine: PaymentsEngine) -> Self {
+        Self(engine.into_core().1)
+    }
+
+    /// Process `tx` exactly as the running engine would, erroring where it would abort.
+    pub(crate) fn process(&mut self, tx: Transaction) -> Result<(), EngineError> {
+        self.0.process(tx)
+    }
+
+    pub(crate) fn accounts(&self) -> &BTreeMap<ClientId, ClientAccount> {
+        &self.0.accounts
+    }
+}
+
+/// Apply `(transaction, time)` pairs in order through a default engine's
+/// dispatch, deduplicating tx IDs under `scope` as the recording engine did.
+pub(crate) fn replay(
+    scope: TxIdScope,
+    events: impl IntoIterator<Item = (Transaction, i64)>,
+) -> BTreeMap<ClientId, ClientAccount> {
+    let (_, mut core) = PaymentsEngine::new().with_tx_id_scope(scope).into_core();
+    for (tx, at) in events {
+        let _ = core.apply(&tx, at);
+    }
+    core.accounts
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Decimal, TransactionType};
+    use crate::{ConservationError, Decimal, MockClock, TxIdEncoding, verify_conservation};
 
     pub async fn process_transactions_vec(
         transactions: Vec<Transaction>,
@@ -90,54 +1054,37 @@ mod tests {
             sender.send(tx).await.unwrap();
         }
         drop(sender); // Close the channel
-        handle.await.unwrap()
+        handle.await.unwrap().unwrap()
     }
 
     // ========== Helper Functions ==========
 
     fn deposit(client: ClientId, tx: TransactionId, amount: f64) -> Transaction {
-        Transaction {
-            transaction_type: TransactionType::Deposit,
-            amount: Some(Decimal::from_f64(amount)),
-            tx,
-            client,
-        }
+        Transaction::deposit(client, tx, Decimal::from_f64(amount))
     }
 
     fn withdrawal(client: ClientId, tx: TransactionId, amount: f64) -> Transaction {
-        Transaction {
-            transaction_type: TransactionType::Withdrawal,
-            amount: Some(Decimal::from_f64(amount)),
-            tx,
-            client,
-        }
+        Transaction::withdrawal(client, tx, Decimal::from_f64(amount))
     }
 
     fn dispute(client: ClientId, tx: TransactionId) -> Transaction {
-        Transaction {
-            transaction_type: TransactionType::Dispute,
-            amount: None,
-            tx,
-            client,
-        }
+        Transaction::dispute(client, tx)
     }
 
     fn resolve(client: ClientId, tx: TransactionId) -> Transaction {
-        Transaction {
-            transaction_type: TransactionType::Resolve,
-            amount: None,
-            tx,
-            client,
-        }
+        Transaction::resolve(client, tx)
     }
 
     fn chargeback(client: ClientId, tx: TransactionId) -> Transaction {
-        Transaction {
-            transaction_type: TransactionType::Chargeback,
-            amount: None,
-            tx,
-            client,
-        }
+        Transaction::chargeback(client, tx)
+    }
+
+    fn close(client: ClientId, tx: TransactionId) -> Transaction {
+        Transaction::close(client, tx)
+    }
+
+    fn unlock(client: ClientId, tx: TransactionId) -> Transaction {
+        Transaction::unlock(client, tx)
     }
 
     fn assert_account(
@@ -419,6 +1366,97 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_account_on_any_reference_for_dispute_only_client() {
+        let txs = [
+            deposit(1, 1, 10.0),
+            dispute(2, 1),
+            resolve(3, 1),
+            close(5, 2),
+            deposit(5, 3, 10.0),
+        ];
+
+        let (accounts, rejections, _) = PaymentsEngine::new()
+            .process_all_detailed(txs.clone())
+            .await
+            .unwrap();
+        assert_eq!(accounts.keys().copied().collect::<Vec<_>>(), vec![1, 5]);
+        assert_eq!(rejections[0].reason, RejectionReason::UnknownAccount);
+
+        let (accounts, rejections, _) = PaymentsEngine::builder()
+            .create_account_on_any_reference(true)
+            .build()
+            .process_all_detailed(txs)
+            .await
+            .unwrap();
+        assert_account(&accounts, 1, 10.0, 0.0, 10.0, false);
+        assert_account(&accounts, 2, 0.0, 0.0, 0.0, false);
+        assert_account(&accounts, 3, 0.0, 0.0, 0.0, false);
+        // The close is rejected, so the later deposit lands on an open account
+        assert!(!accounts[&5].is_closed());
+        assert_account(&accounts, 5, 10.0, 0.0, 10.0, false);
+        let reasons: Vec<_> = rejections.iter().map(|r| (r.tx.tx, r.reason)).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (1, RejectionReason::UnknownTransaction),
+                (1, RejectionReason::UnknownTransaction),
+                (2, RejectionReason::UnknownAccount),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_closed_account_streamed_before_end_of_input() {
+        #[derive(Clone, Default)]
+        struct SharedBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+        impl std::io::Write for SharedBuffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = SharedBuffer::default();
+        let contents = || String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let output = crate::StreamingAccountsWriter::new(buffer.clone());
+        let engine = PaymentsEngine::new().with_close_sink(output.clone());
+        let sender = engine.sender();
+        let handle = engine.serve().await;
+        for tx in [
+            deposit(1, 1, 5.0),
+            deposit(2, 2, 3.0),
+            Transaction::close(1, 3),
+        ] {
+            sender.send(tx).await.unwrap();
+        }
+
+        // The input is still open, yet the closed account is already written
+        for _ in 0..1000 {
+            if !contents().is_empty() {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(
+            contents(),
+            "client,available,held,total,locked\n1,0.0,0.0,0.0,false\n"
+        );
+
+        drop(sender);
+        let accounts = handle.await.unwrap().unwrap();
+        output.finish(&accounts).unwrap();
+        assert_eq!(
+            contents(),
+            "client,available,held,total,locked\n1,0.0,0.0,0.0,false\n2,3.0,0.0,3.0,false\n"
+        );
+    }
+
     // ========== Locked Account Tests ==========
 
     #[tokio::test]
@@ -517,21 +1555,880 @@ mod tests {
         assert_account(&accounts, 1, 10.0, 0.0, 10.0, false);
     }
 
-    // ========== Negative Balance Edge Cases ==========
-
     #[tokio::test]
-    async fn test_dispute_after_partial_spend() {
-        // Dispute a deposit after some funds were already withdrawn
-        // This causes available to go negative (client owes money during dispute)
+    async fn test_max_tx_id_deposit_and_dispute() {
         let accounts = process_transactions_vec(vec![
-            deposit(1, 1, 10.0),
-            withdrawal(1, 2, 7.0),
-            dispute(1, 1),
+            deposit(1, u64::MAX, 10.0),
+            deposit(1, u64::MAX - 1, 5.0),
+            dispute(1, u64::MAX),
+        ])
+        .await;
+        assert_account(&accounts, 1, 5.0, 10.0, 15.0, false);
+    }
+
+    #[tokio::test]
+    async fn test_dispute_withdrawal_ignored_without_withdrawal_ledger() {
+        let engine = PaymentsEngine::new().with_account_policy(AccountPolicy {
+            store_withdrawals_in_ledger: false,
+            ..AccountPolicy::default()
+        });
+        let sender = engine.sender();
+        let handle = engine.serve().await;
+        for tx in [deposit(1, 1, 10.0), withdrawal(1, 2, 3.0), dispute(1, 2)] {
+            sender.send(tx).await.unwrap();
+        }
+        drop(sender);
+        let accounts = handle.await.unwrap().unwrap();
+        assert_account(&accounts, 1, 7.0, 0.0, 7.0, false);
+    }
+
+    // ========== Builder Tests ==========
+
+    #[tokio::test]
+    async fn test_builder_applies_non_default_options() {
+        let collector = RejectionCollector::new();
+        let engine = PaymentsEngine::builder()
+            .buffer_size(1)
+            .tx_id_scope(TxIdScope::PerClient)
+            .account_policy(AccountPolicy {
+                allow_negative_deposits: true,
+                ..AccountPolicy::default()
+            })
+            .rejection_sink(collector.clone())
+            .build();
+        assert!(matches!(engine.sender(), TransactionSender::Bounded(_)));
+
+        let sender = engine.sender();
+        let handle = engine.serve().await;
+        for tx in [
+            deposit(1, 1, 10.0),
+            deposit(2, 1, 5.0), // Same tx ID, different client: allowed per client
+            deposit(1, 2, -3.0), // Refund: allowed by the account policy
+            withdrawal(2, 2, 9.0), // Insufficient funds: reported to the sink
+        ] {
+            sender.send(tx).await.unwrap();
+        }
+        drop(sender);
+        let accounts = handle.await.unwrap().unwrap();
+
+        assert_account(&accounts, 1, 7.0, 0.0, 7.0, false);
+        assert_account(&accounts, 2, 5.0, 0.0, 5.0, false);
+        let rejections = collector.rejections();
+        assert_eq!(rejections.len(), 1);
+        assert_eq!(rejections[0].reason, RejectionReason::InsufficientFunds);
+    }
+
+    #[tokio::test]
+    async fn test_builder_unbounded_with_abort() {
+        let engine = PaymentsEngine::builder()
+            .unbounded()
+            .duplicate_policy(DuplicatePolicy::Abort)
+            .build();
+        assert!(matches!(engine.sender(), TransactionSender::Unbounded(_)));
+
+        let result = engine
+            .process_stream(futures::stream::iter([
+                deposit(1, 1, 1.0),
+                deposit(1, 1, 1.0),
+            ]))
+            .await;
+        assert_eq!(result.unwrap_err(), EngineError::DuplicateTransaction(1));
+    }
+
+    // ========== Clock Tests ==========
+
+    #[tokio::test]
+    async fn test_mock_clock_resets_daily_withdrawal_limit() {
+        let clock = MockClock::new(1_700_000_000);
+        let engine = PaymentsEngine::builder()
+            .account_policy(AccountPolicy {
+                daily_withdrawal_limit: Some(Decimal::from_f64(10.0)),
+                ..AccountPolicy::default()
+            })
+            .clock(clock.clone())
+            .build();
+        let accounts = engine
+            .process_stream(futures::stream::iter([
+                deposit(1, 1, 100.0),
+                withdrawal(1, 2, 8.0),
+                withdrawal(1, 3, 5.0), // Over the limit for today
+            ]))
+            .await
+            .unwrap();
+        assert_account(&accounts, 1, 92.0, 0.0, 92.0, false);
+
+        // The following day, the same account may withdraw again
+        clock.advance(24 * 60 * 60);
+        let engine = PaymentsEngine::builder()
+            .accounts(accounts)
+            .clock(clock)
+            .build();
+        let accounts = engine
+            .process_stream(futures::stream::iter([withdrawal(1, 4, 5.0)]))
+            .await
+            .unwrap();
+        assert_account(&accounts, 1, 87.0, 0.0, 87.0, false);
+    }
+
+    #[tokio::test]
+    async fn test_mock_clock_prunes_settled_disputes() {
+        let clock = MockClock::new(0);
+        let policy = AccountPolicy {
+            settled_dispute_retention: Some(3_600),
+            ..AccountPolicy::default()
+        };
+        let engine = PaymentsEngine::builder()
+            .account_policy(policy)
+            .clock(clock.clone())
+            .build();
+        let accounts = engine
+            .process_stream(futures::stream::iter([
+                deposit(1, 1, 10.0),
+                dispute(1, 1),
+                resolve(1, 1),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(accounts[&1].disputes().len(), 1);
+
+        clock.advance(3_600);
+        let collector = RejectionCollector::new();
+        let engine = PaymentsEngine::builder()
+            .accounts(accounts)
+            .clock(clock)
+            .rejection_sink(collector.clone())
+            .build();
+        let accounts = engine
+            .process_stream(futures::stream::iter([dispute(1, 1)]))
+            .await
+            .unwrap();
+        assert!(accounts[&1].disputes().is_empty());
+        assert_eq!(
+            collector.rejections()[0].reason,
+            RejectionReason::UnknownTransaction
+        );
+    }
+
+    // ========== Interceptor Tests ==========
+
+    /// Rejects withdrawals over a threshold and caps deposits at the threshold.
+    struct ThresholdInterceptor(Decimal);
+
+    impl TransactionInterceptor for ThresholdInterceptor {
+        fn before(
+            &mut self,
+            tx: &Transaction,
+            account: Option<&ClientAccount>,
+        ) -> InterceptDecision {
+            match tx.transaction_type {
+                TransactionType::Withdrawal if tx.amount > Some(self.0) => {
+                    assert!(account.is_some(), "withdrawals here follow a deposit");
+                    InterceptDecision::Reject
+                }
+                TransactionType::Deposit if tx.amount > Some(self.0) => {
+                    InterceptDecision::Modify(Transaction::deposit(tx.client, tx.tx, self.0))
+                }
+                _ => InterceptDecision::Accept,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interceptor_rejects_large_withdrawals() {
+        let collector = RejectionCollector::new();
+        let engine = PaymentsEngine::new()
+            .with_interceptor(ThresholdInterceptor(Decimal::from_f64(50.0)))
+            .with_rejection_sink(collector.clone());
+        let accounts = engine
+            .process_stream(futures::stream::iter([
+                deposit(1, 1, 40.0),
+                deposit(1, 2, 80.0), // Capped to 50.0
+                withdrawal(1, 3, 60.0),
+                withdrawal(1, 4, 50.0),
+            ]))
+            .await
+            .unwrap();
+
+        assert_account(&accounts, 1, 40.0, 0.0, 40.0, false);
+        assert_eq!(
+            collector.rejections(),
+            vec![RejectedTransaction {
+                tx: withdrawal(1, 3, 60.0),
+                reason: RejectionReason::Intercepted,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_noop_interceptor_changes_nothing() {
+        let transactions = vec![deposit(1, 1, 10.0), withdrawal(1, 2, 4.0), dispute(1, 1)];
+        let accounts = PaymentsEngine::builder()
+            .interceptor(crate::NoopInterceptor)
+            .build()
+            .process_stream(futures::stream::iter(transactions.clone()))
+            .await
+            .unwrap();
+        assert_eq!(accounts, process_transactions_vec(transactions).await);
+    }
+
+    // ========== Resource Limit Tests ==========
+
+    async fn process_with_limits(
+        limits: ResourceLimits,
+        transactions: Vec<Transaction>,
+    ) -> Result<BTreeMap<ClientId, ClientAccount>, EngineError> {
+        PaymentsEngine::builder()
+            .resource_limits(limits)
+            .build()
+            .process_stream(futures::stream::iter(transactions))
+            .await
+    }
+
+    fn limit_exceeded(limit: ResourceLimit, tx: TransactionId) -> EngineError {
+        EngineError::LimitExceeded { limit, tx }
+    }
+
+    #[tokio::test]
+    async fn test_max_rows_limit() {
+        let limits = ResourceLimits {
+            max_rows: Some(2),
+            ..ResourceLimits::default()
+        };
+        let within = vec![deposit(1, 1, 1.0), dispute(1, 9)]; // Rejected rows count too
+        assert!(process_with_limits(limits, within.clone()).await.is_ok());
+
+        let mut over = within;
+        over.push(deposit(1, 3, 1.0));
+        assert_eq!(
+            process_with_limits(limits, over).await,
+            Err(limit_exceeded(ResourceLimit::Rows, 3))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_accounts_limit() {
+        let limits = ResourceLimits {
+            max_accounts: Some(2),
+            ..ResourceLimits::default()
+        };
+        let result = process_with_limits(
+            limits,
+            vec![
+                deposit(1, 1, 1.0),
+                deposit(2, 2, 1.0),
+                deposit(1, 3, 1.0),
+                deposit(3, 4, 1.0),
+            ],
+        )
+        .await;
+        assert_eq!(result, Err(limit_exceeded(ResourceLimit::Accounts, 4)));
+    }
+
+    #[tokio::test]
+    async fn test_max_disputes_per_account_limit() {
+        let limits = ResourceLimits {
+            max_disputes_per_account: Some(1),
+            ..ResourceLimits::default()
+        };
+        let result = process_with_limits(
+            limits,
+            vec![
+                deposit(1, 1, 1.0),
+                deposit(1, 2, 1.0),
+                dispute(1, 1),
+                resolve(1, 1), // Settled disputes still count
+                dispute(1, 2),
+            ],
+        )
+        .await;
+        assert_eq!(
+            result,
+            Err(limit_exceeded(ResourceLimit::DisputesPerAccount, 2))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_ledger_per_account_limit() {
+        let limits = ResourceLimits {
+            max_ledger_per_account: Some(2),
+            ..ResourceLimits::default()
+        };
+        let result = process_with_limits(
+            limits,
+            vec![
+                deposit(1, 1, 5.0),
+                withdrawal(1, 2, 1.0),
+                deposit(2, 3, 1.0), // Limit is per account
+                withdrawal(1, 4, 1.0),
+            ],
+        )
+        .await;
+        assert_eq!(
+            result,
+            Err(limit_exceeded(ResourceLimit::LedgerPerAccount, 4))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_amount_limit() {
+        let limits = ResourceLimits {
+            max_amount: Some(Decimal::from_f64(100.0)),
+            ..ResourceLimits::default()
+        };
+        assert!(
+            process_with_limits(limits, vec![deposit(1, 1, 100.0)])
+                .await
+                .is_ok()
+        );
+        assert_eq!(
+            process_with_limits(
+                limits,
+                vec![deposit(1, 1, 100.0), withdrawal(1, 2, 100.0001)]
+            )
+            .await,
+            Err(limit_exceeded(ResourceLimit::Amount, 2))
+        );
+        let error = process_with_limits(limits, vec![deposit(1, 7, -500.0)])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "aborted on transaction ID 7: exceeded max amount"
+        );
+
+        // A full withdrawal is checked against the balance it would sweep
+        let sweep = |available| {
+            process_with_limits(
+                limits,
+                vec![
+                    deposit(1, 1, 100.0),
+                    deposit(1, 2, available - 100.0),
+                    Transaction::withdraw_all(1, 3),
+                ],
+            )
+        };
+        assert!(sweep(100.0).await.is_ok());
+        assert_eq!(
+            sweep(150.0).await,
+            Err(limit_exceeded(ResourceLimit::Amount, 3))
+        );
+    }
+
+    // ========== Locked Set Tests ==========
+
+    #[tokio::test]
+    async fn test_locked_set_tracks_locks_and_unlocks() {
+        let seeded = BTreeMap::from([(
+            9,
+            ClientAccount::with_balances(9, Decimal::ZERO, Decimal::ZERO, true),
+        )]);
+        let engine = PaymentsEngine::new().with_accounts(seeded);
+        let locked = engine.locked_set();
+        let sender = engine.sender();
+        let handle = engine.serve().await;
+
+        for tx in [
+            deposit(1, 1, 10.0),
+            deposit(2, 2, 10.0),
+            dispute(1, 1),
+            chargeback(1, 1),
+            dispute(2, 2),
+            chargeback(2, 2),
+        ] {
+            sender.send(tx).await.unwrap();
+        }
+        // Let the engine catch up before reading the live set
+        while locked.len() < 3 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(locked.clients(), vec![1, 2, 9]);
+
+        sender.send(unlock(1, 3)).await.unwrap();
+        sender.send(unlock(9, 4)).await.unwrap();
+        drop(sender);
+        let accounts = handle.await.unwrap().unwrap();
+
+        assert_eq!(locked.clients(), vec![2]);
+        assert!(locked.contains(2) && !locked.contains(1));
+        assert_eq!(locked.clients(), crate::locked_accounts(&accounts));
+    }
+
+    fn lock_after_failed_withdrawals(max: u32) -> PaymentsEngine {
+        PaymentsEngine::new().with_account_policy(AccountPolicy {
+            lock_after_failed_withdrawals: Some(max),
+            ..AccountPolicy::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_lock_after_failed_withdrawals_threshold_hit() {
+        let engine = lock_after_failed_withdrawals(3);
+        let locked = engine.locked_set();
+        let (accounts, rejections, _) = engine
+            .process_all_detailed([
+                deposit(1, 1, 5.0),
+                withdrawal(1, 2, 6.0),
+                withdrawal(1, 3, 7.0),
+                withdrawal(1, 4, 8.0), // Third in a row: locks
+                deposit(1, 5, 1.0),
+            ])
+            .await
+            .unwrap();
+
+        assert_account(&accounts, 1, 5.0, 0.0, 5.0, true);
+        assert_eq!(
+            rejections.last().map(|r| (r.tx.tx, r.reason)),
+            Some((5, RejectionReason::AccountLocked))
+        );
+        assert_eq!(locked.clients(), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_lock_after_failed_withdrawals_reset_by_success() {
+        let accounts = lock_after_failed_withdrawals(3)
+            .process_stream(futures::stream::iter([
+                deposit(1, 1, 5.0),
+                withdrawal(1, 2, 6.0),
+                withdrawal(1, 3, 7.0),
+                withdrawal(1, 4, 1.0), // Succeeds: resets the count
+                withdrawal(1, 5, 6.0),
+                withdrawal(1, 6, 7.0),
+                deposit(2, 7, 1.0),
+                withdrawal(2, 8, 2.0), // Counted per account
+            ]))
+            .await
+            .unwrap();
+
+        assert_account(&accounts, 1, 4.0, 0.0, 4.0, false);
+        assert_account(&accounts, 2, 1.0, 0.0, 1.0, false);
+    }
+
+    // ========== Account Store Tests ==========
+
+    /// Store that counts every lookup made through it.
+    #[derive(Default)]
+    struct CountingStore {
+        accounts: BTreeMap<ClientId, ClientAccount>,
+        lookups: usize,
+        inserts: usize,
+    }
+
+    impl AccountStore for CountingStore {
+        fn get(&self, client: ClientId) -> Option<&ClientAccount> {
+            self.accounts.get(&client)
+        }
+
+        fn get_mut(&mut self, client: ClientId) -> Option<&mut ClientAccount> {
+            self.lookups += 1;
+            self.accounts.get_mut(&client)
+        }
+
+        fn insert(&mut self, account: ClientAccount) {
+            self.inserts += 1;
+            self.accounts.insert(account.client_id, account);
+        }
+
+        fn len(&self) -> usize {
+            self.accounts.len()
+        }
+
+        fn iter(&self) -> Box<dyn Iterator<Item = &ClientAccount> + '_> {
+            Box::new(self.accounts.values())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_account_store_matches_default() {
+        let transactions = vec![
+            deposit(1, 1, 10.0),
+            deposit(2, 2, 5.0),
+            withdrawal(1, 3, 4.0),
+            dispute(2, 2),
+            chargeback(2, 2),
+            deposit(3, 4, 1.0),
+        ];
+        let seeded = BTreeMap::from([(9, ClientAccount::new(9))]);
+
+        let engine = PaymentsEngine::new().with_accounts(seeded.clone());
+        let sender = engine.sender();
+        let handle = engine.serve_with_store(CountingStore::default()).await;
+        for tx in transactions.clone() {
+            sender.send(tx).await.unwrap();
+        }
+        drop(sender);
+        let store = handle.await.unwrap().unwrap();
+
+        let engine = PaymentsEngine::new().with_accounts(seeded);
+        let sender = engine.sender();
+        let handle = engine.serve().await;
+        for tx in transactions {
+            sender.send(tx).await.unwrap();
+        }
+        drop(sender);
+        assert_eq!(store.accounts, handle.await.unwrap().unwrap());
+
+        // One seeded plus three created through the default get_or_insert_with
+        assert_eq!(store.inserts, 4);
+        assert!(store.lookups >= 6, "every transaction reached the store");
+    }
+
+    // ========== Sequence Tests ==========
+
+    #[tokio::test]
+    async fn test_sequence_numbers_across_interleaved_clients() {
+        let seeded = BTreeMap::from([(9, ClientAccount::new(9))]);
+        let engine = PaymentsEngine::new().with_accounts(seeded);
+        let sender = engine.sender();
+        let handle = engine.serve().await;
+        for tx in [
+            deposit(1, 1, 10.0),    // 1: creates client 1
+            deposit(2, 2, 5.0),     // 2: creates client 2
+            withdrawal(1, 3, 4.0),  // 3
+            withdrawal(2, 4, 50.0), // 4: rejected, client 2 unchanged
+            deposit(3, 5, 1.0),     // 5: creates client 3
+            dispute(1, 1),          // 6
+            dispute(2, 99),         // 7: rejected
+        ] {
+            sender.send(tx).await.unwrap();
+        }
+        drop(sender);
+        let accounts = handle.await.unwrap().unwrap();
+
+        let seqs = |client| {
+            (
+                accounts[&client].created_seq(),
+                accounts[&client].last_seq(),
+            )
+        };
+        assert_eq!(seqs(1), (1, 6));
+        assert_eq!(seqs(2), (2, 2));
+        assert_eq!(seqs(3), (5, 5));
+        // Seeded accounts predate the engine and were never touched
+        assert_eq!(seqs(9), (0, 0));
+    }
+
+    // ========== Audit Replay Tests ==========
+
+    #[tokio::test]
+    async fn test_replay_audit_reproduces_engine_output() {
+        let clock = MockClock::new(0);
+        let log = crate::AuditLog::new();
+        let engine = PaymentsEngine::builder()
+            .clock(clock)
+            .audit_sink(log.clone())
+            .interceptor(ThresholdInterceptor(Decimal::from_f64(50.0)))
+            .build();
+        let accounts = engine
+            .process_stream(futures::stream::iter([
+                deposit(1, 1, 40.0),
+                deposit(1, 2, 80.0),    // Modified to 50.0
+                withdrawal(1, 3, 60.0), // Intercepted
+                withdrawal(2, 4, 5.0),  // Insufficient funds, but claims tx 4
+                deposit(2, 4, 5.0),     // Duplicate
+                deposit(2, 5, 30.0),
+                dispute(2, 5),
+                deposit(3, 6, 10.0),
+                dispute(3, 6),
+                chargeback(3, 6),
+                Transaction::hold(1, 7, Decimal::from_f64(20.0)),
+                dispute(1, 1),
+                resolve(1, 1),
+            ]))
+            .await
+            .unwrap();
+
+        let events = log.events();
+        assert_eq!(events.len(), 13);
+        assert_eq!(events[1].tx.amount, Some(Decimal::from_f64(50.0)));
+        assert_eq!(events[2].rejection, Some(RejectionReason::Intercepted));
+
+        let replayed = crate::replay_audit(&events);
+        assert_eq!(replayed, accounts);
+        assert_eq!(
+            crate::accounts_digest(&replayed),
+            crate::accounts_digest(&accounts)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_audit_uses_recorded_tx_id_scope() {
+        // Two clients reusing one tx ID: a duplicate only under the global scope
+        let txs = [deposit(1, 5, 10.0), deposit(2, 5, 20.0)];
+        for scope in [TxIdScope::Global, TxIdScope::PerClient] {
+            let log = crate::AuditLog::new();
+            let accounts = PaymentsEngine::builder()
+                .tx_id_scope(scope)
+                .audit_sink(log.clone())
+                .build()
+                .process_stream(futures::stream::iter(txs.clone()))
+                .await
+                .unwrap();
+            let expected_clients = match scope {
+                TxIdScope::Global => vec![1],
+                TxIdScope::PerClient => vec![1, 2],
+            };
+            assert_eq!(
+                accounts.keys().copied().collect::<Vec<_>>(),
+                expected_clients
+            );
+            assert_eq!(crate::replay_audit(&log.events()), accounts, "{:?}", scope);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_client_matches_engine() {
+        let engine = PaymentsEngine::builder().retain_history(true).build();
+        let history = engine.history().unwrap();
+        let accounts = engine
+            .process_stream(futures::stream::iter([
+                deposit(1, 1, 40.0),
+                deposit(2, 2, 25.0),
+                withdrawal(1, 3, 15.0),
+                withdrawal(2, 4, 30.0), // Insufficient funds
+                deposit(1, 5, 10.0),
+                dispute(1, 5),
+                dispute(2, 2),
+                chargeback(2, 2),
+                withdrawal(1, 6, 100.0), // Insufficient funds
+                dispute(1, 1),
+                resolve(1, 1),
+                deposit(3, 1, 5.0), // Duplicate of client 1's tx ID
+            ]))
+            .await
+            .unwrap();
+
+        assert_eq!(history.clients(), vec![1, 2]);
+        assert!(!accounts.contains_key(&3));
+        assert_eq!(
+            crate::replay_client(3, history.for_client(3)),
+            ClientAccount::new(3)
+        );
+        assert_eq!(history.for_client(1).len(), 7);
+        for client in [1, 2] {
+            let replayed = crate::replay_client(client, history.for_client(client));
+            assert_eq!(replayed, accounts[&client]);
+        }
+        assert!(PaymentsEngine::new().history().is_none());
+    }
+
+    // ========== Order Hold Tests ==========
+
+    #[tokio::test]
+    async fn test_hold_and_release_through_engine() {
+        let accounts = process_transactions_vec(vec![
+            deposit(1, 1, 10.0),
+            Transaction::hold(1, 2, Decimal::from_f64(6.0)),
+            withdrawal(1, 3, 5.0), // Rejected - only 4.0 available
+            Transaction::release(1, 2),
+            withdrawal(1, 4, 5.0),
+            Transaction::hold(1, 4, Decimal::from_f64(1.0)), // Duplicate tx ID
+        ])
+        .await;
+        assert_account(&accounts, 1, 5.0, 0.0, 5.0, false);
+    }
+
+    // ========== Unlock Account Tests ==========
+
+    #[tokio::test]
+    async fn test_unlock_account_after_chargeback() {
+        let accounts = process_transactions_vec(vec![
+            deposit(1, 1, 10.0),
+            deposit(1, 2, 4.0),
+            dispute(1, 1),
+            chargeback(1, 1),
+            deposit(1, 3, 1.0), // Rejected - locked
+            unlock(1, 4),
+            deposit(1, 5, 2.0),
+        ])
+        .await;
+        assert_account(&accounts, 1, 6.0, 0.0, 6.0, false);
+    }
+
+    #[tokio::test]
+    async fn test_unlock_with_open_dispute_rejected() {
+        let rejections = process_with_collector(vec![
+            deposit(1, 1, 10.0),
+            deposit(1, 2, 4.0),
+            dispute(1, 2),
+            dispute(1, 1),
+            chargeback(1, 1),
+            unlock(1, 3),
+            unlock(2, 4), // No such account
+        ])
+        .await;
+
+        assert_eq!(
+            rejections,
+            vec![
+                RejectedTransaction {
+                    tx: unlock(1, 3),
+                    reason: RejectionReason::OpenDisputes,
+                },
+                RejectedTransaction {
+                    tx: unlock(2, 4),
+                    reason: RejectionReason::UnknownAccount,
+                },
+            ]
+        );
+    }
+
+    // ========== Close Account Tests ==========
+
+    #[tokio::test]
+    async fn test_close_account() {
+        let accounts = process_transactions_vec(vec![
+            deposit(1, 1, 10.0),
+            withdrawal(1, 2, 10.0),
+            close(1, 3),
+            deposit(1, 4, 5.0), // Rejected - account closed
+        ])
+        .await;
+        assert_account(&accounts, 1, 0.0, 0.0, 0.0, false);
+        assert!(accounts[&1].is_closed());
+    }
+
+    #[tokio::test]
+    async fn test_close_with_open_dispute_rejected() {
+        let accounts =
+            process_transactions_vec(vec![deposit(1, 1, 10.0), dispute(1, 1), close(1, 2)]).await;
+        assert_account(&accounts, 1, 0.0, 10.0, 10.0, false);
+        assert!(!accounts[&1].is_closed());
+    }
+
+    #[tokio::test]
+    async fn test_close_nonexistent_account_ignored() {
+        let accounts = process_transactions_vec(vec![close(1, 1)]).await;
+        assert_eq!(accounts.len(), 0);
+    }
+
+    // ========== Tx ID Scope Tests ==========
+
+    async fn process_with_scope(
+        scope: TxIdScope,
+        transactions: Vec<Transaction>,
+    ) -> BTreeMap<ClientId, ClientAccount> {
+        let engine = PaymentsEngine::new().with_tx_id_scope(scope);
+        let sender = engine.sender();
+        let handle = engine.serve().await;
+        for tx in transactions {
+            sender.send(tx).await.unwrap();
+        }
+        drop(sender);
+        handle.await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_global_scope_rejects_same_tx_id_different_client() {
+        let accounts = process_with_scope(
+            TxIdScope::Global,
+            vec![deposit(1, 1, 10.0), deposit(2, 1, 20.0)],
+        )
+        .await;
+        assert_eq!(accounts.len(), 1);
+        assert_account(&accounts, 1, 10.0, 0.0, 10.0, false);
+    }
+
+    #[tokio::test]
+    async fn test_per_client_scope_allows_same_tx_id_different_client() {
+        let accounts = process_with_scope(
+            TxIdScope::PerClient,
+            vec![deposit(1, 1, 10.0), deposit(2, 1, 20.0)],
+        )
+        .await;
+        assert_eq!(accounts.len(), 2);
+        assert_account(&accounts, 1, 10.0, 0.0, 10.0, false);
+        assert_account(&accounts, 2, 20.0, 0.0, 20.0, false);
+    }
+
+    #[tokio::test]
+    async fn test_per_client_scope_disputes_match_own_transaction() {
+        let accounts = process_with_scope(
+            TxIdScope::PerClient,
+            vec![
+                deposit(1, 7, 10.0),
+                deposit(2, 7, 20.0),
+                dispute(1, 7),
+                dispute(2, 7),
+                resolve(1, 7),
+                chargeback(2, 7),
+            ],
+        )
+        .await;
+        // Each client's rows settled its own tx 7, not the other's
+        assert_account(&accounts, 1, 10.0, 0.0, 10.0, false);
+        assert_account(&accounts, 2, 0.0, 0.0, 0.0, true);
+    }
+
+    #[tokio::test]
+    async fn test_disputes_never_match_another_clients_transaction() {
+        for scope in [TxIdScope::Global, TxIdScope::PerClient] {
+            let accounts = process_with_scope(
+                scope,
+                vec![
+                    deposit(1, 7, 10.0),
+                    deposit(2, 8, 20.0),
+                    // Client 2 references client 1's tx 7
+                    dispute(2, 7),
+                    chargeback(2, 7),
+                ],
+            )
+            .await;
+            assert_account(&accounts, 1, 10.0, 0.0, 10.0, false);
+            assert_account(&accounts, 2, 20.0, 0.0, 20.0, false);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_per_client_scope_rejects_same_tx_id_same_client() {
+        let accounts = process_with_scope(
+            TxIdScope::PerClient,
+            vec![deposit(1, 1, 10.0), deposit(1, 1, 50.0)],
+        )
+        .await;
+        assert_account(&accounts, 1, 10.0, 0.0, 10.0, false);
+    }
+
+    // ========== Negative Balance Edge Cases ==========
+
+    #[tokio::test]
+    async fn test_dispute_after_partial_spend() {
+        // Dispute a deposit after some funds were already withdrawn
+        // This causes available to go negative (client owes money during dispute)
+        let accounts = process_transactions_vec(vec![
+            deposit(1, 1, 10.0),
+            withdrawal(1, 2, 7.0),
+            dispute(1, 1),
         ])
         .await;
         assert_account(&accounts, 1, -7.0, 10.0, 3.0, false);
     }
 
+    #[tokio::test]
+    async fn test_dispute_after_partial_spend_rejected_by_policy() {
+        let collector = RejectionCollector::new();
+        let engine = PaymentsEngine::builder()
+            .account_policy(AccountPolicy {
+                allow_negative_available_on_dispute: false,
+                ..AccountPolicy::default()
+            })
+            .rejection_sink(collector.clone())
+            .build();
+        let accounts = engine
+            .process_stream(futures::stream::iter([
+                deposit(1, 1, 10.0),
+                withdrawal(1, 2, 7.0),
+                dispute(1, 1),
+            ]))
+            .await
+            .unwrap();
+
+        assert_account(&accounts, 1, 3.0, 0.0, 3.0, false);
+        assert_eq!(
+            collector.rejections()[0].reason,
+            RejectionReason::DisputeExceedsAvailable
+        );
+    }
+
     #[tokio::test]
     async fn test_resolve_after_partial_spend() {
         let accounts = process_transactions_vec(vec![
@@ -576,11 +2473,129 @@ mod tests {
             deposit(2, 7, 1000.0), // Ignored - locked
         ])
         .await;
-        assert_eq!(accounts.len(), 2);
-        // Client 1: 100 - 25 + 50 = 125
-        assert_account(&accounts, 1, 125.0, 0.0, 125.0, false);
-        // Client 2: 200 + 50 - 100 - 200(chargeback) = -50
-        assert_account(&accounts, 2, -50.0, 0.0, -50.0, true);
+        assert_eq!(accounts.len(), 2);
+        // Client 1: 100 - 25 + 50 = 125
+        assert_account(&accounts, 1, 125.0, 0.0, 125.0, false);
+        // Client 2: 200 + 50 - 100 - 200(chargeback) = -50
+        assert_account(&accounts, 2, -50.0, 0.0, -50.0, true);
+    }
+
+    // ========== Conservation Tests ==========
+
+    async fn process_with_stats(
+        engine: PaymentsEngine,
+        transactions: Vec<Transaction>,
+    ) -> (BTreeMap<ClientId, ClientAccount>, ProcessStats) {
+        let stats = engine.stats();
+        let sender = engine.sender();
+        let handle = engine.serve().await;
+        for tx in transactions {
+            sender.send(tx).await.unwrap();
+        }
+        drop(sender);
+        (handle.await.unwrap().unwrap(), stats.snapshot())
+    }
+
+    #[tokio::test]
+    async fn test_conservation_holds_for_complex_scenario() {
+        let seeded = BTreeMap::from([(
+            3,
+            ClientAccount::with_balances(3, Decimal::from_f64(40.0), Decimal::ZERO, false),
+        )]);
+        let (accounts, stats) = process_with_stats(
+            PaymentsEngine::new().with_accounts(seeded),
+            vec![
+                deposit(1, 1, 100.0),
+                deposit(2, 2, 200.0),
+                withdrawal(1, 3, 25.0),
+                deposit(1, 4, 50.0),
+                dispute(1, 1),
+                deposit(2, 5, 50.0),
+                resolve(1, 1),
+                withdrawal(2, 6, 100.0),
+                dispute(2, 2),
+                chargeback(2, 2),
+                deposit(2, 7, 1000.0),    // Ignored - locked
+                withdrawal(1, 8, 1000.0), // Ignored - insufficient funds
+                Transaction::withdraw_all(3, 9),
+                deposit(3, 10, 5.0),
+                Transaction::close(3, 11), // Sweeps out the 5.0
+            ],
+        )
+        .await;
+
+        assert_eq!(stats.opening_total, Decimal::from_f64(40.0).into());
+        assert_eq!(stats.deposited, Decimal::from_f64(405.0).into());
+        assert_eq!(stats.withdrawn, Decimal::from_f64(170.0).into());
+        assert_eq!(stats.charged_back, Decimal::from_f64(200.0).into());
+        assert_eq!(verify_conservation(&accounts, &stats), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_conservation_flags_corrupted_account() {
+        let (mut accounts, stats) = process_with_stats(
+            PaymentsEngine::new(),
+            vec![deposit(1, 1, 100.0), deposit(2, 2, 20.0), dispute(2, 2)],
+        )
+        .await;
+        assert!(verify_conservation(&accounts, &stats).is_ok());
+
+        // Funds conjured outside the engine; the per-account invariant still holds
+        let account = accounts.get_mut(&2).unwrap();
+        account.available += Decimal::from_f64(0.5);
+        account.recompute_total();
+        let error = verify_conservation(&accounts, &stats).unwrap_err();
+        assert_eq!(
+            error,
+            ConservationError {
+                expected: Decimal::from_f64(120.0).into(),
+                actual: Decimal::from_f64(120.5).into(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "funds not conserved: accounts total 120.5, expected 120"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stats_sum_past_decimal_range() {
+        // Each deposit fits in a Decimal, but their sum does not
+        let (accounts, stats) = process_with_stats(
+            PaymentsEngine::new(),
+            vec![
+                deposit(1, 1, 500_000_000_000_000.0),
+                deposit(2, 2, 500_000_000_000_000.0),
+            ],
+        )
+        .await;
+        assert_eq!(
+            stats.deposited,
+            DecimalWide(2 * Decimal::from_f64(500_000_000_000_000.0).0 as i128)
+        );
+        assert_eq!(verify_conservation(&accounts, &stats), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_conservation_counts_clamped_chargeback_write_off() {
+        let policy = AccountPolicy {
+            negative_total: crate::NegativeTotalPolicy::ClampZero,
+            ..AccountPolicy::default()
+        };
+        let (accounts, stats) = process_with_stats(
+            PaymentsEngine::new().with_account_policy(policy),
+            vec![
+                deposit(1, 1, 100.0),
+                withdrawal(1, 2, 70.0),
+                dispute(1, 1),
+                chargeback(1, 1), // 30.0 remained, so 70.0 is written off
+            ],
+        )
+        .await;
+        assert_account(&accounts, 1, 0.0, 0.0, 0.0, true);
+        assert_eq!(stats.charged_back, Decimal::from_f64(100.0).into());
+        assert_eq!(stats.written_off, Decimal::from_f64(70.0).into());
+        assert_eq!(verify_conservation(&accounts, &stats), Ok(()));
     }
 
     #[tokio::test]
@@ -701,4 +2716,706 @@ mod tests {
         // If code allows chargeback after resolve, total would be 20 and account locked
         assert_account(&accounts, 1, 30.0, 0.0, 30.0, false);
     }
+
+    // ========== Unbounded Channel Tests ==========
+
+    #[tokio::test]
+    async fn test_unbounded_accepts_many_before_serving() {
+        let engine = PaymentsEngine::unbounded();
+        let sender = engine.sender();
+        // Far beyond the bounded buffer; a bounded engine would stall here since
+        // nothing is receiving yet.
+        for tx in 1..=10_000 {
+            sender
+                .send(deposit(1 + (tx % 3) as u16, tx, 0.5))
+                .await
+                .unwrap();
+        }
+        sender.send(withdrawal(1, 10_001, 1.0)).await.unwrap();
+        drop(sender);
+
+        let accounts = engine.serve().await.await.unwrap().unwrap();
+
+        // 10,000 deposits of 0.5 split 3333 / 3334 / 3333 across clients 1-3
+        assert_account(&accounts, 1, 1665.5, 0.0, 1665.5, false);
+        assert_account(&accounts, 2, 1667.0, 0.0, 1667.0, false);
+        assert_account(&accounts, 3, 1666.5, 0.0, 1666.5, false);
+    }
+
+    #[tokio::test]
+    async fn test_unbounded_matches_bounded() {
+        let transactions = vec![
+            deposit(1, 1, 10.0),
+            deposit(2, 2, 5.0),
+            withdrawal(1, 3, 4.0),
+            dispute(2, 2),
+            chargeback(2, 2),
+            deposit(2, 4, 1.0),
+        ];
+
+        let engine = PaymentsEngine::unbounded();
+        let sender = engine.sender();
+        let handle = engine.serve().await;
+        for tx in transactions.clone() {
+            sender.send(tx).await.unwrap();
+        }
+        drop(sender);
+
+        assert_eq!(
+            handle.await.unwrap().unwrap(),
+            process_transactions_vec(transactions).await
+        );
+    }
+
+    // ========== Duplicate Policy Tests ==========
+
+    async fn process_with_duplicate_policy(
+        policy: DuplicatePolicy,
+        transactions: Vec<Transaction>,
+    ) -> Result<BTreeMap<ClientId, ClientAccount>, EngineError> {
+        let engine = PaymentsEngine::new().with_duplicate_policy(policy);
+        let sender = engine.sender();
+        let handle = engine.serve().await;
+        for tx in transactions {
+            if sender.send(tx).await.is_err() {
+                break; // Engine aborted
+            }
+        }
+        drop(sender);
+        handle.await.unwrap()
+    }
+
+    fn transactions_with_duplicate() -> Vec<Transaction> {
+        vec![
+            deposit(1, 1, 10.0),
+            deposit(2, 1, 5.0), // Duplicate tx ID
+            deposit(1, 2, 3.0),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_policy_skip() {
+        let accounts =
+            process_with_duplicate_policy(DuplicatePolicy::Skip, transactions_with_duplicate())
+                .await
+                .unwrap();
+
+        assert_account(&accounts, 1, 13.0, 0.0, 13.0, false);
+        assert!(!accounts.contains_key(&2));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_policy_abort() {
+        let result =
+            process_with_duplicate_policy(DuplicatePolicy::Abort, transactions_with_duplicate())
+                .await;
+
+        assert_eq!(result, Err(EngineError::DuplicateTransaction(1)));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_policy_abort_without_duplicates() {
+        let accounts = process_with_duplicate_policy(
+            DuplicatePolicy::Abort,
+            vec![deposit(1, 1, 10.0), withdrawal(1, 2, 20.0)],
+        )
+        .await
+        .unwrap();
+
+        // Ordinary rejections do not abort
+        assert_account(&accounts, 1, 10.0, 0.0, 10.0, false);
+    }
+
+    // ========== Stream Input Tests ==========
+
+    #[tokio::test]
+    async fn test_process_stream() {
+        let transactions = vec![
+            deposit(1, 1, 10.0),
+            deposit(2, 2, 4.0),
+            withdrawal(1, 3, 2.5),
+            dispute(2, 2),
+        ];
+        let accounts = PaymentsEngine::new()
+            .process_stream(futures::stream::iter(transactions.clone()))
+            .await
+            .unwrap();
+
+        assert_account(&accounts, 1, 7.5, 0.0, 7.5, false);
+        assert_account(&accounts, 2, 0.0, 4.0, 4.0, false);
+        assert_eq!(accounts, process_transactions_vec(transactions).await);
+    }
+
+    #[tokio::test]
+    async fn test_process_stream_larger_than_buffer() {
+        let stream = futures::stream::iter(1..=1000).map(|tx| deposit(1, tx, 0.1));
+        let accounts = PaymentsEngine::new().process_stream(stream).await.unwrap();
+
+        assert_account(&accounts, 1, 100.0, 0.0, 100.0, false);
+    }
+
+    #[tokio::test]
+    async fn test_process_stream_abort() {
+        let stream = futures::stream::iter(vec![deposit(1, 1, 1.0), deposit(1, 1, 1.0)]);
+        let result = PaymentsEngine::new()
+            .with_duplicate_policy(DuplicatePolicy::Abort)
+            .process_stream(stream)
+            .await;
+
+        assert_eq!(result, Err(EngineError::DuplicateTransaction(1)));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "interceptor failed")]
+    async fn test_process_stream_resumes_engine_panic() {
+        struct PanickingInterceptor;
+
+        impl TransactionInterceptor for PanickingInterceptor {
+            fn before(&mut self, _: &Transaction, _: Option<&ClientAccount>) -> InterceptDecision {
+                panic!("interceptor failed");
+            }
+        }
+
+        let _ = PaymentsEngine::new()
+            .with_interceptor(PanickingInterceptor)
+            .process_stream(futures::stream::iter([deposit(1, 1, 1.0)]))
+            .await;
+    }
+
+    async fn saved_state(txs: Vec<Transaction>, encoding: TxIdEncoding) -> Vec<u8> {
+        let engine = PaymentsEngine::new();
+        let sender = engine.sender();
+        let handle = engine.serve_state().await;
+        for tx in txs {
+            sender.send(tx).await.unwrap();
+        }
+        drop(sender);
+        let state = handle.await.unwrap().unwrap();
+        let mut bytes = Vec::new();
+        state.save_state(&mut bytes, encoding).unwrap();
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_state_round_trip_exact_tx_ids() {
+        let bytes = saved_state(
+            vec![
+                deposit(1, 1, 10.0),
+                withdrawal(1, 2, 4.0),
+                deposit(2, 3, 1.0),
+            ],
+            TxIdEncoding::Exact,
+        )
+        .await;
+        let state = EngineState::load_state(bytes.as_slice()).unwrap();
+        assert!(state.is_processed(1, 1) && state.is_processed(2, 3));
+        assert!(!state.is_processed(1, 4));
+        assert_account(&state.accounts, 1, 6.0, 0.0, 6.0, false);
+
+        let (accounts, rejections, _) = PaymentsEngine::new()
+            .with_state(state)
+            .process_all_detailed([deposit(1, 2, 5.0), deposit(1, 4, 5.0)])
+            .await
+            .unwrap();
+        assert_account(&accounts, 1, 11.0, 0.0, 11.0, false);
+        assert_account(&accounts, 2, 1.0, 0.0, 1.0, false);
+        let reasons: Vec<_> = rejections.iter().map(|r| (r.tx.tx, r.reason)).collect();
+        assert_eq!(reasons, vec![(2, RejectionReason::DuplicateTransaction)]);
+
+        assert!(EngineState::load_state(&b"not a state"[..]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_state_round_trip_bloom_tx_ids() {
+        // Sparse IDs, which cost the exact encoding several bytes each.
+        let ids: Vec<TransactionId> = (1..=2000).map(|i| i * 1_000_003).collect();
+        let txs: Vec<_> = ids.iter().map(|&tx| deposit(1, tx, 1.0)).collect();
+        let rate = 0.01;
+        let exact = saved_state(txs.clone(), TxIdEncoding::Exact).await;
+        let bloom = saved_state(
+            txs,
+            TxIdEncoding::Bloom {
+                false_positive_rate: rate,
+            },
+        )
+        .await;
+        assert!(bloom.len() < exact.len());
+
+        let state = EngineState::load_state(bloom.as_slice()).unwrap();
+        assert!(ids.iter().all(|&tx| state.is_processed(1, tx)));
+        let false_positives = (1..=10_000)
+            .filter(|&i| state.is_processed(1, i * 1_000_003 + 1))
+            .count();
+        assert!(false_positives < 300, "{false_positives} false positives");
+
+        // Reloaded bloom state saves as a bloom filter again.
+        let mut resaved = Vec::new();
+        state.save_state(&mut resaved, TxIdEncoding::Exact).unwrap();
+        assert_eq!(resaved, bloom);
+
+        let (accounts, rejections, _) = PaymentsEngine::new()
+            .with_state(state)
+            .process_all_detailed([deposit(1, ids[7], 1.0)])
+            .await
+            .unwrap();
+        assert_account(&accounts, 1, 2000.0, 0.0, 2000.0, false);
+        assert_eq!(rejections[0].reason, RejectionReason::DuplicateTransaction);
+    }
+
+    #[tokio::test]
+    async fn test_state_round_trip_keeps_closed_accounts_closed() {
+        let bytes = saved_state(
+            vec![deposit(1, 1, 10.0), close(1, 2), deposit(2, 3, 1.0)],
+            TxIdEncoding::Exact,
+        )
+        .await;
+        let state = EngineState::load_state(bytes.as_slice()).unwrap();
+        assert!(state.accounts[&1].is_closed());
+        assert!(!state.accounts[&2].is_closed());
+
+        let (accounts, rejections, _) = PaymentsEngine::new()
+            .with_state(state)
+            .process_all_detailed([deposit(1, 4, 5.0)])
+            .await
+            .unwrap();
+        assert_account(&accounts, 1, 0.0, 0.0, 0.0, false);
+        assert_eq!(rejections[0].reason, RejectionReason::AccountClosed);
+    }
+
+    #[tokio::test]
+    async fn test_state_round_trip_keeps_open_disputes_and_holds() {
+        let partial = |amount| Transaction {
+            amount: Some(Decimal::from_f64(amount)),
+            ..dispute(2, 2)
+        };
+        let bytes = saved_state(
+            vec![
+                deposit(1, 1, 10.0),
+                Transaction::dispute(1, 1).with_reason("fraud"),
+                deposit(2, 2, 30.0),
+                deposit(2, 3, 20.0),
+                partial(5.0),
+                partial(15.0),
+                chargeback(2, 2), // Settles the first claim, and locks the account
+                deposit(3, 5, 8.0),
+                Transaction::hold(3, 6, Decimal::from_f64(3.0)),
+            ],
+            TxIdEncoding::Exact,
+        )
+        .await;
+        let state = EngineState::load_state(bytes.as_slice()).unwrap();
+        assert_eq!(
+            state.accounts[&1].disputes()[&1].reason.as_deref(),
+            Some("fraud")
+        );
+        assert_eq!(
+            state.accounts[&3].reservations()[&6],
+            Decimal::from_f64(3.0)
+        );
+
+        let (accounts, rejections, _) = PaymentsEngine::new()
+            .with_state(state)
+            .process_all_detailed([
+                resolve(1, 1),
+                close(1, 7),
+                chargeback(2, 2), // The second claim is still open
+                Transaction::release(3, 6),
+            ])
+            .await
+            .unwrap();
+        assert!(rejections.is_empty(), "{rejections:?}");
+        assert!(accounts[&1].is_closed());
+        assert_account(&accounts, 2, 30.0, 0.0, 30.0, true);
+        assert_eq!(
+            accounts[&2].disputes()[&2].state,
+            crate::DisputeState::ChargedBack
+        );
+        assert_account(&accounts, 3, 8.0, 0.0, 8.0, false);
+    }
+
+    #[tokio::test]
+    async fn test_process_all_detailed_mixed_input() {
+        let own_sink = RejectionCollector::new();
+        let (accounts, rejections, stats) = PaymentsEngine::new()
+            .with_rejection_sink(own_sink.clone())
+            .process_all_detailed([
+                deposit(1, 1, 10.0),
+                deposit(2, 2, 5.0),
+                withdrawal(1, 3, 12.0), // Insufficient funds
+                withdrawal(1, 4, 3.0),
+                Transaction {
+                    amount: None, // Missing amount
+                    ..deposit(2, 5, 0.0)
+                },
+                dispute(2, 2),
+                chargeback(2, 2),
+                deposit(2, 6, 1.0), // Account locked
+            ])
+            .await
+            .unwrap();
+
+        assert_account(&accounts, 1, 7.0, 0.0, 7.0, false);
+        assert_account(&accounts, 2, 0.0, 0.0, 0.0, true);
+
+        let reasons: Vec<_> = rejections.iter().map(|r| (r.tx.tx, r.reason)).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (3, RejectionReason::InsufficientFunds),
+                (5, RejectionReason::MissingAmount),
+                (6, RejectionReason::AccountLocked),
+            ]
+        );
+        assert_eq!(own_sink.rejections(), rejections);
+
+        assert_eq!(stats.insufficient_funds, 1);
+        assert_eq!(stats.missing_amount, 1);
+        assert_eq!(stats.deposited, Decimal::from_f64(15.0).into());
+        assert_eq!(stats.withdrawn, Decimal::from_f64(3.0).into());
+        assert_eq!(stats.charged_back, Decimal::from_f64(5.0).into());
+    }
+
+    // ========== Reorder Window Tests ==========
+
+    async fn process_with_reorder_window(
+        window: i64,
+        transactions: Vec<Transaction>,
+    ) -> BTreeMap<ClientId, ClientAccount> {
+        PaymentsEngine::new()
+            .with_reorder_window(window)
+            .process_stream(futures::stream::iter(transactions))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reorder_window_applies_in_timestamp_order() {
+        // File order: dispute and withdrawal arrive before the deposit they depend on
+        let transactions = vec![
+            dispute(1, 1).at(30),
+            withdrawal(1, 2, 4.0).at(20),
+            deposit(1, 1, 10.0).at(10),
+            deposit(2, 3, 1.0).at(15),
+        ];
+
+        let accounts = process_with_reorder_window(60, transactions.clone()).await;
+        assert_account(&accounts, 1, -4.0, 10.0, 6.0, false);
+        assert_account(&accounts, 2, 1.0, 0.0, 1.0, false);
+
+        // Without reordering, only the deposits apply
+        let accounts = process_transactions_vec(transactions).await;
+        assert_account(&accounts, 1, 10.0, 0.0, 10.0, false);
+    }
+
+    #[tokio::test]
+    async fn test_reorder_window_too_small_applies_late() {
+        let accounts = process_with_reorder_window(
+            5,
+            vec![
+                dispute(1, 1).at(30),
+                deposit(1, 2, 1.0).at(40), // Releases the dispute before tx 1 arrives
+                deposit(1, 1, 10.0).at(10),
+            ],
+        )
+        .await;
+
+        assert_account(&accounts, 1, 11.0, 0.0, 11.0, false);
+    }
+
+    // ========== Stats Tests ==========
+
+    #[tokio::test]
+    async fn test_stats_count_insufficient_funds() {
+        let engine = PaymentsEngine::new().with_account_policy(AccountPolicy {
+            log_insufficient_funds: true,
+            ..AccountPolicy::default()
+        });
+        let stats = engine.stats();
+        let sender = engine.sender();
+        let handle = engine.serve().await;
+        for tx in [
+            deposit(1, 1, 10.0),
+            withdrawal(1, 2, 15.0), // Over-withdrawal
+            withdrawal(1, 3, 10.0),
+            withdrawal(1, 4, 0.0001), // Over-withdrawal on empty account
+            withdrawal(2, 5, 1.0),    // Over-withdrawal creating the account
+        ] {
+            sender.send(tx).await.unwrap();
+        }
+        drop(sender);
+        let accounts = handle.await.unwrap().unwrap();
+
+        assert_eq!(stats.snapshot().insufficient_funds, 3);
+        assert_account(&accounts, 1, 0.0, 0.0, 0.0, false);
+    }
+
+    #[tokio::test]
+    async fn test_stats_count_insufficient_funds_without_logging() {
+        let engine = PaymentsEngine::new();
+        let stats = engine.stats();
+        let sender = engine.sender();
+        let handle = engine.serve().await;
+        sender.send(withdrawal(1, 1, 5.0)).await.unwrap();
+        sender.send(deposit(1, 2, 5.0)).await.unwrap();
+        drop(sender);
+        handle.await.unwrap().unwrap();
+
+        assert_eq!(stats.snapshot().insufficient_funds, 1);
+    }
+
+    #[tokio::test]
+    async fn test_stats_count_missing_amounts() {
+        // Empty amount field, and the amount column missing entirely
+        let input = "type,client,tx,amount\n\
+                     deposit,1,1,\n\
+                     withdrawal,1,2\n\
+                     deposit,1,3,4.0\n";
+        let transactions: Vec<Transaction> = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(input.as_bytes())
+            .deserialize()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(transactions[0].amount, None);
+        assert_eq!(transactions[1].amount, None);
+
+        let collector = RejectionCollector::new();
+        let engine = PaymentsEngine::new().with_rejection_sink(collector.clone());
+        let stats = engine.stats();
+        let sender = engine.sender();
+        let handle = engine.serve().await;
+        for tx in transactions {
+            sender.send(tx).await.unwrap();
+        }
+        drop(sender);
+        let accounts = handle.await.unwrap().unwrap();
+
+        assert_eq!(stats.snapshot().missing_amount, 2);
+        assert_eq!(stats.snapshot().insufficient_funds, 0);
+        let reasons: Vec<_> = collector.rejections().iter().map(|r| r.reason).collect();
+        assert_eq!(reasons, vec![RejectionReason::MissingAmount; 2]);
+        assert_account(&accounts, 1, 4.0, 0.0, 4.0, false);
+    }
+
+    // ========== Concurrent Producer Tests ==========
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_senders_disjoint_clients() {
+        let engine = PaymentsEngine::new();
+        let mut producers = Vec::new();
+        for client in 1..=8u16 {
+            let sender = engine.sender();
+            producers.push(tokio::spawn(async move {
+                let base = client as TransactionId * 1000;
+                // Order-dependent sequence, safe because it all goes through one sender
+                for tx in [
+                    deposit(client, base + 1, 100.0),
+                    withdrawal(client, base + 2, 30.0),
+                    deposit(client, base + 3, 5.0),
+                    dispute(client, base + 3),
+                    withdrawal(client, base + 4, 1000.0), // Insufficient funds
+                ] {
+                    sender.send(tx).await.unwrap();
+                    tokio::task::yield_now().await;
+                }
+            }));
+        }
+        let handle = engine.serve().await;
+        for producer in producers {
+            producer.await.unwrap();
+        }
+        let accounts = handle.await.unwrap().unwrap();
+
+        assert_eq!(accounts.len(), 8);
+        for client in 1..=8 {
+            assert_account(&accounts, client, 70.0, 5.0, 75.0, false);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_senders_overlapping_clients() {
+        let engine = PaymentsEngine::new();
+        let mut producers = Vec::new();
+        for producer in 0..4 as TransactionId {
+            let sender = engine.sender();
+            producers.push(tokio::spawn(async move {
+                for i in 0..25 {
+                    let tx = producer * 100 + i;
+                    // Every producer touches both clients; deposits commute
+                    sender.send(deposit(1, tx * 2, 1.0)).await.unwrap();
+                    sender.send(deposit(2, tx * 2 + 1, 2.0)).await.unwrap();
+                    tokio::task::yield_now().await;
+                }
+                // Each producer disputes one of its own deposits after sending it
+                sender.send(dispute(1, producer * 200)).await.unwrap();
+            }));
+        }
+        let handle = engine.serve().await;
+        for producer in producers {
+            producer.await.unwrap();
+        }
+        let accounts = handle.await.unwrap().unwrap();
+
+        assert_account(&accounts, 1, 96.0, 4.0, 100.0, false);
+        assert_account(&accounts, 2, 200.0, 0.0, 200.0, false);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_senders_deterministic_across_runs() {
+        async fn run_once() -> BTreeMap<ClientId, ClientAccount> {
+            let engine = PaymentsEngine::new();
+            let mut producers = Vec::new();
+            for client in 1..=4u16 {
+                let sender = engine.sender();
+                producers.push(tokio::spawn(async move {
+                    let base = client as TransactionId * 100;
+                    for i in 0..20 {
+                        sender.send(deposit(client, base + i, 1.5)).await.unwrap();
+                        sender
+                            .send(withdrawal(3, base + 50 + i, 0.5))
+                            .await
+                            .unwrap();
+                    }
+                }));
+            }
+            let handle = engine.serve().await;
+            for producer in producers {
+                producer.await.unwrap();
+            }
+            handle.await.unwrap().unwrap()
+        }
+
+        let first = run_once().await;
+        for _ in 0..5 {
+            // Client 3's withdrawals from other senders race with its own deposits,
+            // so only the clients without cross-sender dependencies are compared.
+            let next = run_once().await;
+            for client in [1, 2, 4] {
+                assert_eq!(first[&client], next[&client]);
+            }
+        }
+    }
+
+    // ========== Rejection Sink Tests ==========
+
+    async fn process_with_collector(transactions: Vec<Transaction>) -> Vec<RejectedTransaction> {
+        let collector = RejectionCollector::new();
+        let engine = PaymentsEngine::new().with_rejection_sink(collector.clone());
+        let sender = engine.sender();
+        let handle = engine.serve().await;
+        for tx in transactions {
+            sender.send(tx).await.unwrap();
+        }
+        drop(sender);
+        handle.await.unwrap().unwrap();
+        collector.rejections()
+    }
+
+    // ========== Dispute Tx ID Semantics Tests ==========
+
+    #[tokio::test]
+    async fn test_dispute_of_dispute_only_tx_id_rejected() {
+        let rejections = process_with_collector(vec![
+            deposit(1, 1, 10.0),
+            dispute(1, 1),
+            // Tx 2 has only ever appeared on dispute rows, never as a deposit
+            dispute(1, 2),
+            dispute(1, 2),
+            resolve(1, 2),
+            chargeback(1, 2),
+        ])
+        .await;
+
+        let reasons: Vec<_> = rejections.iter().map(|r| (r.tx.tx, r.reason)).collect();
+        assert_eq!(reasons, vec![(2, RejectionReason::UnknownTransaction); 4]);
+    }
+
+    #[tokio::test]
+    async fn test_dispute_rows_do_not_claim_tx_ids() {
+        let accounts = process_transactions_vec(vec![
+            deposit(1, 1, 10.0),
+            dispute(1, 5), // Unknown, and does not reserve tx 5
+            deposit(1, 5, 3.0),
+            dispute(1, 5),
+        ])
+        .await;
+
+        // The later deposit with tx 5 is accepted and can then be disputed
+        assert_account(&accounts, 1, 10.0, 3.0, 13.0, false);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_dispute_rows_reuse_deposit_tx_id() {
+        let rejections = process_with_collector(vec![
+            deposit(1, 1, 10.0),
+            dispute(1, 1),
+            resolve(1, 1),
+            dispute(1, 1), // Same deposit: already went through a dispute
+        ])
+        .await;
+
+        assert_eq!(
+            rejections,
+            vec![RejectedTransaction {
+                tx: dispute(1, 1),
+                reason: RejectionReason::AlreadyDisputed,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejection_sink_insufficient_funds() {
+        let rejections =
+            process_with_collector(vec![deposit(1, 1, 10.0), withdrawal(1, 2, 15.0)]).await;
+        assert_eq!(
+            rejections,
+            vec![RejectedTransaction {
+                tx: withdrawal(1, 2, 15.0),
+                reason: RejectionReason::InsufficientFunds,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejection_sink_double_chargeback() {
+        let rejections = process_with_collector(vec![
+            deposit(1, 1, 10.0),
+            dispute(1, 1),
+            chargeback(1, 1),
+            chargeback(1, 1), // Already charged back
+        ])
+        .await;
+        assert_eq!(
+            rejections,
+            vec![RejectedTransaction {
+                tx: chargeback(1, 1),
+                reason: RejectionReason::NotUnderDispute,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejection_sink_engine_level_reasons() {
+        let rejections = process_with_collector(vec![
+            dispute(1, 1),       // No account yet
+            deposit(1, 1, 10.0), // Accepted
+            deposit(2, 1, 20.0), // Duplicate tx ID
+        ])
+        .await;
+        let reasons: Vec<RejectionReason> = rejections.iter().map(|r| r.reason).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                RejectionReason::UnknownAccount,
+                RejectionReason::DuplicateTransaction
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejection_sink_accepts_all_valid_transactions() {
+        let rejections =
+            process_with_collector(vec![deposit(1, 1, 10.0), dispute(1, 1), resolve(1, 1)]).await;
+        assert!(rejections.is_empty());
+    }
 }
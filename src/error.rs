@@ -0,0 +1,57 @@
+//! Errors that stop the engine from processing further transactions.
+//!
+//! Individual rejected transactions are not errors at this level; they are
+//! reported as [`RejectionReason`](crate::RejectionReason)s and processing continues.
+
+use std::fmt;
+
+use crate::TransactionId;
+use crate::limits::ResourceLimit;
+
+/// Why the engine stopped before (or without) processing its whole input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineError {
+    /// A tx ID was reused under [`DuplicatePolicy::Abort`](crate::DuplicatePolicy::Abort).
+    DuplicateTransaction(TransactionId),
+    /// Transaction `tx` exceeded one of the engine's
+    /// [`ResourceLimits`](crate::ResourceLimits).
+    LimitExceeded {
+        limit: ResourceLimit,
+        tx: TransactionId,
+    },
+    /// The input header is missing an expected column or names an unknown one.
+    BadHeader {
+        found: Vec<String>,
+        expected: Vec<&'static str>,
+    },
+    /// The input could not be read, with the underlying error's message.
+    Io(String),
+    /// The input is not valid CSV, with the underlying error's message.
+    Csv(String),
+    /// The engine's task was cancelled before it finished.
+    Cancelled,
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateTransaction(tx) => {
+                write!(f, "aborted on duplicate transaction ID {}", tx)
+            }
+            Self::LimitExceeded { limit, tx } => {
+                write!(f, "aborted on transaction ID {}: exceeded {}", tx, limit)
+            }
+            Self::BadHeader { found, expected } => write!(
+                f,
+                "bad CSV header: found `{}`, expected `{}`",
+                found.join(","),
+                expected.join(",")
+            ),
+            Self::Io(message) => write!(f, "cannot read input: {}", message),
+            Self::Csv(message) => write!(f, "malformed CSV input: {}", message),
+            Self::Cancelled => write!(f, "engine task was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
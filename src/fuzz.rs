@@ -0,0 +1,108 @@
+//! Entry point for coverage-guided fuzzing (`fuzzing` feature).
+//!
+//! [`fuzz_apply`] accepts arbitrary bytes, so a `cargo fuzz` target is a one-liner:
+//!
+//! ```ignore
+//! fuzz_target!(|data: &[u8]| crypto_events::fuzz_apply(data));
+//! ```
+
+use std::io::Read;
+
+use crate::engine::SyncEngine;
+use crate::{
+    ClientAccount, Decimal, InputOptions, PaymentsEngine, ResourceLimits, Transaction,
+    read_transactions,
+};
+
+/// Header prepended to the input, so every fuzzed byte lands in the rows.
+const HEADER: &[u8] = b"type,client,tx,amount\n";
+
+/// Transactions applied per input.
+const MAX_ROWS: u64 = 10_000;
+
+/// Largest amount magnitude accepted, in whole units. Together with
+/// [`MAX_ROWS`] this keeps every balance far inside `i64`, so any overflow is
+/// an engine bug rather than an artifact of the input.
+const MAX_AMOUNT: i64 = 1_000_000_000;
+
+/// Parse `data` as transaction CSV rows and apply them through an engine,
+/// checking the account invariants after every transaction.
+///
+/// Parsing is as tolerant as regular CSV input: unreadable rows are skipped,
+/// and input beyond the row or amount limits ends the run, so malformed input
+/// alone never panics.
+///
+/// # Panics
+///
+/// If the engine breaks an invariant: an account's `available + held`
+/// overflows or differs from its `total`.
+pub fn fuzz_apply(data: &[u8]) {
+    let limits = ResourceLimits {
+        max_rows: Some(MAX_ROWS),
+        max_amount: Some(Decimal::from_int(MAX_AMOUNT)),
+        ..Default::default()
+    };
+    let mut engine = SyncEngine::new(PaymentsEngine::new().with_resource_limits(limits));
+
+    let reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(HEADER.chain(data));
+    let submit = |tx: Transaction| {
+        let client = tx.client;
+        let result = engine.process(tx);
+        if let Some(account) = engine.accounts().get(&client) {
+            check_invariants(account);
+        }
+        result.is_ok()
+    };
+    read_transactions(reader, InputOptions::default(), submit, 0, None, None);
+}
+
+fn check_invariants(account: &ClientAccount) {
+    let sum = account.available.0.checked_add(account.held.0);
+    assert_eq!(
+        sum,
+        Some(account.total.0),
+        "available + held != total: {:?}",
+        account
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzz_apply_seed_full_lifecycle() {
+        fuzz_apply(
+            b"deposit,1,1,10.0\n\
+              withdrawal,1,2,4.5\n\
+              dispute,1,1,\n\
+              resolve,1,1,\n\
+              dispute,1,1,\n\
+              chargeback,1,1,\n\
+              unlock,1,3,\n\
+              deposit,2,4,3.0\n\
+              hold,2,5,1.0\n\
+              release,2,5,\n\
+              withdraw_all,2,6,\n\
+              close,2,7,\n",
+        );
+    }
+
+    #[test]
+    fn test_fuzz_apply_seed_malformed_input() {
+        for seed in [
+            &b""[..],
+            b"\xff\xfe\x00garbage,,,\n,,,\n",
+            b"deposit,1\ndeposit,x,1,1.0\ndeposit,1,1,1.2.3\ndeposit,1,1,-5\n",
+            // Each amount is representable, but their sum is not
+            b"deposit,1,1,922337203685477.5807\ndeposit,1,2,922337203685477.5807\n",
+            b"withdrawal,65535,18446744073709551615,922337203685477.5807\n",
+            b"dispute,1,1,\nchargeback,7,7,\nrelease,3,3,\n\"deposit\",1,1,\"1\n",
+        ] {
+            fuzz_apply(seed);
+        }
+    }
+}
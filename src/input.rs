@@ -0,0 +1,317 @@
+//! Options controlling how transaction CSV input is read.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::error;
+use crate::transaction::{MinorUnitsTransaction, Transaction};
+
+/// Field separator for transaction CSV input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delimiter {
+    /// Always split on this byte (e.g., `b';'` for European exports, `b'\t'` for TSV).
+    Byte(u8),
+    /// Pick whichever of `,`, `;`, `\t`, or `|` occurs most often in the header
+    /// line, falling back to `,` if none do.
+    Auto,
+}
+
+impl Default for Delimiter {
+    fn default() -> Self {
+        Self::Byte(b',')
+    }
+}
+
+/// Delimiters considered by [`Delimiter::Auto`], in tie-breaking order.
+const CANDIDATE_DELIMITERS: [u8; 4] = [b',', b';', b'\t', b'|'];
+
+/// Most frequent candidate delimiter in `header`; `,` on a tie with it or if none appear.
+fn detect_delimiter(header: &str) -> u8 {
+    CANDIDATE_DELIMITERS
+        .into_iter()
+        .map(|delimiter| {
+            (
+                header.bytes().filter(|&b| b == delimiter).count(),
+                delimiter,
+            )
+        })
+        .fold((0, b','), |best, candidate| {
+            if candidate.0 > best.0 {
+                candidate
+            } else {
+                best
+            }
+        })
+        .1
+}
+
+/// What the reader does when the engine's channel is full.
+///
+/// A closed channel always stops reading: the engine has stopped, and its
+/// handle reports why.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SendPolicy {
+    /// Wait for the engine to make room (backpressure).
+    #[default]
+    Wait,
+    /// Log and drop the transaction, then keep reading. Trades completeness
+    /// for never stalling the reader on a slow engine.
+    DropWhenFull,
+}
+
+/// How the `amount` column is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AmountFormat {
+    /// Decimal notation, e.g., `123.45`.
+    #[default]
+    Decimal,
+    /// An integer count of minor units with `decimals` places, e.g., `12345`
+    /// for 123.45 with `decimals: 2` (cents), or satoshis with `decimals: 8`.
+    /// Converted exactly, without going through `f64`; see
+    /// [`Decimal::from_minor_units`](crate::Decimal::from_minor_units) for
+    /// rounding. Amounts with a fractional part are rejected as bad values.
+    MinorUnits { decimals: u32 },
+}
+
+impl AmountFormat {
+    /// Deserialize a transaction row whose amount is written in this format.
+    pub(crate) fn deserialize(
+        self,
+        record: &csv::StringRecord,
+        headers: &csv::StringRecord,
+    ) -> csv::Result<Transaction> {
+        match self {
+            Self::Decimal => record.deserialize(Some(headers)),
+            Self::MinorUnits { decimals } => record
+                .deserialize::<MinorUnitsTransaction>(Some(headers))
+                .map(|row| row.into_transaction(decimals)),
+        }
+    }
+}
+
+/// What to do with a decimal amount written to more than four places, which
+/// the engine otherwise rounds without comment (e.g., `1.23456` to `1.2346`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PrecisionPolicy {
+    /// Round silently.
+    #[default]
+    Round,
+    /// Round, but log each such row so operators know the feed is losing precision.
+    Warn,
+    /// Log and drop each such row.
+    Reject,
+}
+
+/// How to read transaction CSV input. See [`run_with_options`](crate::run_with_options).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputOptions {
+    pub delimiter: Delimiter,
+    pub send_policy: SendPolicy,
+    pub amount_format: AmountFormat,
+    pub precision_policy: PrecisionPolicy,
+    /// Column names, in file order, for input without a header line (e.g.,
+    /// `Some(["amount", "type", "tx", "client"].map(String::from).into())` for a
+    /// legacy feed, or a list read from config at runtime). When set,
+    /// the first line is read as a transaction, and columns map by position.
+    pub columns: Option<Vec<String>>,
+    /// Longest line to read, in bytes, not counting the line terminator
+    /// (default: no limit). Longer lines are logged and skipped without being
+    /// buffered in full, so one enormous malformed field can't exhaust memory.
+    pub max_line_length: Option<usize>,
+}
+
+impl InputOptions {
+    /// Open `path` as a CSV reader configured by these options.
+    pub(crate) fn open<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<csv::Reader<Box<dyn Read + Send>>, Box<dyn std::error::Error>> {
+        let delimiter = match self.delimiter {
+            Delimiter::Byte(delimiter) => delimiter,
+            Delimiter::Auto => {
+                let limit = self.max_line_length.map_or(u64::MAX, |max| max as u64 + 2);
+                let mut header = Vec::new();
+                BufReader::new(File::open(path.as_ref())?.take(limit))
+                    .read_until(b'\n', &mut header)?;
+                detect_delimiter(&String::from_utf8_lossy(&header))
+            }
+        };
+        let file = File::open(path)?;
+        let input: Box<dyn Read + Send> = match self.max_line_length {
+            Some(max) => Box::new(LineLimitReader::new(file, max)),
+            None => Box::new(file),
+        };
+
+        // The csv reader already strips a leading UTF-8 BOM and accepts CRLF
+        // line endings, so Windows exports work as-is.
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .flexible(true)
+            .delimiter(delimiter)
+            .has_headers(self.columns.is_none())
+            .from_reader(input);
+        if let Some(columns) = &self.columns {
+            reader.set_headers(csv::StringRecord::from(columns.clone()));
+        }
+        Ok(reader)
+    }
+}
+
+/// Reader that passes input through line by line, dropping (and logging) any
+/// line longer than `max` bytes. At most `max` bytes plus a CRLF are buffered.
+///
+/// Works on raw lines, so a quoted field spanning several lines counts each
+/// line separately.
+pub(crate) struct LineLimitReader<R> {
+    inner: BufReader<R>,
+    max: usize,
+    line: Vec<u8>,
+    /// Bytes of `line` already handed out.
+    pos: usize,
+    line_number: u64,
+}
+
+impl<R: Read> LineLimitReader<R> {
+    pub(crate) fn new(inner: R, max: usize) -> Self {
+        Self {
+            inner: BufReader::new(inner),
+            max,
+            line: Vec::new(),
+            pos: 0,
+            line_number: 0,
+        }
+    }
+
+    /// Load the next line within the limit into `line`. Returns false at the end of input.
+    fn next_line(&mut self) -> io::Result<bool> {
+        loop {
+            self.line.clear();
+            self.pos = 0;
+            self.line_number += 1;
+            let limit = self.max as u64 + 2; // Room for a CRLF terminator
+            if (&mut self.inner)
+                .take(limit)
+                .read_until(b'\n', &mut self.line)?
+                == 0
+            {
+                return Ok(false);
+            }
+            let content = match self.line.strip_suffix(b"\n") {
+                Some(content) => content.strip_suffix(b"\r").unwrap_or(content),
+                None => &self.line,
+            };
+            if content.len() <= self.max {
+                return Ok(true);
+            }
+            if !self.line.ends_with(b"\n") {
+                self.inner.skip_until(b'\n')?;
+            }
+            error!(
+                "Skipping line {}: longer than the {}-byte limit",
+                self.line_number, self.max
+            );
+        }
+    }
+}
+
+impl<R: Read> Read for LineLimitReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.line.len() && !self.next_line()? {
+            return Ok(0);
+        }
+        let read = (&self.line[self.pos..]).read(buf)?;
+        self.pos += read;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_delimiter() {
+        assert_eq!(detect_delimiter("type,client,tx,amount\n"), b',');
+        assert_eq!(detect_delimiter("type;client;tx;amount\r\n"), b';');
+        assert_eq!(detect_delimiter("type\tclient\ttx\tamount\n"), b'\t');
+        assert_eq!(detect_delimiter("type|client|tx|amount"), b'|');
+    }
+
+    #[test]
+    fn test_line_limit_skips_oversized_row() {
+        let huge = "9".repeat(10_000_000);
+        let input = format!(
+            "type,client,tx,amount\r\n\
+             deposit,1,1,1.0\r\n\
+             deposit,1,2,{}\r\n\
+             deposit,1,3,2.0\n\
+             deposit,1,4,12345678.0",
+            huge
+        );
+        let reader = LineLimitReader::new(input.as_bytes(), 21);
+        let mut reader = csv::Reader::from_reader(reader);
+        let txs: Vec<crate::TransactionId> = reader
+            .deserialize::<Transaction>()
+            .map(|tx| tx.unwrap().tx)
+            .collect();
+
+        // The 22-byte final line is over the limit too, though the header (exactly 21) is not
+        assert_eq!(txs, vec![1, 3]);
+    }
+
+    fn parse_rows(input: &str, format: AmountFormat) -> Vec<csv::Result<Transaction>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .flexible(true)
+            .from_reader(input.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        reader
+            .records()
+            .map(|record| format.deserialize(&record.unwrap(), &headers))
+            .collect()
+    }
+
+    #[test]
+    fn test_minor_units_amount() {
+        let input = "type,client,tx,amount\n\
+                     deposit,1,1,12345\n\
+                     dispute,1,1,\n\
+                     withdrawal,1,2,123.45\n";
+        let rows = parse_rows(input, AmountFormat::MinorUnits { decimals: 2 });
+        let deposit = rows[0].as_ref().unwrap();
+        assert_eq!(deposit.amount, Some(crate::Decimal::from_f64(123.45)));
+        assert_eq!(deposit.amount.unwrap().to_string(), "123.45");
+        assert_eq!(rows[1].as_ref().unwrap().amount, None);
+        assert!(rows[2].is_err(), "fractional minor units are a bad value");
+
+        // Satoshis round to the internal four places
+        let rows = parse_rows(
+            "type,client,tx,amount\ndeposit,1,1,150000000\ndeposit,1,2,5\n",
+            AmountFormat::MinorUnits { decimals: 8 },
+        );
+        assert_eq!(
+            rows[0].as_ref().unwrap().amount,
+            Some(crate::Decimal::from_f64(1.5))
+        );
+        assert_eq!(rows[1].as_ref().unwrap().amount, Some(crate::Decimal::ZERO));
+    }
+
+    #[test]
+    fn test_decimal_amount_parsed_exactly() {
+        // Beyond f64 precision, which would read it as 900719925474.0992
+        let rows = parse_rows(
+            "type,client,tx,amount\ndeposit,1,1,900719925474.0993\ndeposit,1,2, \n",
+            AmountFormat::Decimal,
+        );
+        let amount = rows[0].as_ref().unwrap().amount.unwrap();
+        assert_eq!(amount.to_string(), "900719925474.0993");
+        assert_eq!(rows[1].as_ref().unwrap().amount, None);
+    }
+
+    #[test]
+    fn test_detect_delimiter_falls_back_to_comma() {
+        assert_eq!(detect_delimiter("type\n"), b',');
+        assert_eq!(detect_delimiter(""), b',');
+    }
+}
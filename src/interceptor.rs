@@ -0,0 +1,37 @@
+//! Hooks for custom rules applied before the engine's own processing.
+//!
+//! A [`TransactionInterceptor`] attached via
+//! [`PaymentsEngine::with_interceptor`](crate::PaymentsEngine::with_interceptor)
+//! sees every transaction before it is dispatched, and may let it through,
+//! reject it, or substitute a modified transaction.
+
+use crate::client_account::ClientAccount;
+use crate::transaction::Transaction;
+
+/// What the engine should do with an intercepted transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterceptDecision {
+    /// Process the transaction as usual.
+    Accept,
+    /// Drop the transaction, reporting it as
+    /// [`RejectionReason::Intercepted`](crate::RejectionReason::Intercepted).
+    Reject,
+    /// Process this transaction in place of the original.
+    Modify(Transaction),
+}
+
+/// Custom validation run on each transaction before it is applied.
+pub trait TransactionInterceptor: Send {
+    /// Decide what to do with `tx`. `account` is the client's current account,
+    /// if one exists yet. The default accepts everything.
+    fn before(&mut self, tx: &Transaction, account: Option<&ClientAccount>) -> InterceptDecision {
+        let _ = (tx, account);
+        InterceptDecision::Accept
+    }
+}
+
+/// Interceptor that accepts every transaction unchanged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopInterceptor;
+
+impl TransactionInterceptor for NoopInterceptor {}
@@ -15,24 +15,85 @@
 //! }
 //! ```
 
-use std::collections::BTreeMap;
-use std::io::Write;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Read, Write};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
+use tokio::sync::mpsc::error::TrySendError;
+
+mod audit;
 mod client_account;
+mod clock;
 mod decimal;
 mod engine;
+mod error;
+#[cfg(feature = "fuzzing")]
+mod fuzz;
+mod input;
+mod interceptor;
+mod limits;
+mod output;
+#[cfg(feature = "parquet")]
+mod parquet_output;
+mod rejection;
+mod reorder;
+mod report;
+#[cfg(feature = "sqlite")]
+mod sqlite_output;
+mod state;
+mod stats;
+mod store;
 mod transaction;
 #[macro_use]
 mod tracing;
 
-pub use client_account::{ClientAccount, DisputeState, TransactionHistoryEntry};
-pub use decimal::Decimal;
-pub use engine::PaymentsEngine;
+pub use audit::{AuditEvent, AuditLog, AuditSink, ClientHistory, replay_audit, replay_client};
+pub use client_account::{
+    AccountPolicy, AccountView, ClientAccount, DisputeRecord, DisputeState, LockReason,
+    NegativeTotalPolicy, SimResult, TransactionHistoryEntry,
+};
+pub use clock::{Clock, MockClock, SystemClock};
+pub use decimal::{Decimal, DecimalWide, ParseDecimalError, StrictDecimalError};
+pub use engine::{
+    DuplicatePolicy, PaymentsEngine, PaymentsEngineBuilder, TransactionSender, TxIdScope,
+};
+pub use error::EngineError;
+#[cfg(feature = "fuzzing")]
+pub use fuzz::fuzz_apply;
+pub use input::{AmountFormat, Delimiter, InputOptions, PrecisionPolicy, SendPolicy};
+pub use interceptor::{InterceptDecision, NoopInterceptor, TransactionInterceptor};
+pub use limits::{ResourceLimit, ResourceLimits};
+pub use output::{
+    ClosedAccountSink, FlushPolicy, LineTerminator, OrderBy, OutputMismatch, OutputOptions,
+    StreamingAccountsWriter, compare_output, redact_client_id, write_accounts_csv,
+    write_accounts_csv_flushing, write_accounts_csv_redacted, write_accounts_csv_with_closed,
+    write_accounts_csv_with_disputes, write_accounts_csv_with_options, write_accounts_ndjson,
+    write_accounts_sharded, write_disputes_csv, write_disputes_csv_with_reasons,
+    write_rejections_csv,
+};
+#[cfg(feature = "parquet")]
+pub use parquet_output::write_accounts_parquet;
+pub use rejection::{RejectedTransaction, RejectionCollector, RejectionReason, RejectionSink};
+pub use report::{
+    AccountChange, AccountDiff, AggregateTotals, ConservationError, DisputeReportEntry,
+    accounts_digest, aggregate_totals, diff_accounts, disputed_report, locked_accounts,
+    verify_conservation,
+};
+#[cfg(feature = "sqlite")]
+pub use sqlite_output::write_accounts_sqlite;
+pub use state::{EngineState, TxIdEncoding};
+pub use stats::{LockedSet, ProcessStats, StatsHandle};
+pub use store::AccountStore;
+#[doc(hidden)]
+pub use tracing::log as __log;
+pub use tracing::{LogCapture, LogLevel, LogSink, reset_logger, set_logger};
 pub use transaction::{Transaction, TransactionType};
 
 /// Type aliases for clarity.
-pub type TransactionId = u32;
+pub type TransactionId = u64;
 pub type ClientId = u16;
 
 /// Run the payments engine on a CSV file and write results to a writer.
@@ -48,170 +109,884 @@ pub async fn run<P: AsRef<Path>, W: Write>(
     input_path: P,
     writer: W,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let accounts = process_csv_file(input_path).await?;
+    run_with_progress(input_path, writer, 0, None).await
+}
+
+/// Like [`run`], but returns the final account states instead of writing them.
+///
+/// Fails with [`EngineError::Io`] or [`EngineError::Csv`] on unreadable or
+/// malformed input, or with the engine's own error if it stops early.
+///
+/// ```
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use crypto_events::Decimal;
+///
+/// let accounts = crypto_events::compute("tests/inputs/basic_transactions.csv").await?;
+/// assert_eq!(accounts[&1].available, Decimal::from_f64(8.5));
+/// assert_eq!(accounts[&2].total, Decimal::from_f64(20.0));
+/// # Ok(())
+/// # }
+/// ```
+pub async fn compute<P: AsRef<Path>>(
+    input_path: P,
+) -> Result<BTreeMap<ClientId, ClientAccount>, EngineError> {
+    let options = InputOptions::default();
+    process_csv_file(PaymentsEngine::new(), input_path, options, 0, None, None)
+        .await
+        .map_err(into_engine_error)
+}
+
+/// Classify an error from [`process_csv_file`] as an [`EngineError`]. A panic
+/// in the reader task is resumed rather than converted.
+fn into_engine_error(error: Box<dyn std::error::Error>) -> EngineError {
+    let error = match error.downcast::<EngineError>() {
+        Ok(error) => return *error,
+        Err(error) => error,
+    };
+    let error = match error.downcast::<csv::Error>() {
+        Ok(error) => return EngineError::Csv(error.to_string()),
+        Err(error) => error,
+    };
+    match error.downcast::<tokio::task::JoinError>() {
+        Ok(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+        Ok(_) => EngineError::Cancelled,
+        Err(error) => EngineError::Io(error.to_string()),
+    }
+}
+
+/// Callback invoked with the running count of transactions read.
+///
+/// Runs on the blocking reader thread, hence the `Send` bound.
+pub type ProgressCallback = Box<dyn FnMut(u64) + Send>;
+
+/// Like [`run`], but invokes `progress` every `every_n` transactions read.
+///
+/// The callback receives the running count of CSV rows read so far (including
+/// rows that failed to deserialize). Passing `None` (or `every_n == 0`) disables
+/// progress reporting.
+pub async fn run_with_progress<P: AsRef<Path>, W: Write>(
+    input_path: P,
+    writer: W,
+    every_n: u64,
+    progress: Option<ProgressCallback>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let accounts = process_csv_file(
+        PaymentsEngine::new(),
+        input_path,
+        InputOptions::default(),
+        every_n,
+        progress,
+        None,
+    )
+    .await?;
     write_accounts_csv(accounts, writer)?;
     Ok(())
 }
 
-/// Process a CSV file through payments engine and return final account states.
+/// Like [`run`], but reads the input as described by `options` (e.g., a
+/// semicolon- or tab-delimited export).
+pub async fn run_with_options<P: AsRef<Path>, W: Write>(
+    input_path: P,
+    writer: W,
+    options: InputOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let accounts =
+        process_csv_file(PaymentsEngine::new(), input_path, options, 0, None, None).await?;
+    write_accounts_csv(accounts, writer)?;
+    Ok(())
+}
+
+/// Like [`run`], but stops reading input once `stop` is set (e.g., by a
+/// Ctrl-C handler), then drains the engine and writes the accounts so far.
+///
+/// The result is a partial but consistent snapshot: every transaction read
+/// before the stop is fully applied, and none after it is applied at all, so
+/// each account reflects exactly a prefix of the input.
+pub async fn run_until_stopped<P: AsRef<Path>, W: Write>(
+    input_path: P,
+    writer: W,
+    stop: Arc<AtomicBool>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let options = InputOptions::default();
+    let accounts = process_csv_file(
+        PaymentsEngine::new(),
+        input_path,
+        options,
+        0,
+        None,
+        Some(stop),
+    )
+    .await?;
+    write_accounts_csv(accounts, writer)?;
+    Ok(())
+}
+
+/// Like [`run`], but writes each account as soon as a `close` transaction
+/// closes it, then the remaining accounts at the end of the input. See
+/// [`StreamingAccountsWriter`] for the output order.
+pub async fn run_streaming<P: AsRef<Path>, W: Write + Send + 'static>(
+    input_path: P,
+    writer: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let output = StreamingAccountsWriter::new(writer);
+    let engine = PaymentsEngine::new().with_close_sink(output.clone());
+    let options = InputOptions::default();
+    let accounts = process_csv_file(engine, input_path, options, 0, None, None).await?;
+    output.finish(&accounts)
+}
+
+/// Like [`run`], but also writes every transaction the engine rejected to
+/// `errors`, as described by [`write_rejections_csv`].
+///
+/// Rows that fail to parse never reach the engine, so they are only logged,
+/// not listed in `errors`. As with [`run_until_stopped`], reading stops early
+/// once `stop` is set; both outputs then cover the same prefix of the input.
+pub async fn run_with_rejections<P: AsRef<Path>, W: Write, E: Write>(
+    input_path: P,
+    writer: W,
+    errors: E,
+    stop: Arc<AtomicBool>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let collector = RejectionCollector::new();
+    let engine = PaymentsEngine::new().with_rejection_sink(collector.clone());
+    let options = InputOptions::default();
+    let accounts = process_csv_file(engine, input_path, options, 0, None, Some(stop)).await?;
+    write_accounts_csv(accounts, writer)?;
+    write_rejections_csv(&collector.rejections(), errors)?;
+    Ok(())
+}
+
+/// Like [`run`], but merges several input files into one set of accounts.
+///
+/// Files are read one after another, in the order given, through a single
+/// engine, so a tx ID is a duplicate if any earlier file already used it. Every
+/// transaction is tagged with its file name as its
+/// [`source`](Transaction::source), which shows up in rejection logs and in
+/// [`RejectedTransaction`]s.
+pub async fn run_many<P: AsRef<Path>, W: Write>(
+    input_paths: &[P],
+    writer: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let accounts =
+        process_csv_files(PaymentsEngine::new(), input_paths, InputOptions::default()).await?;
+    write_accounts_csv(accounts, writer)?;
+    Ok(())
+}
+
+/// Like [`run_many`], but reads every file concurrently on its own task,
+/// feeding `shard_count` engines that each own the clients whose ID modulo
+/// `shard_count` matches their index.
+///
+/// # Conflict resolution
+///
+/// Tx IDs stay globally unique: readers claim each deposit, withdrawal, or
+/// hold tx ID in a shared set before routing it, and the first reader to claim
+/// an ID wins. Since files are read in parallel, which file that is (like the
+/// relative order of any two files' rows) is not deterministic; the losing
+/// transaction is logged as a duplicate and dropped. Rows within one file keep
+/// their order, so the result matches [`run_many`] whenever each client's
+/// transactions, and each tx ID, are confined to a single file.
+pub async fn run_many_concurrent<P: AsRef<Path>, W: Write>(
+    input_paths: &[P],
+    shard_count: usize,
+    writer: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let accounts = process_csv_files_sharded(input_paths, shard_count).await?;
+    write_accounts_csv(accounts, writer)?;
+    Ok(())
+}
+
+/// Process a CSV file through `engine` and return final account states.
+///
+/// Reading and parsing run on a dedicated blocking task so synchronous file IO
+/// never stalls the async runtime; the engine processes on its own task as usual.
+/// Once `stop` is set, no further rows are submitted and the engine drains
+/// whatever it has already received.
 async fn process_csv_file<P: AsRef<Path>>(
+    engine: PaymentsEngine,
     input_path: P,
+    options: InputOptions,
+    every_n: u64,
+    progress: Option<ProgressCallback>,
+    stop: Option<Arc<AtomicBool>>,
 ) -> Result<BTreeMap<ClientId, ClientAccount>, Box<dyn std::error::Error>> {
-    let engine = PaymentsEngine::new();
-    let sender = engine.sender();
+    let (sender, stats) = (engine.sender(), engine.stats());
     let engine_handle = engine.serve().await;
 
     // Read and parse transactions from CSV
-    let mut reader = csv::ReaderBuilder::new()
-        .trim(csv::Trim::All)
-        .flexible(true)
-        .from_path(input_path)?;
+    let started = Instant::now();
+    let mut reader = options.open(input_path)?;
+    validate_headers(reader.headers()?)?;
+
+    // The sender moves into the reader task and is dropped when it finishes,
+    // closing the channel to signal completion
+    let send_policy = options.send_policy;
+    tokio::task::spawn_blocking(move || {
+        let stopped = || {
+            stop.as_ref()
+                .is_some_and(|stop| stop.load(Ordering::Relaxed))
+        };
+        let submit = |tx| !stopped() && send_transaction(&sender, tx, send_policy);
+        read_transactions(reader, options, submit, every_n, progress, None)
+    })
+    .await?;
+    let elapsed = started.elapsed();
+    stats.update(|stats| stats.read_duration += elapsed);
 
-    for result in reader.deserialize() {
-        match result {
-            Ok(tx) => sender.send(tx).await?,
-            Err(e) => error!("Failed to deserialize transaction: {}", e),
-        }
-    }
+    // Wait for the engine to finish processing
+    let accounts = engine_handle.await??;
+    Ok(accounts)
+}
+
+/// Feed each of `input_paths` in turn through `engine`, tagging every
+/// transaction with its file name, and return the final account states.
+async fn process_csv_files<P: AsRef<Path>>(
+    engine: PaymentsEngine,
+    input_paths: &[P],
+    options: InputOptions,
+) -> Result<BTreeMap<ClientId, ClientAccount>, Box<dyn std::error::Error>> {
+    let (sender, stats) = (engine.sender(), engine.stats());
+    let engine_handle = engine.serve().await;
 
-    // Close the channel to signal completion
-    drop(sender);
+    for path in input_paths {
+        let path = path.as_ref();
+        let started = Instant::now();
+        let mut reader = options.open(path)?;
+        validate_headers(reader.headers()?)?;
+
+        let source: Arc<str> = path.display().to_string().into();
+        let (sender, options) = (sender.clone(), options.clone());
+        let send_policy = options.send_policy;
+        tokio::task::spawn_blocking(move || {
+            let submit = |tx| send_transaction(&sender, tx, send_policy);
+            read_transactions(reader, options, submit, 0, None, Some(source))
+        })
+        .await?;
+        let elapsed = started.elapsed();
+        stats.update(|stats| stats.read_duration += elapsed);
+    }
 
-    // Wait for the engine to finish processing
-    let accounts = engine_handle.await?;
+    drop(sender); // Close the channel
+    let accounts = engine_handle.await??;
     Ok(accounts)
 }
 
-/// Write account states to a CSV writer.
-fn write_accounts_csv<W: Write>(
-    accounts: BTreeMap<ClientId, ClientAccount>,
-    writer: W,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut csv_writer = csv::Writer::from_writer(writer);
+/// Read all of `input_paths` concurrently into `shard_count` engines and merge
+/// their accounts. See [`run_many_concurrent`] for the ordering guarantees.
+async fn process_csv_files_sharded<P: AsRef<Path>>(
+    input_paths: &[P],
+    shard_count: usize,
+) -> Result<BTreeMap<ClientId, ClientAccount>, Box<dyn std::error::Error>> {
+    if shard_count == 0 {
+        return Err("shard_count must be at least 1".into());
+    }
+    let mut senders = Vec::with_capacity(shard_count);
+    let mut shards = Vec::with_capacity(shard_count);
+    for _ in 0..shard_count {
+        let engine = PaymentsEngine::new();
+        senders.push(engine.sender());
+        shards.push(engine.serve().await);
+    }
+    let senders = Arc::new(senders);
+    let claimed = Arc::new(Mutex::new(HashSet::new()));
+
+    // Open every file before reading any, so a bad header fails the run up front
+    let mut readers = Vec::with_capacity(input_paths.len());
+    for path in input_paths {
+        let path = path.as_ref();
+        let mut reader = InputOptions::default().open(path)?;
+        validate_headers(reader.headers()?)?;
+        readers.push((reader, Arc::<str>::from(path.display().to_string())));
+    }
 
-    if accounts.is_empty() {
-        // Write header manually when no accounts
-        csv_writer.write_record(["client", "available", "held", "total", "locked"])?;
+    let mut tasks = Vec::with_capacity(readers.len());
+    for (reader, source) in readers {
+        let (senders, claimed) = (senders.clone(), claimed.clone());
+        tasks.push(tokio::task::spawn_blocking(move || {
+            let submit = |tx: Transaction| {
+                if tx.claims_tx_id() && !claimed.lock().unwrap().insert(tx.tx) {
+                    error!("Duplicate transaction ID received: {:?}", tx);
+                    return true;
+                }
+                let shard = &senders[tx.client as usize % senders.len()];
+                send_transaction(shard, tx, SendPolicy::Wait)
+            };
+            read_transactions(
+                reader,
+                InputOptions::default(),
+                submit,
+                0,
+                None,
+                Some(source),
+            )
+        }));
     }
-    for account in accounts.values() {
-        csv_writer.serialize(account)?;
+    for task in tasks {
+        task.await?;
     }
 
-    csv_writer.flush()?;
+    drop(senders); // Close every shard's channel
+    let mut accounts = BTreeMap::new();
+    for shard in shards {
+        accounts.extend(shard.await??);
+    }
+    Ok(accounts)
+}
+
+/// Columns every transaction row must carry. `amount` may be left off the end
+/// of a row since dispute-related rows leave it empty.
+const REQUIRED_COLUMNS: [&str; 3] = ["type", "client", "tx"];
+
+/// Columns the header must name, in any order.
+const EXPECTED_COLUMNS: [&str; 4] = ["type", "client", "tx", "amount"];
+
+/// Columns the header may name in addition to the [`EXPECTED_COLUMNS`].
+const OPTIONAL_COLUMNS: [&str; 2] = ["timestamp", "reason"];
+
+/// Fail fast if the header is missing any of the [`EXPECTED_COLUMNS`] or names
+/// a column we don't know, rather than silently dropping every row (e.g., `amt`
+/// for `amount` would otherwise leave every deposit without an amount). A
+/// completely empty file has no rows to lose and is still accepted.
+fn validate_headers(headers: &csv::StringRecord) -> Result<(), EngineError> {
+    if headers.is_empty() {
+        return Ok(());
+    }
+    let missing = EXPECTED_COLUMNS
+        .iter()
+        .any(|column| !headers.iter().any(|header| header == *column));
+    let unknown = headers
+        .iter()
+        .any(|header| !EXPECTED_COLUMNS.contains(&header) && !OPTIONAL_COLUMNS.contains(&header));
+    if missing || unknown {
+        return Err(EngineError::BadHeader {
+            found: headers.iter().map(String::from).collect(),
+            expected: EXPECTED_COLUMNS.to_vec(),
+        });
+    }
     Ok(())
 }
 
+/// Describe why `record` failed to deserialize, calling out required columns
+/// the row is too short to reach separately from values that failed to parse.
+fn describe_row_error(
+    record: &csv::StringRecord,
+    headers: &csv::StringRecord,
+    error: &csv::Error,
+) -> String {
+    let missing: Vec<&str> = headers
+        .iter()
+        .skip(record.len())
+        .filter(|column| REQUIRED_COLUMNS.contains(column))
+        .collect();
+    let line = record.position().map_or(0, |position| position.line());
+    if missing.is_empty() {
+        format!("Bad value in transaction on line {}: {}", line, error)
+    } else {
+        format!(
+            "Transaction on line {} is missing column(s): {}",
+            line,
+            missing.join(", ")
+        )
+    }
+}
+
+/// Submit `tx` under `policy`. Returns false once the engine has stopped
+/// receiving, which is the only error that should end reading.
+fn send_transaction(sender: &TransactionSender, tx: Transaction, policy: SendPolicy) -> bool {
+    match policy {
+        SendPolicy::Wait => sender.blocking_send(tx).is_ok(),
+        SendPolicy::DropWhenFull => match sender.try_send(tx) {
+            Ok(()) => true,
+            Err(TrySendError::Full(tx)) => {
+                error!("Dropping transaction, engine channel is full: {:?}", tx);
+                true
+            }
+            Err(TrySendError::Closed(_)) => false,
+        },
+    }
+}
+
+/// Deserialize every row of `reader`, with amounts written as
+/// `options.amount_format`, and hand it to `submit` (e.g., [`send_transaction`]),
+/// stopping once that returns false. Transactions are tagged with `source`,
+/// which also prefixes errors about unreadable rows.
+fn read_transactions<R: Read>(
+    mut reader: csv::Reader<R>,
+    options: InputOptions,
+    mut submit: impl FnMut(Transaction) -> bool,
+    every_n: u64,
+    mut progress: Option<ProgressCallback>,
+    source: Option<Arc<str>>,
+) {
+    let origin = source
+        .as_deref()
+        .map_or_else(String::new, |source| format!("{}: ", source));
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => {
+            error!("{}Failed to read CSV header: {}", origin, e);
+            return;
+        }
+    };
+
+    let amount_column = headers.iter().position(|column| column == "amount");
+    let check_precision = options.precision_policy != PrecisionPolicy::Round
+        && options.amount_format == AmountFormat::Decimal;
+
+    let mut rows_read: u64 = 0;
+    for result in reader.records() {
+        let format = options.amount_format;
+        match result.map(|record| (format.deserialize(&record, &headers), record)) {
+            Ok((Ok(tx), record)) => {
+                // Parsing has already rounded the amount, so inspect the original digits
+                let lossy_amount = amount_column
+                    .and_then(|column| record.get(column))
+                    .filter(|amount| check_precision && decimal::exceeds_precision(amount));
+                let reject =
+                    lossy_amount.is_some() && options.precision_policy == PrecisionPolicy::Reject;
+                if let Some(amount) = lossy_amount {
+                    let line = record.position().map_or(0, |position| position.line());
+                    error!(
+                        "{}Amount {} on line {} has more than 4 decimal places; {}",
+                        origin,
+                        amount,
+                        line,
+                        if reject {
+                            "rejecting transaction"
+                        } else {
+                            "rounding"
+                        }
+                    );
+                }
+                let tx = Transaction {
+                    source: source.clone(),
+                    ..tx
+                };
+                if !reject && !submit(tx) {
+                    break; // Engine stopped early (its handle reports why) or reading was cancelled
+                }
+            }
+            Ok((Err(e), record)) => {
+                error!("{}{}", origin, describe_row_error(&record, &headers, &e))
+            }
+            Err(e) => error!("{}Failed to read transaction: {}", origin, e),
+        }
+
+        rows_read += 1;
+        if let Some(callback) = progress.as_mut()
+            && every_n > 0
+            && rows_read.is_multiple_of(every_n)
+        {
+            callback(rows_read);
+        }
+    }
+}
+
+/// A row of the standard account output schema.
+#[derive(serde::Deserialize)]
+struct AccountRow {
+    client: ClientId,
+    available: Decimal,
+    held: Decimal,
+    total: Decimal,
+    locked: bool,
+}
+
+/// Load accounts from CSV in the output schema (`client,available,held,total,locked`).
+///
+/// Use with [`PaymentsEngine::with_accounts`] to resume from a prior run's output.
+/// Only balances and lock state are restored: loaded accounts have an empty
+/// ledger, so transactions from the prior run can no longer be disputed, and
+/// their tx IDs are not known to the engine's duplicate detection.
+///
+/// Balances are loaded as-is; call [`ClientAccount::repair`] on each account
+/// if the input may not satisfy `total = available + held`.
+pub fn load_accounts_csv<R: Read>(
+    reader: R,
+) -> Result<BTreeMap<ClientId, ClientAccount>, Box<dyn std::error::Error>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+
+    let mut accounts = BTreeMap::new();
+    for result in reader.deserialize() {
+        let row: AccountRow = result?;
+        let mut account = ClientAccount::new(row.client);
+        account.available = row.available;
+        account.held = row.held;
+        account.total = row.total;
+        account.locked = row.locked;
+        if accounts.insert(row.client, account).is_some() {
+            return Err(format!("Duplicate account row for client {}", row.client).into());
+        }
+    }
+    Ok(accounts)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
+
+    // ========== Column Validation Tests ==========
+
+    fn first_row_error(input: &str) -> String {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .flexible(true)
+            .from_reader(input.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        let record = reader.records().next().unwrap().unwrap();
+        let error = record
+            .deserialize::<Transaction>(Some(&headers))
+            .unwrap_err();
+        describe_row_error(&record, &headers, &error)
+    }
 
-    fn make_account(client_id: ClientId, available: f64, held: f64, locked: bool) -> ClientAccount {
-        let mut account = ClientAccount::new(client_id);
-        account.available = Decimal::from_f64(available);
-        account.held = Decimal::from_f64(held);
-        account.total = Decimal::from_f64(available + held);
-        account.locked = locked;
-        account
+    fn header(columns: &[&str]) -> csv::StringRecord {
+        csv::StringRecord::from(columns.to_vec())
     }
 
-    // ========== write_accounts_csv Tests ==========
+    #[test]
+    fn test_header_missing_expected_column_rejected() {
+        assert_eq!(
+            validate_headers(&header(&["type", "client", "amount"])),
+            Err(EngineError::BadHeader {
+                found: vec!["type".into(), "client".into(), "amount".into()],
+                expected: EXPECTED_COLUMNS.to_vec(),
+            })
+        );
+        assert!(validate_headers(&header(&["type", "client", "tx"])).is_err());
+    }
 
     #[test]
-    fn test_write_accounts_csv_empty() {
-        let accounts: BTreeMap<ClientId, ClientAccount> = BTreeMap::new();
-        let mut output = Vec::new();
+    fn test_mislabeled_header_rejected() {
+        let error = validate_headers(&header(&["type", "client", "tx", "amt"])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "bad CSV header: found `type,client,tx,amt`, expected `type,client,tx,amount`"
+        );
+    }
 
-        write_accounts_csv(accounts, &mut output).unwrap();
+    #[test]
+    fn test_valid_headers_accepted() {
+        assert!(validate_headers(&header(&["type", "client", "tx", "amount"])).is_ok());
+        assert!(validate_headers(&header(&["tx", "amount", "client", "type"])).is_ok());
+        assert!(
+            validate_headers(&header(&["type", "client", "tx", "amount", "timestamp"])).is_ok()
+        );
+        assert!(validate_headers(&header(&["type", "client", "tx", "amount", "reason"])).is_ok());
+        assert!(validate_headers(&csv::StringRecord::new()).is_ok());
+    }
 
-        let output_str = String::from_utf8(output).unwrap();
-        // Headers always written, even with no records
-        assert_eq!(output_str, "client,available,held,total,locked\n");
+    #[test]
+    fn test_row_missing_tx_column_reported_as_missing() {
+        let error = first_row_error(
+            "type,client,tx,amount
+deposit,1
+",
+        );
+        assert_eq!(error, "Transaction on line 2 is missing column(s): tx");
     }
 
     #[test]
-    fn test_write_accounts_csv_single_account() {
-        let mut accounts = BTreeMap::new();
-        accounts.insert(1, make_account(1, 100.0, 0.0, false));
+    fn test_row_with_bad_value_reported_as_bad_value() {
+        let error = first_row_error(
+            "type,client,tx,amount
+deposit,1,abc,1.0
+",
+        );
+        assert!(
+            error.starts_with("Bad value in transaction on line 2:"),
+            "{error}"
+        );
+    }
 
-        let mut output = Vec::new();
-        write_accounts_csv(accounts, &mut output).unwrap();
+    // ========== Send Policy Tests ==========
 
-        let output_str = String::from_utf8(output).unwrap();
-        let lines: Vec<&str> = output_str.lines().collect();
+    fn deposits_csv(count: u64) -> csv::Reader<std::io::Cursor<Vec<u8>>> {
+        let mut input = String::from("type,client,tx,amount\n");
+        for tx in 1..=count {
+            input.push_str(&format!("deposit,1,{},1.0\n", tx));
+        }
+        csv::Reader::from_reader(std::io::Cursor::new(input.into_bytes()))
+    }
 
-        assert_eq!(lines.len(), 2);
-        assert_eq!(lines[0], "client,available,held,total,locked");
-        assert_eq!(lines[1], "1,100.0,0.0,100.0,false");
+    fn rows_read_counter() -> (Arc<AtomicU64>, ProgressCallback) {
+        let rows_read = Arc::new(AtomicU64::new(0));
+        let counter = rows_read.clone();
+        let callback = Box::new(move |n| counter.store(n, Ordering::SeqCst));
+        (rows_read, callback)
     }
 
-    #[test]
-    fn test_write_accounts_csv_multiple_accounts_sorted() {
-        let mut accounts = BTreeMap::new();
-        // Insert in non-sorted order (BTreeMap will sort them)
-        accounts.insert(3, make_account(3, 30.0, 0.0, false));
-        accounts.insert(1, make_account(1, 10.0, 5.0, false));
-        accounts.insert(2, make_account(2, 20.0, 0.0, true));
+    #[tokio::test]
+    async fn test_full_channel_drops_and_keeps_reading() {
+        // The engine isn't serving yet, so everything past the one-slot buffer is full
+        let engine = PaymentsEngine::builder().buffer_size(1).build();
+        let (rows_read, callback) = rows_read_counter();
+        let sender = engine.sender();
+        let submit = move |tx| send_transaction(&sender, tx, SendPolicy::DropWhenFull);
+        let options = InputOptions::default();
+        read_transactions(deposits_csv(3), options, submit, 1, Some(callback), None);
+        assert_eq!(rows_read.load(Ordering::SeqCst), 3);
+
+        let accounts = engine.serve().await.await.unwrap().unwrap();
+        assert_eq!(accounts[&1].total, Decimal::from_f64(1.0));
+    }
 
-        let mut output = Vec::new();
-        write_accounts_csv(accounts, &mut output).unwrap();
+    #[test]
+    fn test_precision_policy_on_six_decimal_amount() {
+        let input = "type,client,tx,amount\ndeposit,1,1,1.234567\ndeposit,1,2,2.5\n";
+        let read = |precision_policy| {
+            let reader = csv::Reader::from_reader(input.as_bytes());
+            let options = InputOptions {
+                precision_policy,
+                ..Default::default()
+            };
+            let mut amounts = Vec::new();
+            let submit = |tx: Transaction| {
+                amounts.push(tx.amount.unwrap());
+                true
+            };
+            read_transactions(reader, options, submit, 0, None, None);
+            amounts
+        };
+
+        let rounded = vec!["1.2346".parse().unwrap(), Decimal::from_f64(2.5)];
+        assert_eq!(read(PrecisionPolicy::Round), rounded);
+        assert_eq!(read(PrecisionPolicy::Warn), rounded);
+        assert_eq!(read(PrecisionPolicy::Reject), vec![Decimal::from_f64(2.5)]);
+    }
 
-        let output_str = String::from_utf8(output).unwrap();
-        let lines: Vec<&str> = output_str.lines().collect();
+    #[test]
+    fn test_closed_channel_stops_reading() {
+        for policy in [SendPolicy::Wait, SendPolicy::DropWhenFull] {
+            let sender = PaymentsEngine::new().sender(); // Engine (and receiver) dropped
+            let (rows_read, callback) = rows_read_counter();
+            let submit = |tx| send_transaction(&sender, tx, policy);
+            let options = InputOptions::default();
+            read_transactions(deposits_csv(3), options, submit, 1, Some(callback), None);
+            assert_eq!(rows_read.load(Ordering::SeqCst), 0, "{:?}", policy);
+        }
+    }
 
-        assert_eq!(lines.len(), 4);
-        assert_eq!(lines[0], "client,available,held,total,locked");
-        // Should be sorted by client ID
-        assert!(lines[1].starts_with("1,"));
-        assert!(lines[2].starts_with("2,"));
-        assert!(lines[3].starts_with("3,"));
+    #[tokio::test]
+    async fn test_read_and_process_durations_recorded() {
+        let path = scratch_dir("durations").join("input.csv");
+        let mut input = String::from("type,client,tx,amount\n");
+        for tx in 1..=100 {
+            input.push_str(&format!("deposit,{},{},1.0\n", tx % 7, tx));
+        }
+        std::fs::write(&path, input).unwrap();
+
+        let engine = PaymentsEngine::new();
+        let stats = engine.stats();
+        assert_eq!(stats.snapshot().read_duration, Duration::ZERO);
+        process_csv_file(engine, &path, InputOptions::default(), 0, None, None)
+            .await
+            .unwrap();
+
+        let stats = stats.snapshot();
+        assert!(stats.read_duration > Duration::ZERO);
+        assert!(stats.process_duration > Duration::ZERO);
     }
 
-    #[test]
-    fn test_write_accounts_csv_locked_account() {
-        let mut accounts = BTreeMap::new();
-        accounts.insert(1, make_account(1, 50.0, 25.0, true));
+    // ========== run_until_stopped Tests ==========
+
+    #[tokio::test]
+    async fn test_stop_drains_and_writes_rows_read_so_far() {
+        let path = scratch_dir("stop_drain").join("input.csv");
+        std::fs::write(
+            &path,
+            "type,client,tx,amount\n\
+             deposit,1,1,10.0\n\
+             deposit,2,2,5.0\n\
+             withdrawal,1,3,4.0\n\
+             deposit,3,4,1.0\n",
+        )
+        .unwrap();
+
+        // Stand in for the Ctrl-C handler: raise the flag once two rows are read
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = stop.clone();
+        let interrupt: ProgressCallback = Box::new(move |rows| {
+            if rows == 2 {
+                flag.store(true, Ordering::Relaxed);
+            }
+        });
+        let options = InputOptions::default();
+        let engine = PaymentsEngine::new();
+        let accounts = process_csv_file(engine, &path, options, 1, Some(interrupt), Some(stop))
+            .await
+            .unwrap();
 
         let mut output = Vec::new();
         write_accounts_csv(accounts, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "client,available,held,total,locked\n\
+             1,10.0,0.0,10.0,false\n\
+             2,5.0,0.0,5.0,false\n"
+        );
+
+        // Already stopped before reading: a header-only snapshot
+        let mut output = Vec::new();
+        run_until_stopped(&path, &mut output, Arc::new(AtomicBool::new(true)))
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "client,available,held,total,locked\n"
+        );
+    }
 
-        let output_str = String::from_utf8(output).unwrap();
-        let lines: Vec<&str> = output_str.lines().collect();
-
-        assert_eq!(lines.len(), 2);
-        assert_eq!(lines[0], "client,available,held,total,locked");
-        assert_eq!(lines[1], "1,50.0,25.0,75.0,true");
+    // ========== run_many Tests ==========
+
+    #[tokio::test]
+    async fn test_run_many_rejection_names_source_file() {
+        let input = |name| format!("{}/tests/inputs/{}", env!("CARGO_MANIFEST_DIR"), name);
+        let paths = [input("batch_first.csv"), input("batch_second.csv")];
+        let rejections = RejectionCollector::new();
+        let engine = PaymentsEngine::new().with_rejection_sink(rejections.clone());
+
+        let accounts = process_csv_files(engine, &paths, InputOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(accounts[&1].total, Decimal::from_f64(15.0));
+
+        // Tx 1 reappears in the second file, and client 2 overdraws there
+        let rejections = rejections.rejections();
+        let sources: Vec<_> = rejections
+            .iter()
+            .map(|rejection| (rejection.tx.tx, rejection.tx.source.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![(1, paths[1].as_str()), (4, paths[1].as_str())]
+        );
+        assert!(format!("{:?}", rejections[0]).contains("batch_second.csv"));
     }
 
-    #[test]
-    fn test_write_accounts_csv_precision() {
-        let mut accounts = BTreeMap::new();
-        accounts.insert(1, make_account(1, 1.2345, 0.0001, false));
+    // ========== run_many_concurrent Tests ==========
 
-        let mut output = Vec::new();
-        write_accounts_csv(accounts, &mut output).unwrap();
+    fn scratch_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("crypto_events_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
 
-        let output_str = String::from_utf8(output).unwrap();
-        let lines: Vec<&str> = output_str.lines().collect();
+    /// Write `file_count` files in which each client's rows (and tx IDs) live
+    /// in exactly one file, so merging them has no cross-file ordering dependency.
+    fn write_independent_inputs(dir: &Path, file_count: u16) -> Vec<std::path::PathBuf> {
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = |bound: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+        let mut tx: TransactionId = 0;
+        (0..file_count)
+            .map(|file| {
+                let mut input = String::from("type,client,tx,amount\n");
+                let mut deposits = Vec::new();
+                for _ in 0..2_000 {
+                    // Clients are confined to the file matching their ID modulo the file count
+                    let client = (next(50) as u16) * file_count + file;
+                    tx += 1;
+                    let row = match next(10) {
+                        0..=4 => {
+                            deposits.push((client, tx));
+                            format!("deposit,{},{},{}.5", client, tx, next(100))
+                        }
+                        5..=7 => format!("withdrawal,{},{},{}", client, tx, next(80)),
+                        _ if deposits.is_empty() => continue,
+                        kind => {
+                            let (client, tx) = deposits[next(deposits.len() as u64) as usize];
+                            let action = ["dispute", "resolve"][kind as usize - 8];
+                            format!("{},{},{},", action, client, tx)
+                        }
+                    };
+                    input.push_str(&row);
+                    input.push('\n');
+                }
+                let path = dir.join(format!("input_{}.csv", file));
+                std::fs::write(&path, input).unwrap();
+                path
+            })
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_run_many_concurrent_matches_sequential() {
+        let dir = scratch_dir("concurrent_stress");
+        let paths = write_independent_inputs(&dir, 4);
+
+        let sequential = process_csv_files(PaymentsEngine::new(), &paths, InputOptions::default())
+            .await
+            .unwrap();
+        for shard_count in [1, 3, 8] {
+            let concurrent = process_csv_files_sharded(&paths, shard_count)
+                .await
+                .unwrap();
+            assert_eq!(concurrent, sequential, "{} shards", shard_count);
+        }
+        assert!(sequential.len() > 100);
+    }
 
-        assert_eq!(lines.len(), 2);
-        assert_eq!(lines[0], "client,available,held,total,locked");
-        // Verify precision is maintained
-        assert_eq!(lines[1], "1,1.2345,0.0001,1.2346,false");
+    #[tokio::test]
+    async fn test_run_many_concurrent_keeps_tx_ids_globally_unique() {
+        let dir = scratch_dir("concurrent_duplicates");
+        let paths = [dir.join("first.csv"), dir.join("second.csv")];
+        // Same tx ID for different clients, which land on different shards
+        std::fs::write(&paths[0], "type,client,tx,amount\ndeposit,1,7,10.0\n").unwrap();
+        std::fs::write(&paths[1], "type,client,tx,amount\ndeposit,2,7,20.0\n").unwrap();
+
+        let accounts = process_csv_files_sharded(&paths, 2).await.unwrap();
+        assert_eq!(accounts.len(), 1, "only the first reader's deposit is kept");
+        assert!(process_csv_files_sharded(&paths, 0).await.is_err());
     }
 
+    // ========== load_accounts_csv Tests ==========
+
     #[test]
-    fn test_write_accounts_csv_negative_balance() {
-        let mut accounts = BTreeMap::new();
-        accounts.insert(1, make_account(1, -50.0, 0.0, true));
+    fn test_load_accounts_csv() {
+        let input = "client,available,held,total,locked\n\
+                     1,1.5,0.0,1.5,false\n\
+                     2,-50.0,25.0,-25.0,true\n";
+        let accounts = load_accounts_csv(input.as_bytes()).unwrap();
+
+        assert_eq!(accounts.len(), 2);
+        let account = &accounts[&2];
+        assert_eq!(account.available, Decimal::from_f64(-50.0));
+        assert_eq!(account.held, Decimal::from_f64(25.0));
+        assert_eq!(account.total, Decimal::from_f64(-25.0));
+        assert!(account.locked);
+        assert!(!accounts[&1].locked);
+    }
 
-        let mut output = Vec::new();
-        write_accounts_csv(accounts, &mut output).unwrap();
+    #[test]
+    fn test_load_accounts_csv_header_only() {
+        let input = "client,available,held,total,locked\n";
+        assert!(load_accounts_csv(input.as_bytes()).unwrap().is_empty());
+    }
 
-        let output_str = String::from_utf8(output).unwrap();
-        let lines: Vec<&str> = output_str.lines().collect();
+    #[test]
+    fn test_load_accounts_csv_duplicate_client_rejected() {
+        let input = "client,available,held,total,locked\n\
+                     1,1.0,0.0,1.0,false\n\
+                     1,2.0,0.0,2.0,false\n";
+        assert!(load_accounts_csv(input.as_bytes()).is_err());
+    }
 
-        assert_eq!(lines.len(), 2);
-        assert_eq!(lines[0], "client,available,held,total,locked");
-        assert_eq!(lines[1], "1,-50.0,0.0,-50.0,true");
+    #[test]
+    fn test_load_accounts_csv_bad_locked_value_rejected() {
+        let input = "client,available,held,total,locked\n1,1.0,0.0,1.0,maybe\n";
+        assert!(load_accounts_csv(input.as_bytes()).is_err());
     }
 }
@@ -0,0 +1,48 @@
+//! Resource limits for processing untrusted input.
+//!
+//! A feed that exceeds any configured limit is treated as malicious or corrupt:
+//! the engine aborts with [`EngineError::LimitExceeded`](crate::EngineError::LimitExceeded)
+//! naming the limit, rather than growing without bound.
+
+use std::fmt;
+
+use crate::decimal::Decimal;
+
+/// Upper bounds enforced by the engine; `None` leaves a dimension unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Transactions the engine will receive, including rejected ones.
+    pub max_rows: Option<u64>,
+    /// Distinct client accounts.
+    pub max_accounts: Option<usize>,
+    /// Dispute records (open or settled) kept by any one account.
+    pub max_disputes_per_account: Option<usize>,
+    /// Ledger entries kept by any one account.
+    pub max_ledger_per_account: Option<usize>,
+    /// Magnitude of any single transaction amount. For a `withdraw_all`, the
+    /// available balance it would withdraw.
+    pub max_amount: Option<Decimal>,
+}
+
+/// Which of the [`ResourceLimits`] was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceLimit {
+    Rows,
+    Accounts,
+    DisputesPerAccount,
+    LedgerPerAccount,
+    Amount,
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Self::Rows => "max rows",
+            Self::Accounts => "max accounts",
+            Self::DisputesPerAccount => "max disputes per account",
+            Self::LedgerPerAccount => "max ledger entries per account",
+            Self::Amount => "max amount",
+        };
+        write!(f, "{}", msg)
+    }
+}
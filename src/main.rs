@@ -1,23 +1,104 @@
 //! CLI entry point for the payments engine.
 //!
-//! Usage: `cargo run -- <transactions.csv>`
+//! Usage: `cargo run -- <transactions.csv> [--errors <rejections.csv>] [--expect <accounts.csv>]`
+//!
+//! With `--errors`, every transaction the engine rejected is also written to
+//! the given file, one `type,client,tx,amount,reason` row each.
+//!
+//! With `--expect`, the accounts output is also compared line by line with the
+//! given golden file; on a mismatch the first differing line is printed to
+//! stderr and the CLI exits nonzero, for regression checks in CI.
+//!
+//! On Ctrl-C the CLI stops reading input, lets the engine finish what it has
+//! already received, and writes the accounts computed so far: a partial but
+//! consistent snapshot reflecting a prefix of the input.
 
 use std::env;
+use std::fs::{self, File};
+use std::io::Write;
 use std::process;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+fn usage(program: &str) -> ! {
+    eprintln!(
+        "Usage: {} <transactions.csv> [--errors <rejections.csv>] [--expect <accounts.csv>]",
+        program
+    );
+    process::exit(1);
+}
 
 #[tokio::main]
 async fn main() {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() != 2 {
-        eprintln!("Usage: {} <transactions.csv>", args[0]);
-        process::exit(1);
+    let (mut input_path, mut errors_path, mut expect_path) = (None, None, None);
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        let slot = match arg.as_str() {
+            "--errors" => &mut errors_path,
+            "--expect" => &mut expect_path,
+            _ if input_path.is_none() && !arg.starts_with("--") => {
+                input_path = Some(arg);
+                continue;
+            }
+            _ => usage(&args[0]),
+        };
+        match rest.next() {
+            Some(path) if slot.is_none() => *slot = Some(path),
+            _ => usage(&args[0]),
+        }
     }
+    let Some(input_path) = input_path else {
+        usage(&args[0]);
+    };
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let interrupted = stop.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("Interrupted; writing accounts processed so far");
+            interrupted.store(true, Ordering::Relaxed);
+        }
+    });
+
+    let errors = match errors_path.map(File::create).transpose() {
+        Ok(errors) => errors,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    };
 
-    let input_path = &args[1];
+    // Capture the output to compare it once complete
+    let mut captured = Vec::new();
+    let writer: Box<dyn Write + '_> = match expect_path {
+        None => Box::new(std::io::stdout()),
+        Some(_) => Box::new(&mut captured),
+    };
+    let result = match errors {
+        None => crypto_events::run_until_stopped(input_path, writer, stop).await,
+        Some(errors) => crypto_events::run_with_rejections(input_path, writer, errors, stop).await,
+    };
 
-    if let Err(e) = crypto_events::run(input_path, std::io::stdout()).await {
+    if let Err(e) = result {
         eprintln!("Error: {}", e);
         process::exit(1);
     }
+
+    if let Some(path) = expect_path {
+        let expected = match fs::read_to_string(path) {
+            Ok(expected) => expected,
+            Err(e) => {
+                eprintln!("Error: reading {}: {}", path, e);
+                process::exit(1);
+            }
+        };
+        let actual = String::from_utf8_lossy(&captured);
+        print!("{}", actual);
+        if let Err(mismatch) = crypto_events::compare_output(&actual, &expected) {
+            eprintln!("Mismatch against {}: {}", path, mismatch);
+            process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,1318 @@
+//! Output writers for final account states.
+//!
+//! The standard output is a CSV with one row per account, sorted by client ID:
+//! `client,available,held,total,locked`. [`write_accounts_csv_with_disputes`]
+//! extends it with a trailing `disputes` column, [`write_accounts_csv_with_closed`]
+//! with a trailing `closed` column, and [`write_rejections_csv`]
+//! lists rejected transactions alongside it. [`write_accounts_ndjson`] writes
+//! the same fields as newline-delimited JSON. [`compare_output`] checks any of
+//! them against an expected (golden) copy.
+//!
+//! All of these write once the input is complete. [`StreamingAccountsWriter`]
+//! instead writes each account as soon as it is closed, and the rest at the end.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    ClientAccount, ClientId, Decimal, DisputeState, RejectedTransaction, TransactionId,
+    TransactionType, disputed_report, error,
+};
+
+/// Header row of the standard account CSV output.
+const ACCOUNT_HEADER: &[&str] = &["client", "available", "held", "total", "locked"];
+
+/// Header row of the [`write_accounts_csv_with_disputes`] output.
+const EXTENDED_ACCOUNT_HEADER: &[&str] =
+    &["client", "available", "held", "total", "locked", "disputes"];
+
+/// Header row of the [`write_accounts_csv_with_closed`] output.
+const CLOSED_ACCOUNT_HEADER: &[&str] =
+    &["client", "available", "held", "total", "locked", "closed"];
+
+/// Write account states to a CSV writer.
+pub fn write_accounts_csv<W: Write>(
+    accounts: BTreeMap<ClientId, ClientAccount>,
+    writer: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    write_account_rows(
+        accounts.values(),
+        ACCOUNT_HEADER,
+        writer,
+        OutputOptions::default(),
+    )
+}
+
+/// An account row with the extended schema's `disputes` column.
+#[derive(serde::Serialize)]
+struct ExtendedRow {
+    client: ClientId,
+    available: Decimal,
+    held: Decimal,
+    total: Decimal,
+    locked: bool,
+    disputes: usize,
+}
+
+/// Like [`write_accounts_csv`], with an extra `disputes` column counting each
+/// account's open disputes: `client,available,held,total,locked,disputes`.
+pub fn write_accounts_csv_with_disputes<W: Write>(
+    accounts: &BTreeMap<ClientId, ClientAccount>,
+    writer: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let rows = accounts.values().map(|account| ExtendedRow {
+        client: account.client_id,
+        available: account.available,
+        held: account.held,
+        total: account.total,
+        locked: account.locked,
+        disputes: account.open_dispute_count(),
+    });
+    write_account_rows(
+        rows,
+        EXTENDED_ACCOUNT_HEADER,
+        writer,
+        OutputOptions::default(),
+    )
+}
+
+/// An account row with a trailing `closed` column.
+#[derive(serde::Serialize)]
+struct ClosedRow {
+    client: ClientId,
+    available: Decimal,
+    held: Decimal,
+    total: Decimal,
+    locked: bool,
+    closed: bool,
+}
+
+impl From<&ClientAccount> for ClosedRow {
+    fn from(account: &ClientAccount) -> Self {
+        Self {
+            client: account.client_id,
+            available: account.available,
+            held: account.held,
+            total: account.total,
+            locked: account.locked,
+            closed: account.is_closed(),
+        }
+    }
+}
+
+/// Like [`write_accounts_csv`], with an extra `closed` column marking accounts
+/// closed by a `close` row: `client,available,held,total,locked,closed`.
+pub fn write_accounts_csv_with_closed<W: Write>(
+    accounts: &BTreeMap<ClientId, ClientAccount>,
+    writer: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    write_account_rows(
+        accounts.values().map(ClosedRow::from),
+        CLOSED_ACCOUNT_HEADER,
+        writer,
+        OutputOptions::default(),
+    )
+}
+
+/// When to flush buffered output while writing accounts.
+///
+/// By default output is flushed once, after the last record. Setting either
+/// threshold flushes mid-stream so that a crash leaves a usable partial file;
+/// when both are set, whichever is reached first triggers the flush.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlushPolicy {
+    /// Flush after this many records have been written since the last flush.
+    pub every_records: Option<usize>,
+    /// Flush when at least this much time has passed since the last flush.
+    pub every_duration: Option<Duration>,
+}
+
+/// Like [`write_accounts_csv`], but flushes according to `policy` while writing.
+pub fn write_accounts_csv_flushing<W: Write>(
+    accounts: &BTreeMap<ClientId, ClientAccount>,
+    writer: W,
+    policy: FlushPolicy,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let options = OutputOptions {
+        flush: policy,
+        ..Default::default()
+    };
+    write_account_rows(accounts.values(), ACCOUNT_HEADER, writer, options)
+}
+
+/// Line ending written after each CSV record.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineTerminator {
+    /// `\n`.
+    #[default]
+    Lf,
+    /// `\r\n`, for Windows tooling that requires it.
+    CrLf,
+}
+
+impl From<LineTerminator> for csv::Terminator {
+    fn from(terminator: LineTerminator) -> Self {
+        match terminator {
+            LineTerminator::Lf => csv::Terminator::Any(b'\n'),
+            LineTerminator::CrLf => csv::Terminator::CRLF,
+        }
+    }
+}
+
+/// Order of the rows in account CSV output.
+///
+/// Ties are broken by client ID, ascending.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OrderBy {
+    /// Ascending client ID.
+    #[default]
+    ClientId,
+    /// The order accounts were created in; see [`ClientAccount::created_seq`].
+    FirstSeen,
+    /// Largest `total` first.
+    TotalDesc,
+    /// Smallest `total` first.
+    TotalAsc,
+}
+
+/// How to write account CSV output. See [`write_accounts_csv_with_options`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputOptions {
+    pub flush: FlushPolicy,
+    pub line_terminator: LineTerminator,
+    /// Round each balance to this many decimal places as it is written, with
+    /// [`Decimal::round_to`] (default: the full four). Accounts themselves are
+    /// left untouched, and since balances are rounded independently, a written
+    /// `total` may differ from the written `available + held` in the last place.
+    pub precision: Option<u32>,
+    /// Row order (default: by client ID).
+    pub order_by: OrderBy,
+}
+
+/// An account row of the standard schema, with balances as written.
+#[derive(serde::Serialize)]
+struct AccountRow {
+    client: ClientId,
+    available: Decimal,
+    held: Decimal,
+    total: Decimal,
+    locked: bool,
+}
+
+/// Like [`write_accounts_csv`], but written as described by `options` (e.g.,
+/// with CRLF line endings, or rounded to cents).
+pub fn write_accounts_csv_with_options<W: Write>(
+    accounts: &BTreeMap<ClientId, ClientAccount>,
+    writer: W,
+    options: OutputOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let round = |balance: Decimal| {
+        options
+            .precision
+            .map_or(balance, |decimals| balance.round_to(decimals))
+    };
+    // The map is in client ID order, and the sorts are stable, so ties keep it
+    let mut ordered: Vec<&ClientAccount> = accounts.values().collect();
+    match options.order_by {
+        OrderBy::ClientId => {}
+        OrderBy::FirstSeen => ordered.sort_by_key(|account| account.created_seq()),
+        OrderBy::TotalDesc => ordered.sort_by_key(|account| Reverse(account.total)),
+        OrderBy::TotalAsc => ordered.sort_by_key(|account| account.total),
+    }
+    let rows = ordered.into_iter().map(|account| AccountRow {
+        client: account.client_id,
+        available: round(account.available),
+        held: round(account.held),
+        total: round(account.total),
+        locked: account.locked,
+    });
+    write_account_rows(rows, ACCOUNT_HEADER, writer, options)
+}
+
+/// CSV writer wrapper that flushes per a [`FlushPolicy`].
+struct FlushingWriter<W: Write> {
+    inner: csv::Writer<W>,
+    policy: FlushPolicy,
+    records_since_flush: usize,
+    last_flush: Instant,
+}
+
+impl<W: Write> FlushingWriter<W> {
+    fn new(writer: W, options: OutputOptions) -> Self {
+        Self {
+            inner: csv::WriterBuilder::new()
+                .terminator(options.line_terminator.into())
+                .from_writer(writer),
+            policy: options.flush,
+            records_since_flush: 0,
+            last_flush: Instant::now(),
+        }
+    }
+
+    fn serialize<S: serde::Serialize>(&mut self, record: S) -> csv::Result<()> {
+        self.inner.serialize(record)?;
+        self.records_since_flush += 1;
+
+        let records_due = self
+            .policy
+            .every_records
+            .is_some_and(|n| self.records_since_flush >= n);
+        let time_due = self
+            .policy
+            .every_duration
+            .is_some_and(|d| self.last_flush.elapsed() >= d);
+        if records_due || time_due {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()?;
+        self.records_since_flush = 0;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+}
+
+/// Write accounts into `shard_count` CSV files under `dir`, split by client ID modulo.
+///
+/// Files are named `shard_0.csv` through `shard_{shard_count - 1}.csv`. Each
+/// contains the standard header and the accounts whose
+/// `client_id % shard_count` matches its index, sorted by client ID. Empty
+/// shards still get a header-only file so downstream loaders see every shard.
+pub fn write_accounts_sharded<P: AsRef<Path>>(
+    accounts: &BTreeMap<ClientId, ClientAccount>,
+    shard_count: usize,
+    dir: P,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if shard_count == 0 {
+        return Err("shard_count must be at least 1".into());
+    }
+
+    let dir = dir.as_ref();
+    for shard in 0..shard_count {
+        let file = File::create(dir.join(format!("shard_{}.csv", shard)))?;
+        let shard_accounts = accounts
+            .values()
+            .filter(|account| account.client_id as usize % shard_count == shard);
+        write_account_rows(
+            shard_accounts,
+            ACCOUNT_HEADER,
+            file,
+            OutputOptions::default(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Write one JSON object per account per line (NDJSON), sorted by client ID,
+/// e.g. `{"client":1,"available":"1.5","held":"0.0","total":"1.5","locked":false,"closed":false}`.
+///
+/// Fields match the standard CSV columns plus `closed`; balances are strings in
+/// the same canonical form, so no precision is lost to JSON numbers.
+pub fn write_accounts_ndjson<W: Write>(
+    accounts: &BTreeMap<ClientId, ClientAccount>,
+    mut writer: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for account in accounts.values() {
+        serde_json::to_writer(&mut writer, &ClosedRow::from(account))?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Header row of the [`write_disputes_csv`] output.
+const DISPUTE_HEADER: [&str; 4] = ["client", "tx", "held_amount", "state"];
+
+/// A row of the [`write_disputes_csv`] output.
+#[derive(serde::Serialize)]
+struct DisputeRow {
+    client: ClientId,
+    tx: TransactionId,
+    held_amount: Decimal,
+    state: DisputeState,
+}
+
+/// Header row of the [`write_disputes_csv_with_reasons`] output.
+const REASONED_DISPUTE_HEADER: [&str; 5] = ["client", "tx", "held_amount", "state", "reason"];
+
+/// A row of the [`write_disputes_csv_with_reasons`] output.
+#[derive(serde::Serialize)]
+struct ReasonedDisputeRow {
+    client: ClientId,
+    tx: TransactionId,
+    held_amount: Decimal,
+    state: DisputeState,
+    reason: Option<String>,
+}
+
+/// Write one row per open dispute (`client,tx,held_amount,state`), breaking
+/// down each account's `held` balance by transaction.
+///
+/// Rows are sorted by client then tx ID; per client, `held_amount` sums to `held`.
+pub fn write_disputes_csv<W: Write>(
+    accounts: &BTreeMap<ClientId, ClientAccount>,
+    writer: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    let report = disputed_report(accounts);
+    if report.is_empty() {
+        csv_writer.write_record(DISPUTE_HEADER)?;
+    }
+    for entry in report {
+        csv_writer.serialize(DisputeRow {
+            client: entry.client,
+            tx: entry.tx,
+            held_amount: entry.held,
+            state: entry.state,
+        })?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Like [`write_disputes_csv`], with a trailing `reason` column holding each
+/// dispute's reason code (empty if none was given):
+/// `client,tx,held_amount,state,reason`.
+pub fn write_disputes_csv_with_reasons<W: Write>(
+    accounts: &BTreeMap<ClientId, ClientAccount>,
+    writer: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    let report = disputed_report(accounts);
+    if report.is_empty() {
+        csv_writer.write_record(REASONED_DISPUTE_HEADER)?;
+    }
+    for entry in report {
+        csv_writer.serialize(ReasonedDisputeRow {
+            client: entry.client,
+            tx: entry.tx,
+            held_amount: entry.held,
+            state: entry.state,
+            reason: entry.reason,
+        })?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Header row of the [`write_rejections_csv`] output.
+const REJECTION_HEADER: [&str; 5] = ["type", "client", "tx", "amount", "reason"];
+
+/// A row of the [`write_rejections_csv`] output.
+#[derive(serde::Serialize)]
+struct RejectionRow {
+    #[serde(rename = "type")]
+    transaction_type: TransactionType,
+    client: ClientId,
+    tx: TransactionId,
+    amount: Option<Decimal>,
+    reason: String,
+}
+
+/// Write one row per rejected transaction (`type,client,tx,amount,reason`), in
+/// the order given.
+///
+/// `type` uses the input's spelling, `amount` is empty when the row had none,
+/// and `reason` is the [`RejectionReason`](crate::RejectionReason) message.
+pub fn write_rejections_csv<W: Write>(
+    rejections: &[RejectedTransaction],
+    writer: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    if rejections.is_empty() {
+        csv_writer.write_record(REJECTION_HEADER)?;
+    }
+    for rejection in rejections {
+        csv_writer.serialize(RejectionRow {
+            transaction_type: rejection.tx.transaction_type,
+            client: rejection.tx.client,
+            tx: rejection.tx.tx,
+            amount: rejection.tx.amount,
+            reason: rejection.reason.to_string(),
+        })?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// An account row whose client ID has been replaced by its pseudonym.
+#[derive(serde::Serialize)]
+struct RedactedRow {
+    client: u64,
+    available: Decimal,
+    held: Decimal,
+    total: Decimal,
+    locked: bool,
+}
+
+/// Pseudonymize a client ID under the secret `salt`.
+///
+/// The pseudonym is HMAC-SHA256 of the ID keyed by the salt, truncated to 64
+/// bits. It is deterministic (the same ID and salt always give the same
+/// pseudonym, across runs and platforms), and without the salt no pseudonym
+/// can be mapped back to its ID, even by someone who knows other ID and
+/// pseudonym pairs. Two IDs sharing a pseudonym is possible but negligibly
+/// unlikely: about one chance in 10^10 across all 65536 IDs.
+pub fn redact_client_id(client_id: ClientId, salt: u64) -> u64 {
+    let digest = hmac_sha256(&salt.to_le_bytes(), &client_id.to_le_bytes());
+    u64::from_le_bytes(digest[..8].try_into().unwrap())
+}
+
+/// HMAC-SHA256 (RFC 2104) of `message`, for keys of at most one block.
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    block[..key.len()].copy_from_slice(key);
+    let pad = |byte: u8| block.map(|k| k ^ byte);
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+/// Like [`write_accounts_csv`], but with each client ID replaced by
+/// [`redact_client_id`] under `salt`, sorted by the redacted ID.
+///
+/// Sharing the salt lets separate reports be joined on the redacted IDs;
+/// keep it secret to prevent mapping them back to real clients.
+pub fn write_accounts_csv_redacted<W: Write>(
+    accounts: &BTreeMap<ClientId, ClientAccount>,
+    writer: W,
+    salt: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rows: Vec<RedactedRow> = accounts
+        .values()
+        .map(|account| RedactedRow {
+            client: redact_client_id(account.client_id, salt),
+            available: account.available,
+            held: account.held,
+            total: account.total,
+            locked: account.locked,
+        })
+        .collect();
+    rows.sort_by_key(|row| row.client);
+    write_account_rows(rows, ACCOUNT_HEADER, writer, OutputOptions::default())
+}
+
+/// Destination for accounts closed by a `close` transaction, handed over by the
+/// engine as soon as the close is applied. See
+/// [`PaymentsEngine::with_close_sink`](crate::PaymentsEngine::with_close_sink).
+pub trait ClosedAccountSink: Send {
+    fn account_closed(&mut self, account: &ClientAccount);
+}
+
+/// Single-pass account CSV output, for feeds where a closed account is never
+/// referenced again: each account is written (and flushed) the moment it is
+/// closed, and [`finish`](Self::finish) writes the remaining accounts at the end.
+///
+/// Uses the standard `client,available,held,total,locked` schema. Closed
+/// accounts come first, in the order they were closed, then the rest sorted by
+/// client ID. Clones share the same output, so one can be given to the engine
+/// with [`PaymentsEngine::with_close_sink`](crate::PaymentsEngine::with_close_sink)
+/// while another finishes it.
+pub struct StreamingAccountsWriter<W: Write> {
+    inner: Arc<Mutex<StreamingInner<W>>>,
+}
+
+struct StreamingInner<W: Write> {
+    writer: csv::Writer<W>,
+    written: BTreeSet<ClientId>,
+    /// First write error, reported by `finish`; the engine can't be told.
+    error: Option<csv::Error>,
+}
+
+impl<W: Write> Clone for StreamingAccountsWriter<W> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<W: Write> StreamingAccountsWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(StreamingInner {
+                writer: csv::Writer::from_writer(writer),
+                written: BTreeSet::new(),
+                error: None,
+            })),
+        }
+    }
+
+    /// Write every account in `accounts` not already written on close, then flush.
+    ///
+    /// Fails with the first error hit while writing, including any from
+    /// writing a closed account earlier.
+    pub fn finish(
+        &self,
+        accounts: &BTreeMap<ClientId, ClientAccount>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(e) = inner.error.take() {
+            return Err(e.into());
+        }
+        if inner.written.is_empty() && accounts.is_empty() {
+            inner.writer.write_record(ACCOUNT_HEADER)?;
+        }
+        for account in accounts.values() {
+            if !inner.written.contains(&account.client_id) {
+                inner.writer.serialize(account)?;
+            }
+        }
+        inner.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Send> ClosedAccountSink for StreamingAccountsWriter<W> {
+    fn account_closed(&mut self, account: &ClientAccount) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.error.is_some() {
+            return;
+        }
+        let result = inner
+            .writer
+            .serialize(account)
+            .and_then(|()| inner.writer.flush().map_err(csv::Error::from));
+        match result {
+            Ok(()) => {
+                inner.written.insert(account.client_id);
+            }
+            Err(e) => {
+                error!(
+                    "Failed to write closed account {}: {}",
+                    account.client_id, e
+                );
+                inner.error = Some(e);
+            }
+        }
+    }
+}
+
+/// The first line where output differs from what was expected, as found by
+/// [`compare_output`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputMismatch {
+    /// 1-based line number.
+    pub line: usize,
+    /// The expected line, or `None` if the expected output ended first.
+    pub expected: Option<String>,
+    /// The actual line, or `None` if the actual output ended first.
+    pub actual: Option<String>,
+}
+
+impl fmt::Display for OutputMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |line: &Option<String>| match line {
+            Some(line) => format!("{:?}", line),
+            None => "end of output".to_string(),
+        };
+        write!(
+            f,
+            "output differs at line {}: expected {}, got {}",
+            self.line,
+            show(&self.expected),
+            show(&self.actual)
+        )
+    }
+}
+
+impl std::error::Error for OutputMismatch {}
+
+/// Compare `actual` output with `expected` line by line, e.g. against a golden
+/// file in CI, reporting the first line that differs.
+///
+/// Line terminators are not compared, so `\n` and `\r\n` output match.
+pub fn compare_output(actual: &str, expected: &str) -> Result<(), OutputMismatch> {
+    let (mut actual_lines, mut expected_lines) = (actual.lines(), expected.lines());
+    for line in 1.. {
+        match (actual_lines.next(), expected_lines.next()) {
+            (None, None) => break,
+            (actual, expected) if actual != expected => {
+                return Err(OutputMismatch {
+                    line,
+                    expected: expected.map(str::to_string),
+                    actual: actual.map(str::to_string),
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Write the header and one row per record, in iteration order. `header` is
+/// only written explicitly when there are no records to derive it from.
+fn write_account_rows<I, W>(
+    accounts: I,
+    header: &[&str],
+    writer: W,
+    options: OutputOptions,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    I: IntoIterator,
+    I::Item: serde::Serialize,
+    W: Write,
+{
+    let mut csv_writer = FlushingWriter::new(writer, options);
+
+    let mut accounts = accounts.into_iter().peekable();
+    if accounts.peek().is_none() {
+        // Write header manually when no accounts
+        csv_writer.inner.write_record(header)?;
+    }
+    for account in accounts {
+        csv_writer.serialize(account)?;
+    }
+
+    csv_writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_account(client_id: ClientId, available: f64, held: f64, locked: bool) -> ClientAccount {
+        ClientAccount::with_balances(
+            client_id,
+            Decimal::from_f64(available),
+            Decimal::from_f64(held),
+            locked,
+        )
+    }
+
+    // ========== write_accounts_csv Tests ==========
+
+    #[test]
+    fn test_write_accounts_csv_empty() {
+        let accounts: BTreeMap<ClientId, ClientAccount> = BTreeMap::new();
+        let mut output = Vec::new();
+
+        write_accounts_csv(accounts, &mut output).unwrap();
+
+        let output_str = String::from_utf8(output).unwrap();
+        // Headers always written, even with no records
+        assert_eq!(output_str, "client,available,held,total,locked\n");
+    }
+
+    #[test]
+    fn test_write_accounts_csv_single_account() {
+        let mut accounts = BTreeMap::new();
+        accounts.insert(1, make_account(1, 100.0, 0.0, false));
+
+        let mut output = Vec::new();
+        write_accounts_csv(accounts, &mut output).unwrap();
+
+        let output_str = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output_str.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "client,available,held,total,locked");
+        assert_eq!(lines[1], "1,100.0,0.0,100.0,false");
+    }
+
+    #[test]
+    fn test_write_accounts_csv_multiple_accounts_sorted() {
+        let mut accounts = BTreeMap::new();
+        // Insert in non-sorted order (BTreeMap will sort them)
+        accounts.insert(3, make_account(3, 30.0, 0.0, false));
+        accounts.insert(1, make_account(1, 10.0, 5.0, false));
+        accounts.insert(2, make_account(2, 20.0, 0.0, true));
+
+        let mut output = Vec::new();
+        write_accounts_csv(accounts, &mut output).unwrap();
+
+        let output_str = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output_str.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "client,available,held,total,locked");
+        // Should be sorted by client ID
+        assert!(lines[1].starts_with("1,"));
+        assert!(lines[2].starts_with("2,"));
+        assert!(lines[3].starts_with("3,"));
+    }
+
+    #[test]
+    fn test_write_accounts_csv_locked_account() {
+        let mut accounts = BTreeMap::new();
+        accounts.insert(1, make_account(1, 50.0, 25.0, true));
+
+        let mut output = Vec::new();
+        write_accounts_csv(accounts, &mut output).unwrap();
+
+        let output_str = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output_str.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "client,available,held,total,locked");
+        assert_eq!(lines[1], "1,50.0,25.0,75.0,true");
+    }
+
+    #[test]
+    fn test_write_accounts_csv_precision() {
+        let mut accounts = BTreeMap::new();
+        accounts.insert(1, make_account(1, 1.2345, 0.0001, false));
+
+        let mut output = Vec::new();
+        write_accounts_csv(accounts, &mut output).unwrap();
+
+        let output_str = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output_str.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "client,available,held,total,locked");
+        // Verify precision is maintained
+        assert_eq!(lines[1], "1,1.2345,0.0001,1.2346,false");
+    }
+
+    #[test]
+    fn test_write_accounts_csv_negative_balance() {
+        let mut accounts = BTreeMap::new();
+        accounts.insert(1, make_account(1, -50.0, 0.0, true));
+
+        let mut output = Vec::new();
+        write_accounts_csv(accounts, &mut output).unwrap();
+
+        let output_str = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output_str.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "client,available,held,total,locked");
+        assert_eq!(lines[1], "1,-50.0,0.0,-50.0,true");
+    }
+
+    #[test]
+    fn test_write_accounts_csv_crlf_line_endings() {
+        let accounts = BTreeMap::from([(1, make_account(1, 1.5, 0.0, false))]);
+        let options = OutputOptions {
+            line_terminator: LineTerminator::CrLf,
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        write_accounts_csv_with_options(&accounts, &mut output, options).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "client,available,held,total,locked\r\n1,1.5,0.0,1.5,false\r\n"
+        );
+
+        // Header-only output uses the same terminator
+        let mut output = Vec::new();
+        write_accounts_csv_with_options(&BTreeMap::new(), &mut output, options).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "client,available,held,total,locked\r\n"
+        );
+    }
+
+    #[test]
+    fn test_write_accounts_csv_rounded_output_precision() {
+        let accounts = BTreeMap::from([(1, make_account(1, 1.2345, 0.005, false))]);
+        let options = OutputOptions {
+            precision: Some(2),
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        write_accounts_csv_with_options(&accounts, &mut output, options).unwrap();
+        // Each balance rounds on its own: 1.2345 + 0.005 = 1.2395 rounds to 1.24
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "client,available,held,total,locked\n1,1.23,0.01,1.24,false\n"
+        );
+        // Internal state keeps its full precision
+        assert_eq!(accounts[&1].available, Decimal::from_f64(1.2345));
+    }
+
+    #[test]
+    fn test_write_accounts_csv_ordered_by_total_desc() {
+        let mut accounts: BTreeMap<_, _> = [(1, 5.0), (2, 20.0), (3, 5.0), (4, 12.5)]
+            .into_iter()
+            .map(|(client, available)| (client, make_account(client, available, 0.0, false)))
+            .collect();
+        // Clients 1 and 2 stay seeded (sequence 0); 4 was created before 3
+        accounts.get_mut(&3).unwrap().mark_created(2);
+        accounts.get_mut(&4).unwrap().mark_created(1);
+        let write = |order_by| {
+            let mut output = Vec::new();
+            let options = OutputOptions {
+                order_by,
+                ..Default::default()
+            };
+            write_accounts_csv_with_options(&accounts, &mut output, options).unwrap();
+            let output = String::from_utf8(output).unwrap();
+            output
+                .lines()
+                .skip(1)
+                .map(|line| line.split(',').next().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // Equal totals keep client ID order
+        assert_eq!(write(OrderBy::TotalDesc), ["2", "4", "1", "3"]);
+        assert_eq!(write(OrderBy::TotalAsc), ["1", "3", "4", "2"]);
+        assert_eq!(write(OrderBy::ClientId), ["1", "2", "3", "4"]);
+        // Seeded accounts come first, tied on client ID
+        assert_eq!(write(OrderBy::FirstSeen), ["1", "2", "4", "3"]);
+    }
+
+    // ========== write_accounts_sharded Tests ==========
+
+    /// Fresh, empty scratch directory unique to this test process and name.
+    fn scratch_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("crypto_events_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_write_accounts_sharded_by_client_modulo() {
+        let mut accounts = BTreeMap::new();
+        for client_id in [1, 2, 3, 4, 7] {
+            accounts.insert(
+                client_id,
+                make_account(client_id, client_id as f64, 0.0, false),
+            );
+        }
+        let dir = scratch_dir("sharded_modulo");
+
+        write_accounts_sharded(&accounts, 3, &dir).unwrap();
+
+        let read = |shard: usize| {
+            std::fs::read_to_string(dir.join(format!("shard_{}.csv", shard))).unwrap()
+        };
+        assert_eq!(
+            read(0),
+            "client,available,held,total,locked\n3,3.0,0.0,3.0,false\n"
+        );
+        assert_eq!(
+            read(1),
+            "client,available,held,total,locked\n1,1.0,0.0,1.0,false\n4,4.0,0.0,4.0,false\n7,7.0,0.0,7.0,false\n"
+        );
+        assert_eq!(
+            read(2),
+            "client,available,held,total,locked\n2,2.0,0.0,2.0,false\n"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_accounts_sharded_empty_shards_have_header() {
+        let mut accounts = BTreeMap::new();
+        accounts.insert(2, make_account(2, 5.0, 0.0, false));
+        let dir = scratch_dir("sharded_empty");
+
+        write_accounts_sharded(&accounts, 4, &dir).unwrap();
+
+        for shard in [0, 1, 3] {
+            let contents =
+                std::fs::read_to_string(dir.join(format!("shard_{}.csv", shard))).unwrap();
+            assert_eq!(contents, "client,available,held,total,locked\n");
+        }
+        assert!(!dir.join("shard_4.csv").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_accounts_sharded_zero_shards_rejected() {
+        let accounts = BTreeMap::new();
+        let dir = scratch_dir("sharded_zero");
+
+        assert!(write_accounts_sharded(&accounts, 0, &dir).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // ========== write_accounts_csv_flushing Tests ==========
+
+    /// Writer that records how many bytes had been written at each flush.
+    #[derive(Default)]
+    struct FlushRecorder {
+        written: Vec<u8>,
+        flushes: Vec<usize>,
+    }
+
+    impl FlushRecorder {
+        /// Distinct flush points (`csv::Writer` also flushes again on drop).
+        fn flush_points(&self) -> Vec<usize> {
+            let mut points = self.flushes.clone();
+            points.dedup();
+            points
+        }
+    }
+
+    impl Write for FlushRecorder {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.flushes.push(self.written.len());
+            Ok(())
+        }
+    }
+
+    fn five_accounts() -> BTreeMap<ClientId, ClientAccount> {
+        (1..=5)
+            .map(|id| (id, make_account(id, 1.0, 0.0, false)))
+            .collect()
+    }
+
+    #[test]
+    fn test_flush_every_records_flushes_mid_stream() {
+        let mut recorder = FlushRecorder::default();
+        let policy = FlushPolicy {
+            every_records: Some(2),
+            every_duration: None,
+        };
+
+        write_accounts_csv_flushing(&five_accounts(), &mut recorder, policy).unwrap();
+
+        // Flushed after records 2 and 4, then once more at the end
+        let points = recorder.flush_points();
+        assert_eq!(points.len(), 3);
+        let partial = String::from_utf8(recorder.written[..points[0]].to_vec()).unwrap();
+        assert_eq!(
+            partial,
+            "client,available,held,total,locked\n1,1.0,0.0,1.0,false\n2,1.0,0.0,1.0,false\n"
+        );
+        assert_eq!(*recorder.flushes.last().unwrap(), recorder.written.len());
+    }
+
+    #[test]
+    fn test_flush_every_duration_flushes_each_record_when_elapsed() {
+        let mut recorder = FlushRecorder::default();
+        let policy = FlushPolicy {
+            every_records: None,
+            every_duration: Some(Duration::ZERO),
+        };
+
+        write_accounts_csv_flushing(&five_accounts(), &mut recorder, policy).unwrap();
+
+        // A zero interval is always due: one flush per record
+        assert_eq!(recorder.flush_points().len(), 5);
+    }
+
+    #[test]
+    fn test_default_flush_policy_flushes_once() {
+        let mut recorder = FlushRecorder::default();
+
+        write_accounts_csv_flushing(&five_accounts(), &mut recorder, FlushPolicy::default())
+            .unwrap();
+
+        assert_eq!(recorder.flush_points(), vec![recorder.written.len()]);
+    }
+
+    // ========== write_accounts_csv_redacted Tests ==========
+
+    fn redacted_output(accounts: &BTreeMap<ClientId, ClientAccount>, salt: u64) -> String {
+        let mut output = Vec::new();
+        write_accounts_csv_redacted(accounts, &mut output, salt).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_redacted_output_stable_for_same_salt() {
+        let mut accounts = BTreeMap::new();
+        for client_id in [1, 2, 3, 500] {
+            accounts.insert(client_id, make_account(client_id, 1.5, 0.0, false));
+        }
+
+        let first = redacted_output(&accounts, 42);
+        assert_eq!(first, redacted_output(&accounts.clone(), 42));
+        assert_ne!(first, redacted_output(&accounts, 43));
+        assert!(!first.contains("\n1,") && !first.contains("\n500,"));
+    }
+
+    #[test]
+    fn test_redacted_output_sorted_by_redacted_id() {
+        let mut accounts = BTreeMap::new();
+        accounts.insert(1, make_account(1, 10.0, 0.0, false));
+        accounts.insert(2, make_account(2, 20.0, 5.0, true));
+        accounts.insert(3, make_account(3, 30.0, 0.0, false));
+
+        let output = redacted_output(&accounts, 7);
+        let mut lines = output.lines();
+        assert_eq!(lines.next(), Some("client,available,held,total,locked"));
+
+        let mut expected: Vec<String> = accounts
+            .values()
+            .map(|a| {
+                format!(
+                    "{},{},{},{},{}",
+                    redact_client_id(a.client_id, 7),
+                    a.available.to_canonical_string(),
+                    a.held.to_canonical_string(),
+                    a.total.to_canonical_string(),
+                    a.locked
+                )
+            })
+            .collect();
+        expected.sort_by_key(|line| line.split(',').next().unwrap().parse::<u64>().unwrap());
+        assert_eq!(lines.collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_redacted_output_empty() {
+        let accounts = BTreeMap::new();
+        assert_eq!(
+            redacted_output(&accounts, 0),
+            "client,available,held,total,locked\n"
+        );
+    }
+
+    #[test]
+    fn test_redact_client_id_no_collisions() {
+        let mut seen = std::collections::HashSet::new();
+        for client_id in 0..=ClientId::MAX {
+            assert!(seen.insert(redact_client_id(client_id, 0xDEAD_BEEF)));
+        }
+    }
+
+    #[test]
+    fn test_hmac_sha256_matches_rfc_4231() {
+        // Test case 2 of RFC 4231
+        let digest = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        let hex: String = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+        assert_eq!(
+            hex,
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    // ========== write_accounts_ndjson Tests ==========
+
+    #[test]
+    fn test_write_accounts_ndjson() {
+        let accounts = BTreeMap::from([
+            (2, make_account(2, -5.0, 2.5, true)),
+            (1, make_account(1, 1.2345, 0.0, false)),
+        ]);
+        let mut output = Vec::new();
+        write_accounts_ndjson(&accounts, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"client\":1,\"available\":\"1.2345\",\"held\":\"0.0\",\"total\":\"1.2345\",\"locked\":false,\"closed\":false}\n\
+             {\"client\":2,\"available\":\"-5.0\",\"held\":\"2.5\",\"total\":\"-2.5\",\"locked\":true,\"closed\":false}\n"
+        );
+    }
+
+    fn closed_accounts() -> BTreeMap<ClientId, ClientAccount> {
+        use crate::Transaction;
+
+        let mut account = ClientAccount::new(1);
+        account
+            .settle_transaction(Transaction::deposit(1, 1, Decimal::from_f64(5.0)))
+            .unwrap();
+        account.close().unwrap();
+        BTreeMap::from([(1, account), (2, make_account(2, 1.0, 0.0, false))])
+    }
+
+    #[test]
+    fn test_write_accounts_ndjson_marks_closed_accounts() {
+        let mut output = Vec::new();
+        write_accounts_ndjson(&closed_accounts(), &mut output).unwrap();
+
+        let closed: Vec<bool> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["closed"] == true)
+            .collect();
+        assert_eq!(closed, [true, false]);
+    }
+
+    #[test]
+    fn test_write_accounts_csv_with_closed() {
+        let mut output = Vec::new();
+        write_accounts_csv_with_closed(&closed_accounts(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "client,available,held,total,locked,closed\n\
+             1,0.0,0.0,0.0,false,true\n\
+             2,1.0,0.0,1.0,false,false\n"
+        );
+
+        let mut output = Vec::new();
+        write_accounts_csv_with_closed(&BTreeMap::new(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "client,available,held,total,locked,closed\n"
+        );
+    }
+
+    // ========== compare_output Tests ==========
+
+    #[test]
+    fn test_compare_output() {
+        let expected = "client,available,held,total,locked\n1,1.5,0.0,1.5,false\n";
+        assert_eq!(compare_output(expected, expected), Ok(()));
+        assert_eq!(
+            compare_output(&expected.replace('\n', "\r\n"), expected),
+            Ok(())
+        );
+
+        let mismatch = compare_output(
+            "client,available,held,total,locked\n1,2.5,0.0,2.5,false\n",
+            expected,
+        )
+        .unwrap_err();
+        assert_eq!(mismatch.line, 2);
+        assert_eq!(mismatch.expected.as_deref(), Some("1,1.5,0.0,1.5,false"));
+        assert_eq!(mismatch.actual.as_deref(), Some("1,2.5,0.0,2.5,false"));
+        assert_eq!(
+            mismatch.to_string(),
+            "output differs at line 2: expected \"1,1.5,0.0,1.5,false\", got \"1,2.5,0.0,2.5,false\""
+        );
+
+        // Missing trailing lines are a mismatch too
+        let mismatch =
+            compare_output("client,available,held,total,locked\n", expected).unwrap_err();
+        assert_eq!((mismatch.line, mismatch.actual), (2, None));
+    }
+
+    // ========== write_disputes_csv Tests ==========
+
+    #[test]
+    fn test_write_disputes_csv_one_row_per_held_transaction() {
+        use crate::Transaction;
+
+        let mut account = ClientAccount::new(1);
+        for (tx, amount) in [(1, 10.0), (2, 2.5), (3, 4.0)] {
+            account
+                .settle_transaction(Transaction::deposit(1, tx, Decimal::from_f64(amount)))
+                .unwrap();
+        }
+        account
+            .adjudicate_claim(Transaction::dispute(1, 2))
+            .unwrap();
+        account
+            .adjudicate_claim(Transaction::dispute(1, 1))
+            .unwrap();
+        let accounts = BTreeMap::from([(1, account), (2, make_account(2, 1.0, 0.0, false))]);
+
+        let mut output = Vec::new();
+        write_disputes_csv(&accounts, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "client,tx,held_amount,state\n\
+             1,1,10.0,disputed\n\
+             1,2,2.5,disputed\n"
+        );
+    }
+
+    #[test]
+    fn test_write_disputes_csv_with_reasons() {
+        use crate::Transaction;
+
+        let mut account = ClientAccount::new(1);
+        for tx in [1, 2] {
+            account
+                .settle_transaction(Transaction::deposit(1, tx, Decimal::from_f64(1.0)))
+                .unwrap();
+        }
+        account
+            .adjudicate_claim(Transaction::dispute(1, 1).with_reason("fraud"))
+            .unwrap();
+        account
+            .adjudicate_claim(Transaction::dispute(1, 2))
+            .unwrap();
+        let accounts = BTreeMap::from([(1, account)]);
+
+        let mut output = Vec::new();
+        write_disputes_csv_with_reasons(&accounts, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "client,tx,held_amount,state,reason\n\
+             1,1,1.0,disputed,fraud\n\
+             1,2,1.0,disputed,\n"
+        );
+    }
+
+    #[test]
+    fn test_write_accounts_csv_with_disputes_counts_open_disputes() {
+        use crate::Transaction;
+
+        let mut account = ClientAccount::new(1);
+        for tx in 1..=3 {
+            account
+                .settle_transaction(Transaction::deposit(1, tx, Decimal::from_f64(5.0)))
+                .unwrap();
+        }
+        for claim in [
+            Transaction::dispute(1, 1),
+            Transaction::dispute(1, 2),
+            Transaction::dispute(1, 3),
+            Transaction::resolve(1, 3), // No longer counted
+        ] {
+            account.adjudicate_claim(claim).unwrap();
+        }
+        let accounts = BTreeMap::from([(1, account), (2, make_account(2, 1.0, 0.0, false))]);
+
+        let mut output = Vec::new();
+        write_accounts_csv_with_disputes(&accounts, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "client,available,held,total,locked,disputes\n\
+             1,5.0,10.0,15.0,false,2\n\
+             2,1.0,0.0,1.0,false,0\n"
+        );
+    }
+
+    #[test]
+    fn test_write_accounts_csv_with_disputes_empty() {
+        let mut output = Vec::new();
+        write_accounts_csv_with_disputes(&BTreeMap::new(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "client,available,held,total,locked,disputes\n"
+        );
+    }
+
+    #[test]
+    fn test_write_disputes_csv_empty() {
+        let accounts = BTreeMap::from([(1, make_account(1, 1.0, 0.0, false))]);
+        let mut output = Vec::new();
+        write_disputes_csv(&accounts, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "client,tx,held_amount,state\n"
+        );
+    }
+
+    #[test]
+    fn test_write_rejections_csv_empty() {
+        let mut output = Vec::new();
+        write_rejections_csv(&[], &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "type,client,tx,amount,reason\n"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Parquet output for analytics pipelines (requires the `parquet` feature).
+//!
+//! Columns mirror the CSV output: `client` is an unsigned 16-bit integer,
+//! `available`/`held`/`total` are `DECIMAL(18, 4)` backed by the raw `i64`
+//! representation, and `locked` is a boolean. Balances of 10^14 or more in
+//! magnitude exceed the declared precision but are still stored exactly.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::Arc;
+
+use parquet::data_type::{BoolType, DataType, Int32Type, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{SerializedFileWriter, SerializedRowGroupWriter};
+use parquet::schema::parser::parse_message_type;
+
+use crate::{ClientAccount, ClientId};
+
+const ACCOUNT_SCHEMA: &str = "
+message accounts {
+    REQUIRED INT32 client (INTEGER(16, false));
+    REQUIRED INT64 available (DECIMAL(18, 4));
+    REQUIRED INT64 held (DECIMAL(18, 4));
+    REQUIRED INT64 total (DECIMAL(18, 4));
+    REQUIRED BOOLEAN locked;
+}
+";
+
+/// Write account states as a single-row-group Parquet file, sorted by client ID.
+pub fn write_accounts_parquet<W: Write + Send>(
+    accounts: &BTreeMap<ClientId, ClientAccount>,
+    writer: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let schema = Arc::new(parse_message_type(ACCOUNT_SCHEMA)?);
+    let properties = Arc::new(WriterProperties::builder().build());
+    let mut file_writer = SerializedFileWriter::new(writer, schema, properties)?;
+
+    let mut row_group = file_writer.next_row_group()?;
+    let accounts = accounts.values();
+    write_column::<Int32Type, _>(
+        &mut row_group,
+        &accounts
+            .clone()
+            .map(|a| a.client_id as i32)
+            .collect::<Vec<_>>(),
+    )?;
+    for balance in [
+        |a: &ClientAccount| a.available,
+        |a: &ClientAccount| a.held,
+        |a: &ClientAccount| a.total,
+    ] {
+        write_column::<Int64Type, _>(
+            &mut row_group,
+            &accounts.clone().map(|a| balance(a).0).collect::<Vec<_>>(),
+        )?;
+    }
+    write_column::<BoolType, _>(
+        &mut row_group,
+        &accounts.map(|a| a.locked).collect::<Vec<_>>(),
+    )?;
+    row_group.close()?;
+
+    file_writer.close()?;
+    Ok(())
+}
+
+/// Write `values` as the next column of `row_group`.
+fn write_column<T: DataType, W: Write + Send>(
+    row_group: &mut SerializedRowGroupWriter<'_, W>,
+    values: &[T::T],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut column = row_group
+        .next_column()?
+        .ok_or("Parquet schema has fewer columns than written")?;
+    column.typed::<T>().write_batch(values, None, None)?;
+    column.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Decimal;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::RowAccessor;
+
+    fn make_account(client_id: ClientId, available: f64, held: f64, locked: bool) -> ClientAccount {
+        ClientAccount::with_balances(
+            client_id,
+            Decimal::from_f64(available),
+            Decimal::from_f64(held),
+            locked,
+        )
+    }
+
+    /// Write `accounts` to a scratch file and read back each row's columns.
+    fn round_trip(
+        name: &str,
+        accounts: &BTreeMap<ClientId, ClientAccount>,
+    ) -> Vec<(u16, i64, i64, i64, bool)> {
+        let path = std::env::temp_dir().join(format!(
+            "crypto_events_{}_{}.parquet",
+            name,
+            std::process::id()
+        ));
+        write_accounts_parquet(accounts, std::fs::File::create(&path).unwrap()).unwrap();
+
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let decimal = |row: &parquet::record::Row, i| {
+            i64::from_be_bytes(row.get_decimal(i).unwrap().data().try_into().unwrap())
+        };
+        let rows = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                let row = row.unwrap();
+                (
+                    row.get_ushort(0).unwrap(),
+                    decimal(&row, 1),
+                    decimal(&row, 2),
+                    decimal(&row, 3),
+                    row.get_bool(4).unwrap(),
+                )
+            })
+            .collect();
+        std::fs::remove_file(&path).unwrap();
+        rows
+    }
+
+    #[test]
+    fn test_write_accounts_parquet_round_trip() {
+        let mut accounts = BTreeMap::new();
+        accounts.insert(2, make_account(2, -50.0, 25.0, true));
+        accounts.insert(1, make_account(1, 1.2345, 0.0, false));
+        accounts.insert(65535, make_account(65535, 100.0, 0.5, false));
+
+        let rows = round_trip("round_trip", &accounts);
+
+        assert_eq!(
+            rows,
+            vec![
+                (1, 12345, 0, 12345, false),
+                (2, -500000, 250000, -250000, true),
+                (65535, 1000000, 5000, 1005000, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_accounts_parquet_empty() {
+        let accounts = BTreeMap::new();
+        assert!(round_trip("empty", &accounts).is_empty());
+    }
+}
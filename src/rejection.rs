@@ -0,0 +1,116 @@
+//! Structured records of transactions the engine refused to apply.
+//!
+//! Every rejected transaction is still logged via `error!`, but embedders can
+//! additionally attach a [`RejectionSink`] to the [`PaymentsEngine`](crate::PaymentsEngine)
+//! to collect [`RejectedTransaction`]s (e.g., into a dead-letter queue).
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::transaction::Transaction;
+
+/// Why a transaction was not applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionReason {
+    /// Deposit or withdrawal on a locked account, or a new dispute after a freeze.
+    AccountLocked,
+    /// Any transaction on a closed account.
+    AccountClosed,
+    /// Close or unlock requested while funds are still held or disputes are open.
+    OpenDisputes,
+    /// Unlock requested for an account that is not locked.
+    NotLocked,
+    /// Deposit or withdrawal with a negative amount.
+    NegativeAmount,
+    /// Deposit or withdrawal without an amount.
+    MissingAmount,
+    /// Withdrawal exceeding the available balance.
+    InsufficientFunds,
+    /// Withdrawal that would exceed the account's daily withdrawal limit.
+    DailyLimitExceeded,
+    /// Deposit or withdrawal reusing an already-seen tx ID.
+    DuplicateTransaction,
+    /// Dispute-related transaction for a client with no account.
+    UnknownAccount,
+    /// Dispute-related transaction referencing a tx ID the account never recorded.
+    UnknownTransaction,
+    /// Dispute on a transaction that was already disputed (or resolved/charged back).
+    AlreadyDisputed,
+    /// Dispute on a transaction type that cannot be disputed (withdrawals).
+    NotDisputable,
+    /// Dispute whose hold would make `available` negative, under a policy forbidding it.
+    DisputeExceedsAvailable,
+    /// Partial dispute that would bring the amount claimed on a deposit above
+    /// the deposit's amount.
+    DisputeExceedsDeposit,
+    /// Dispute on a zero-amount deposit, under a policy rejecting such no-ops.
+    ZeroAmountDeposit,
+    /// Resolve or chargeback on a transaction that is not currently under dispute.
+    NotUnderDispute,
+    /// Rejected by the engine's [`TransactionInterceptor`](crate::TransactionInterceptor).
+    Intercepted,
+}
+
+impl fmt::Display for RejectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Self::AccountLocked => "account is locked",
+            Self::AccountClosed => "account is closed",
+            Self::OpenDisputes => "account has open disputes",
+            Self::NotLocked => "account is not locked",
+            Self::NegativeAmount => "negative amount",
+            Self::MissingAmount => "missing amount",
+            Self::InsufficientFunds => "insufficient available funds",
+            Self::DailyLimitExceeded => "daily withdrawal limit exceeded",
+            Self::DuplicateTransaction => "duplicate transaction ID",
+            Self::UnknownAccount => "account does not exist",
+            Self::UnknownTransaction => "unknown transaction",
+            Self::AlreadyDisputed => "transaction already disputed",
+            Self::NotDisputable => "transaction type cannot be disputed",
+            Self::DisputeExceedsAvailable => "dispute would make available funds negative",
+            Self::DisputeExceedsDeposit => "dispute would claim more than the deposit",
+            Self::ZeroAmountDeposit => "disputed deposit has zero amount",
+            Self::NotUnderDispute => "transaction is not under dispute",
+            Self::Intercepted => "rejected by interceptor",
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+/// A transaction the engine refused to apply, along with the reason.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RejectedTransaction {
+    pub tx: Transaction,
+    pub reason: RejectionReason,
+}
+
+/// Destination for [`RejectedTransaction`] records emitted by the engine.
+pub trait RejectionSink: Send {
+    fn record(&mut self, rejection: RejectedTransaction);
+}
+
+/// In-memory [`RejectionSink`] whose clones share the same buffer.
+///
+/// Keep a clone before handing one to the engine to read the rejections back
+/// once processing completes.
+#[derive(Debug, Clone, Default)]
+pub struct RejectionCollector {
+    rejections: Arc<Mutex<Vec<RejectedTransaction>>>,
+}
+
+impl RejectionCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot of all rejections recorded so far, in arrival order.
+    pub fn rejections(&self) -> Vec<RejectedTransaction> {
+        self.rejections.lock().unwrap().clone()
+    }
+}
+
+impl RejectionSink for RejectionCollector {
+    fn record(&mut self, rejection: RejectedTransaction) {
+        self.rejections.lock().unwrap().push(rejection);
+    }
+}
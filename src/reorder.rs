@@ -0,0 +1,166 @@
+//! Timestamp-based reordering of transactions that arrive out of order.
+//!
+//! A [`ReorderBuffer`] holds transactions back until they can no longer be
+//! overtaken, then releases them sorted by timestamp.
+//!
+//! # Windowing
+//!
+//! With a window `w`, let `max_ts` be the largest timestamp seen so far. A
+//! buffered transaction with timestamp `t` is released once `t <= max_ts - w`,
+//! i.e. once a transaction at least `w` later has arrived. Released
+//! transactions are ordered by timestamp, with ties kept in arrival order.
+//!
+//! - A transaction without a timestamp is treated as happening at `max_ts`, so
+//!   it sorts after everything seen before it.
+//! - A transaction more than `w` older than `max_ts` on arrival is "late": it is
+//!   released immediately, after anything already released, so it is applied
+//!   out of timestamp order.
+//! - When the input ends, everything still buffered is released in order.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use crate::{info, transaction::Transaction};
+
+/// A buffered transaction, ordered by `(timestamp, arrival)`.
+struct Pending {
+    timestamp: i64,
+    arrival: u64,
+    tx: Transaction,
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.timestamp, self.arrival).cmp(&(other.timestamp, other.arrival))
+    }
+}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pending {}
+
+/// Reorders transactions by timestamp within a bounded window (see the module docs).
+pub(crate) struct ReorderBuffer {
+    window: i64,
+    max_timestamp: i64,
+    arrivals: u64,
+    pending: BinaryHeap<Reverse<Pending>>,
+}
+
+impl ReorderBuffer {
+    pub(crate) fn new(window: i64) -> Self {
+        Self {
+            window: window.max(0),
+            max_timestamp: i64::MIN,
+            arrivals: 0,
+            pending: BinaryHeap::new(),
+        }
+    }
+
+    /// Buffer a newly arrived transaction.
+    pub(crate) fn push(&mut self, tx: Transaction) {
+        let timestamp = tx.timestamp.unwrap_or(self.max_timestamp);
+        if timestamp < self.watermark() {
+            info!(
+                "Transaction arrived outside the reorder window, applying late: {:?}",
+                tx
+            );
+        }
+        self.max_timestamp = self.max_timestamp.max(timestamp);
+        self.pending.push(Reverse(Pending {
+            timestamp,
+            arrival: self.arrivals,
+            tx,
+        }));
+        self.arrivals += 1;
+    }
+
+    /// Next transaction that can no longer be overtaken, if any.
+    pub(crate) fn pop_ready(&mut self) -> Option<Transaction> {
+        let watermark = self.watermark();
+        match self.pending.peek() {
+            Some(Reverse(next)) if next.timestamp <= watermark => {
+                self.pending.pop().map(|Reverse(pending)| pending.tx)
+            }
+            _ => None,
+        }
+    }
+
+    /// Release everything still buffered, in order.
+    pub(crate) fn drain(mut self) -> impl Iterator<Item = Transaction> {
+        std::iter::from_fn(move || self.pending.pop().map(|Reverse(pending)| pending.tx))
+    }
+
+    /// Timestamps at or below this can no longer be overtaken.
+    fn watermark(&self) -> i64 {
+        self.max_timestamp.saturating_sub(self.window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Decimal;
+
+    fn deposit_at(tx: u64, timestamp: i64) -> Transaction {
+        Transaction::deposit(1, tx, Decimal::from_f64(1.0)).at(timestamp)
+    }
+
+    fn ready_ids(buffer: &mut ReorderBuffer) -> Vec<u64> {
+        std::iter::from_fn(|| buffer.pop_ready())
+            .map(|tx| tx.tx)
+            .collect()
+    }
+
+    #[test]
+    fn test_releases_once_overtaken_by_window() {
+        let mut buffer = ReorderBuffer::new(10);
+        buffer.push(deposit_at(1, 100));
+        buffer.push(deposit_at(2, 95));
+        assert!(ready_ids(&mut buffer).is_empty());
+
+        // max_ts = 110, so everything at or before 100 is released, sorted
+        buffer.push(deposit_at(3, 110));
+        assert_eq!(ready_ids(&mut buffer), vec![2, 1]);
+        assert_eq!(buffer.drain().map(|tx| tx.tx).collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn test_ties_keep_arrival_order() {
+        let mut buffer = ReorderBuffer::new(0);
+        buffer.push(deposit_at(2, 5));
+        buffer.push(deposit_at(1, 5));
+        assert_eq!(ready_ids(&mut buffer), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_late_arrival_released_immediately() {
+        let mut buffer = ReorderBuffer::new(5);
+        buffer.push(deposit_at(1, 100));
+        buffer.push(deposit_at(2, 50)); // Older than max_ts - window
+        assert_eq!(ready_ids(&mut buffer), vec![2]);
+        assert_eq!(buffer.drain().map(|tx| tx.tx).collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn test_untimestamped_sorts_after_seen() {
+        let mut buffer = ReorderBuffer::new(100);
+        buffer.push(deposit_at(1, 20));
+        buffer.push(Transaction::dispute(1, 1));
+        buffer.push(deposit_at(2, 10));
+        assert_eq!(
+            buffer.drain().map(|tx| tx.tx).collect::<Vec<_>>(),
+            vec![2, 1, 1]
+        );
+    }
+}
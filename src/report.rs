@@ -0,0 +1,406 @@
+//! Read-only reports built from final account states.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    ClientId, ProcessStats, TransactionId,
+    client_account::{ClientAccount, DisputeState},
+    decimal::{Decimal, DecimalWide},
+};
+
+/// A transaction currently under dispute, as listed by [`disputed_report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisputeReportEntry {
+    pub client: ClientId,
+    pub tx: TransactionId,
+    /// Amount of the disputed deposit.
+    pub amount: Decimal,
+    /// Portion of `amount` currently held (less than `amount` if capped).
+    pub held: Decimal,
+    pub state: DisputeState,
+    /// See [`DisputeRecord::reason`](crate::DisputeRecord::reason).
+    pub reason: Option<String>,
+}
+
+/// List every open dispute across all accounts, sorted by client then tx ID.
+///
+/// Disputes that were already resolved or charged back are omitted.
+pub fn disputed_report(accounts: &BTreeMap<ClientId, ClientAccount>) -> Vec<DisputeReportEntry> {
+    let mut report = Vec::new();
+    for account in accounts.values() {
+        let start = report.len();
+        for (&tx, dispute) in account.disputes() {
+            if dispute.state != DisputeState::Disputed {
+                continue;
+            }
+            // Only ledgered deposits can ever be disputed
+            let Some(entry) = account.ledger().get(&tx) else {
+                continue;
+            };
+            report.push(DisputeReportEntry {
+                client: account.client_id,
+                tx,
+                amount: entry.amount,
+                held: dispute.held,
+                state: dispute.state,
+                reason: dispute.reason.clone(),
+            });
+        }
+        // Disputes are stored in a HashMap; sort for deterministic output
+        report[start..].sort_by_key(|entry| entry.tx);
+    }
+    report
+}
+
+/// SHA-256 digest of every account's balances and status, for checking that two
+/// runs produced identical results.
+///
+/// Accounts are hashed in client ID order, each as its client ID, `available`,
+/// `held`, `total` (raw values, little-endian), then `locked` and closed flags,
+/// so the digest depends only on those fields. Ledger and dispute history are
+/// not included.
+pub fn accounts_digest(accounts: &BTreeMap<ClientId, ClientAccount>) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for account in accounts.values() {
+        hasher.update(account.client_id.to_le_bytes());
+        for balance in [account.available, account.held, account.total] {
+            hasher.update(balance.0.to_le_bytes());
+        }
+        hasher.update([account.locked as u8, account.is_closed() as u8]);
+    }
+    hasher.finalize().into()
+}
+
+/// Client IDs of every locked account, in ascending order.
+///
+/// Scans all accounts; a running engine tracks the same set incrementally in
+/// its [`LockedSet`](crate::LockedSet).
+pub fn locked_accounts(accounts: &BTreeMap<ClientId, ClientAccount>) -> Vec<ClientId> {
+    accounts
+        .values()
+        .filter(|account| account.locked)
+        .map(|account| account.client_id)
+        .collect()
+}
+
+/// Combined balances of a set of accounts, as computed by [`aggregate_totals`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AggregateTotals {
+    pub available: DecimalWide,
+    pub held: DecimalWide,
+    pub total: DecimalWide,
+}
+
+/// Sum every account's balances, e.g. for reconciliation.
+///
+/// Accumulates in 128 bits, so the grand totals are exact even where they
+/// exceed [`Decimal::MAX`], which a plain `Decimal` sum would saturate at.
+pub fn aggregate_totals(accounts: &BTreeMap<ClientId, ClientAccount>) -> AggregateTotals {
+    AggregateTotals {
+        available: accounts.values().map(|account| account.available).sum(),
+        held: accounts.values().map(|account| account.held).sum(),
+        total: accounts.values().map(|account| account.total).sum(),
+    }
+}
+
+/// Funds unaccounted for, as found by [`verify_conservation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConservationError {
+    /// Opening balances plus deposits and write-offs, minus withdrawals and chargebacks.
+    pub expected: DecimalWide,
+    /// Combined `total` of all accounts.
+    pub actual: DecimalWide,
+}
+
+impl fmt::Display for ConservationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "funds not conserved: accounts total {}, expected {}",
+            self.actual, self.expected
+        )
+    }
+}
+
+impl std::error::Error for ConservationError {}
+
+/// Check that no funds appeared or vanished system-wide: the accounts'
+/// combined `total` must equal the opening balances plus deposits and
+/// write-offs, minus withdrawals and chargebacks, as tracked in `stats`.
+///
+/// Pass the final accounts and [`ProcessStats`] of the same engine run. Unlike
+/// the per-account `total = available + held` invariant, this catches balance
+/// changes that no money-moving transaction explains, e.g. a dispute altering
+/// `total` or an account edited after the fact.
+pub fn verify_conservation(
+    accounts: &BTreeMap<ClientId, ClientAccount>,
+    stats: &ProcessStats,
+) -> Result<(), ConservationError> {
+    let expected = stats.opening_total + stats.deposited + stats.written_off
+        - stats.withdrawn
+        - stats.charged_back;
+    let actual = accounts.values().map(|account| account.total).sum();
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(ConservationError { expected, actual })
+    }
+}
+
+/// Whether a client's account exists on each side of an [`AccountDiff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountChange {
+    /// Only present in `after`.
+    Added,
+    /// Only present in `before`.
+    Removed,
+    /// Present in both, with different balances or lock state.
+    Modified,
+}
+
+/// How one client's account changed between two snapshots, as listed by [`diff_accounts`].
+///
+/// Balance deltas are `after - before`, with a missing account counting as all
+/// zeros and unlocked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountDiff {
+    pub client: ClientId,
+    pub change: AccountChange,
+    pub available: Decimal,
+    pub held: Decimal,
+    pub total: Decimal,
+    /// New lock state, if it changed.
+    pub locked: Option<bool>,
+}
+
+/// Compare two account snapshots, e.g., engine state before and after a batch.
+///
+/// Returns one entry per client whose balances or lock state differ, sorted by
+/// client ID; unchanged accounts are omitted.
+pub fn diff_accounts(
+    before: &BTreeMap<ClientId, ClientAccount>,
+    after: &BTreeMap<ClientId, ClientAccount>,
+) -> Vec<AccountDiff> {
+    let clients: BTreeSet<ClientId> = before.keys().chain(after.keys()).copied().collect();
+    let balances = |account: Option<&ClientAccount>| {
+        account.map_or((Decimal::ZERO, Decimal::ZERO, Decimal::ZERO, false), |a| {
+            (a.available, a.held, a.total, a.locked)
+        })
+    };
+
+    let mut diffs = Vec::new();
+    for client in clients {
+        let (old, new) = (before.get(&client), after.get(&client));
+        let change = match (old, new) {
+            (None, Some(_)) => AccountChange::Added,
+            (Some(_), None) => AccountChange::Removed,
+            _ => AccountChange::Modified,
+        };
+        let (old, new) = (balances(old), balances(new));
+        if change == AccountChange::Modified && old == new {
+            continue;
+        }
+        diffs.push(AccountDiff {
+            client,
+            change,
+            available: new.0 - old.0,
+            held: new.1 - old.1,
+            total: new.2 - old.2,
+            locked: (old.3 != new.3).then_some(new.3),
+        });
+    }
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::Transaction;
+
+    fn account_with_deposits(client: ClientId, deposits: &[(TransactionId, f64)]) -> ClientAccount {
+        let mut account = ClientAccount::new(client);
+        for &(tx, amount) in deposits {
+            account
+                .settle_transaction(Transaction::deposit(client, tx, Decimal::from_f64(amount)))
+                .unwrap();
+        }
+        account
+    }
+
+    #[test]
+    fn test_disputed_report_lists_open_disputes() {
+        let mut first = account_with_deposits(1, &[(1, 10.0), (2, 20.0), (3, 30.0)]);
+        first.adjudicate_claim(Transaction::dispute(1, 3)).unwrap();
+        first.adjudicate_claim(Transaction::dispute(1, 1)).unwrap();
+        // Resolved disputes are not reported
+        first.adjudicate_claim(Transaction::dispute(1, 2)).unwrap();
+        first.adjudicate_claim(Transaction::resolve(1, 2)).unwrap();
+
+        let mut second = account_with_deposits(2, &[(4, 5.5)]);
+        second.adjudicate_claim(Transaction::dispute(2, 4)).unwrap();
+
+        let untouched = account_with_deposits(3, &[(5, 1.0)]);
+
+        let accounts = BTreeMap::from([(1, first), (2, second), (3, untouched)]);
+        let entry = |client, tx, amount| DisputeReportEntry {
+            client,
+            tx,
+            amount: Decimal::from_f64(amount),
+            held: Decimal::from_f64(amount),
+            state: DisputeState::Disputed,
+            reason: None,
+        };
+
+        assert_eq!(
+            disputed_report(&accounts),
+            vec![entry(1, 1, 10.0), entry(1, 3, 30.0), entry(2, 4, 5.5)]
+        );
+    }
+
+    #[test]
+    fn test_disputed_report_empty_without_disputes() {
+        let mut account = account_with_deposits(1, &[(1, 10.0)]);
+        account
+            .adjudicate_claim(Transaction::dispute(1, 1))
+            .unwrap();
+        account
+            .adjudicate_claim(Transaction::chargeback(1, 1))
+            .unwrap();
+
+        let accounts = BTreeMap::from([(1, account)]);
+        assert!(disputed_report(&accounts).is_empty());
+    }
+
+    #[test]
+    fn test_locked_accounts_lists_only_locked() {
+        let mut accounts = BTreeMap::new();
+        for client in [4, 1, 3] {
+            let mut account = account_with_deposits(client, &[(client as TransactionId, 1.0)]);
+            account.locked = client != 3;
+            accounts.insert(client, account);
+        }
+        assert_eq!(locked_accounts(&accounts), vec![1, 4]);
+        assert!(locked_accounts(&BTreeMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_aggregate_totals_beyond_decimal_range() {
+        // 1000 accounts of a tenth of Decimal::MAX each: ~100x past what i64 holds
+        let balance = Decimal(Decimal::MAX.0 / 10);
+        let accounts: BTreeMap<_, _> = (0..1000)
+            .map(|client| {
+                let account = ClientAccount::with_balances(client, balance, Decimal(1), false);
+                (client, account)
+            })
+            .collect();
+
+        let totals = aggregate_totals(&accounts);
+        assert_eq!(totals.available, DecimalWide(balance.0 as i128 * 1000));
+        assert_eq!(totals.held, DecimalWide(1000));
+        assert_eq!(totals.total, DecimalWide((balance.0 as i128 + 1) * 1000));
+        assert_eq!(totals.total.to_decimal(), None);
+
+        // The plain Decimal sum can only saturate
+        let saturated: Decimal = accounts.values().map(|account| account.total).sum();
+        assert_eq!(saturated, Decimal::MAX);
+    }
+
+    #[test]
+    fn test_diff_accounts_from_empty() {
+        let mut locked = account_with_deposits(2, &[(2, 5.0)]);
+        locked.locked = true;
+        let after = BTreeMap::from([(1, account_with_deposits(1, &[(1, 10.0)])), (2, locked)]);
+        let added = |client, amount, locked| AccountDiff {
+            client,
+            change: AccountChange::Added,
+            available: Decimal::from_f64(amount),
+            held: Decimal::ZERO,
+            total: Decimal::from_f64(amount),
+            locked,
+        };
+
+        assert_eq!(
+            diff_accounts(&BTreeMap::new(), &after),
+            vec![added(1, 10.0, None), added(2, 5.0, Some(true))]
+        );
+        // And the reverse: every account disappears
+        let removed = diff_accounts(&after, &BTreeMap::new());
+        assert_eq!(removed[0].change, AccountChange::Removed);
+        assert_eq!(removed[0].total, Decimal::from_f64(-10.0));
+        assert_eq!(removed[1].locked, Some(false));
+    }
+
+    #[test]
+    fn test_diff_accounts_around_withdrawal() {
+        let before = BTreeMap::from([
+            (1, account_with_deposits(1, &[(1, 10.0)])),
+            (2, account_with_deposits(2, &[(2, 3.0)])),
+        ]);
+        let mut after = before.clone();
+        after
+            .get_mut(&1)
+            .unwrap()
+            .settle_transaction(Transaction::withdrawal(1, 3, Decimal::from_f64(4.0)))
+            .unwrap();
+
+        // Client 2 is unchanged and omitted
+        assert_eq!(
+            diff_accounts(&before, &after),
+            vec![AccountDiff {
+                client: 1,
+                change: AccountChange::Modified,
+                available: Decimal::from_f64(-4.0),
+                held: Decimal::ZERO,
+                total: Decimal::from_f64(-4.0),
+                locked: None,
+            }]
+        );
+        assert!(diff_accounts(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_accounts_digest_matches_for_identical_states() {
+        // Same final state reached through different histories and insertion orders
+        let mut first = BTreeMap::new();
+        first.insert(1, account_with_deposits(1, &[(1, 10.0)]));
+        first.insert(2, account_with_deposits(2, &[(2, 2.5), (3, 2.5)]));
+
+        let mut second = BTreeMap::new();
+        second.insert(2, account_with_deposits(2, &[(5, 5.0)]));
+        second.insert(1, account_with_deposits(1, &[(4, 10.0)]));
+
+        assert_eq!(accounts_digest(&first), accounts_digest(&second));
+        assert_eq!(
+            accounts_digest(&BTreeMap::new()),
+            accounts_digest(&BTreeMap::new())
+        );
+    }
+
+    #[test]
+    fn test_accounts_digest_differs_for_different_states() {
+        let base = BTreeMap::from([(1, account_with_deposits(1, &[(1, 10.0)]))]);
+        let digest = accounts_digest(&base);
+
+        let mut changed_balance = base.clone();
+        changed_balance.get_mut(&1).unwrap().available = Decimal::from_f64(10.0001);
+        assert_ne!(accounts_digest(&changed_balance), digest);
+
+        let mut locked = base.clone();
+        locked.get_mut(&1).unwrap().locked = true;
+        assert_ne!(accounts_digest(&locked), digest);
+
+        let mut disputed = base.clone();
+        disputed
+            .get_mut(&1)
+            .unwrap()
+            .adjudicate_claim(Transaction::dispute(1, 1))
+            .unwrap();
+        assert_ne!(accounts_digest(&disputed), digest);
+
+        let other_client = BTreeMap::from([(2, account_with_deposits(2, &[(1, 10.0)]))]);
+        assert_ne!(accounts_digest(&other_client), digest);
+    }
+}
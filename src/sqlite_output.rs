@@ -0,0 +1,122 @@
+//! SQLite output for interactive querying (requires the `sqlite` feature).
+//!
+//! Balances are stored as `TEXT` in their canonical string form (see
+//! [`Decimal::to_canonical_string`](crate::Decimal::to_canonical_string)) so they
+//! never pass through a float; cast them in queries if numeric ordering is needed.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use rusqlite::{Connection, params};
+
+use crate::{ClientAccount, ClientId};
+
+const CREATE_ACCOUNTS_TABLE: &str = "
+CREATE TABLE accounts (
+    client INTEGER PRIMARY KEY,
+    available TEXT NOT NULL,
+    held TEXT NOT NULL,
+    total TEXT NOT NULL,
+    locked INTEGER NOT NULL
+)";
+
+/// Write account states into a new `accounts` table in the SQLite database at
+/// `path`, creating the database file if needed.
+///
+/// All rows are inserted in a single transaction, so a failed write leaves no
+/// partial table behind. Fails if the database already has an `accounts` table.
+pub fn write_accounts_sqlite<P: AsRef<Path>>(
+    accounts: &BTreeMap<ClientId, ClientAccount>,
+    path: P,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut connection = Connection::open(path)?;
+    let transaction = connection.transaction()?;
+    transaction.execute(CREATE_ACCOUNTS_TABLE, [])?;
+    {
+        let mut insert = transaction.prepare(
+            "INSERT INTO accounts (client, available, held, total, locked) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for account in accounts.values() {
+            insert.execute(params![
+                account.client_id,
+                account.available.to_canonical_string(),
+                account.held.to_canonical_string(),
+                account.total.to_canonical_string(),
+                account.locked,
+            ])?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Decimal;
+
+    fn scratch_path(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "crypto_events_{}_{}.sqlite",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_write_accounts_sqlite_round_trip() {
+        let mut accounts = BTreeMap::new();
+        accounts.insert(
+            2,
+            ClientAccount::with_balances(
+                2,
+                Decimal::from_f64(-50.0),
+                Decimal::from_f64(25.0),
+                true,
+            ),
+        );
+        accounts.insert(
+            1,
+            ClientAccount::with_balances(1, Decimal::from_f64(1.2345), Decimal::ZERO, false),
+        );
+        let path = scratch_path("round_trip");
+        write_accounts_sqlite(&accounts, &path).unwrap();
+
+        let connection = Connection::open(&path).unwrap();
+        let mut query = connection
+            .prepare("SELECT client, available, held, total, locked FROM accounts ORDER BY client")
+            .unwrap();
+        let rows: Vec<(u16, String, String, String, bool)> = query
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            rows,
+            vec![
+                (1, "1.2345".into(), "0.0".into(), "1.2345".into(), false),
+                (2, "-50.0".into(), "25.0".into(), "-25.0".into(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_accounts_sqlite_refuses_existing_table() {
+        let path = scratch_path("existing");
+        write_accounts_sqlite(&BTreeMap::new(), &path).unwrap();
+        assert!(write_accounts_sqlite(&BTreeMap::new(), &path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
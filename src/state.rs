@@ -0,0 +1,429 @@
+//! Saving and restoring engine state between runs.
+//!
+//! [`PaymentsEngine::serve_state`](crate::PaymentsEngine::serve_state) resolves
+//! to an [`EngineState`]: the final accounts together with every tx ID the
+//! engine has seen, so that duplicate detection keeps working once the state
+//! is reloaded with [`PaymentsEngine::with_state`](crate::PaymentsEngine::with_state).
+//!
+//! # Encoding
+//!
+//! [`EngineState::save_state`] writes a compact binary format: a magic number
+//! and version, the tx ID scope, each account's balances, a flags byte (locked,
+//! closed), its open disputes and order holds, then the tx IDs. Exact tx IDs are grouped by client (for
+//! [`TxIdScope::PerClient`]), sorted, and delta-encoded as LEB128 varints, so a
+//! feed with mostly sequential IDs costs about a byte per ID. Alternatively,
+//! [`TxIdEncoding::Bloom`] stores only a bloom filter, whose size depends on
+//! the number of IDs and the accepted false-positive rate, not on the IDs.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Read, Write};
+
+use crate::client_account::{ClientAccount, SavedDispute};
+use crate::decimal::Decimal;
+use crate::engine::{TxIdScope, TxKey};
+use crate::{ClientId, TransactionId};
+
+const MAGIC: &[u8; 4] = b"CEST";
+const VERSION: u8 = 3;
+
+/// Bits of the per-account flags byte.
+const LOCKED: u8 = 1;
+const CLOSED: u8 = 2;
+
+/// How [`EngineState::save_state`] stores the tx IDs the engine has seen.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TxIdEncoding {
+    /// Every tx ID, exactly. Grows with the number of IDs.
+    #[default]
+    Exact,
+    /// A bloom filter sized for the current IDs at `false_positive_rate`
+    /// (e.g., `0.001`).
+    ///
+    /// A reloaded engine never accepts a tx ID it had seen, but rejects a new
+    /// one as a duplicate with roughly this probability. The filter keeps its
+    /// saved size, so the rate climbs as the reloaded engine records more IDs;
+    /// resave periodically to resize it. State that was loaded from a filter
+    /// is always saved as one, since the exact IDs are gone.
+    Bloom { false_positive_rate: f64 },
+}
+
+/// Final accounts and processed tx IDs of an engine, for persisting between runs.
+///
+/// Balances, the locked and closed flags, open disputes, and order holds
+/// survive a save and load, so held funds can still be resolved, charged back,
+/// or released. The rest of the ledger is not kept, so other transactions from
+/// before the save can no longer be disputed.
+#[derive(Debug, Clone)]
+pub struct EngineState {
+    pub accounts: BTreeMap<ClientId, ClientAccount>,
+    pub(crate) tx_id_scope: TxIdScope,
+    pub(crate) tx_ids: TxIdSet,
+}
+
+impl EngineState {
+    /// Whether the engine had seen deposit/withdrawal tx ID `tx` (from `client`,
+    /// under [`TxIdScope::PerClient`]). May be a false positive for state loaded
+    /// from a bloom filter.
+    pub fn is_processed(&self, client: ClientId, tx: TransactionId) -> bool {
+        self.tx_ids.contains(self.tx_id_scope.key(client, tx))
+    }
+
+    /// Write this state to `writer`, with tx IDs stored per `encoding`. See
+    /// the [module docs](self) for the format.
+    pub fn save_state<W: Write>(&self, mut writer: W, encoding: TxIdEncoding) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, (self.tx_id_scope == TxIdScope::PerClient) as u8])?;
+
+        write_varint(&mut writer, self.accounts.len() as u64)?;
+        for account in self.accounts.values() {
+            writer.write_all(&account.client_id.to_le_bytes())?;
+            for balance in [account.available, account.held, account.total] {
+                write_decimal(&mut writer, balance)?;
+            }
+            let flags = (account.locked as u8 * LOCKED) | (account.is_closed() as u8 * CLOSED);
+            writer.write_all(&[flags])?;
+            write_holds(&mut writer, account)?;
+        }
+
+        match (&self.tx_ids, encoding) {
+            (TxIdSet::Exact(keys), TxIdEncoding::Exact) => write_exact(&mut writer, keys),
+            (
+                TxIdSet::Exact(keys),
+                TxIdEncoding::Bloom {
+                    false_positive_rate,
+                },
+            ) => {
+                let mut filter = BloomFilter::with_rate(keys.len(), false_positive_rate);
+                for &key in keys {
+                    filter.insert(key);
+                }
+                write_bloom(&mut writer, &filter)
+            }
+            (TxIdSet::Bloom(filter), _) => write_bloom(&mut writer, filter),
+        }
+    }
+
+    /// Read state written by [`save_state`](Self::save_state).
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if `reader` does not hold a
+    /// saved state.
+    pub fn load_state<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut header = [0; 6];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC || header[4] != VERSION {
+            return Err(invalid("not a saved engine state"));
+        }
+        let tx_id_scope = match header[5] {
+            0 => TxIdScope::Global,
+            1 => TxIdScope::PerClient,
+            _ => return Err(invalid("unknown tx ID scope")),
+        };
+
+        let mut accounts = BTreeMap::new();
+        for _ in 0..read_varint(&mut reader)? {
+            let client = ClientId::from_le_bytes(read_array(&mut reader)?);
+            let mut balances = [Decimal::ZERO; 3];
+            for balance in &mut balances {
+                *balance = read_decimal(&mut reader)?;
+            }
+            let [available, held, total] = balances;
+            let [flags] = read_array(&mut reader)?;
+            if flags & !(LOCKED | CLOSED) != 0 {
+                return Err(invalid("unknown account flags"));
+            }
+            let mut account =
+                ClientAccount::with_balances(client, available, held, flags & LOCKED != 0);
+            account.total = total;
+            if flags & CLOSED != 0 {
+                account.mark_closed();
+            }
+            read_holds(&mut reader, &mut account)?;
+            accounts.insert(client, account);
+        }
+
+        let tx_ids = match read_array::<1>(&mut reader)?[0] {
+            0 => TxIdSet::Exact(read_exact(&mut reader)?),
+            1 => TxIdSet::Bloom(read_bloom(&mut reader)?),
+            _ => return Err(invalid("unknown tx ID encoding")),
+        };
+        Ok(Self {
+            accounts,
+            tx_id_scope,
+            tx_ids,
+        })
+    }
+}
+
+/// The tx IDs an engine has seen, for duplicate detection.
+#[derive(Debug, Clone)]
+pub(crate) enum TxIdSet {
+    Exact(HashSet<TxKey>),
+    Bloom(BloomFilter),
+}
+
+impl Default for TxIdSet {
+    fn default() -> Self {
+        Self::Exact(HashSet::new())
+    }
+}
+
+impl TxIdSet {
+    /// Record `key`, returning whether it was new (or, for a bloom filter,
+    /// definitely new).
+    pub(crate) fn insert(&mut self, key: TxKey) -> bool {
+        match self {
+            Self::Exact(keys) => keys.insert(key),
+            Self::Bloom(filter) => filter.insert(key),
+        }
+    }
+
+    fn contains(&self, key: TxKey) -> bool {
+        match self {
+            Self::Exact(keys) => keys.contains(&key),
+            Self::Bloom(filter) => filter.contains(key),
+        }
+    }
+}
+
+/// Fixed-size bloom filter over tx ID keys, hashed deterministically so a
+/// saved filter stays valid across runs and builds.
+#[derive(Debug, Clone)]
+pub(crate) struct BloomFilter {
+    words: Vec<u64>,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    /// A filter sized to hold `count` keys at `false_positive_rate`.
+    fn with_rate(count: usize, false_positive_rate: f64) -> Self {
+        let rate = false_positive_rate.clamp(1e-9, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-(count.max(1) as f64) * rate.ln() / (ln2 * ln2)).ceil() as u64;
+        let words = bits.div_ceil(64).max(1);
+        let hashes = ((words * 64) as f64 / count.max(1) as f64 * ln2).round();
+        Self {
+            words: vec![0; words as usize],
+            hashes: (hashes as u32).clamp(1, 32),
+        }
+    }
+
+    /// Bit positions for `key`, by double hashing.
+    fn positions(&self, (client, tx): TxKey) -> impl Iterator<Item = u64> + use<> {
+        let client = client.map_or(0, |client| client as u64 + 1);
+        let first = splitmix64(tx ^ splitmix64(client));
+        let step = splitmix64(first) | 1;
+        let bits = self.words.len() as u64 * 64;
+        (0..self.hashes as u64).map(move |i| first.wrapping_add(i.wrapping_mul(step)) % bits)
+    }
+
+    fn insert(&mut self, key: TxKey) -> bool {
+        let mut new = false;
+        for bit in self.positions(key) {
+            let (word, mask) = ((bit / 64) as usize, 1 << (bit % 64));
+            new |= self.words[word] & mask == 0;
+            self.words[word] |= mask;
+        }
+        new
+    }
+
+    fn contains(&self, key: TxKey) -> bool {
+        self.positions(key)
+            .all(|bit| self.words[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+}
+
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Write the open disputes and order holds behind an account's `held` balance.
+fn write_holds<W: Write>(writer: &mut W, account: &ClientAccount) -> io::Result<()> {
+    let disputes = account.open_disputes();
+    write_varint(writer, disputes.len() as u64)?;
+    for dispute in disputes {
+        write_varint(writer, dispute.tx)?;
+        write_decimal(writer, dispute.deposit)?;
+        write_decimal(writer, dispute.claimed)?;
+        writer.write_all(&[dispute.charged_back as u8])?;
+        match &dispute.reason {
+            Some(reason) => {
+                writer.write_all(&[1])?;
+                write_varint(writer, reason.len() as u64)?;
+                writer.write_all(reason.as_bytes())?;
+            }
+            None => writer.write_all(&[0])?,
+        }
+        write_varint(writer, dispute.claims.len() as u64)?;
+        for (amount, held) in dispute.claims {
+            write_decimal(writer, amount)?;
+            write_decimal(writer, held)?;
+        }
+    }
+
+    let mut reservations: Vec<_> = account.reservations().iter().collect();
+    reservations.sort_unstable();
+    write_varint(writer, reservations.len() as u64)?;
+    for (&tx, &amount) in reservations {
+        write_varint(writer, tx)?;
+        write_decimal(writer, amount)?;
+    }
+    Ok(())
+}
+
+fn read_holds<R: Read>(reader: &mut R, account: &mut ClientAccount) -> io::Result<()> {
+    for _ in 0..read_varint(reader)? {
+        let tx = read_varint(reader)?;
+        let deposit = read_decimal(reader)?;
+        let claimed = read_decimal(reader)?;
+        let charged_back = read_array::<1>(reader)?[0] != 0;
+        let reason = match read_array::<1>(reader)?[0] {
+            0 => None,
+            1 => {
+                let len = read_varint(reader)?;
+                let mut bytes = Vec::new();
+                reader.by_ref().take(len).read_to_end(&mut bytes)?;
+                if bytes.len() as u64 != len {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                Some(String::from_utf8(bytes).map_err(|_| invalid("reason is not UTF-8"))?)
+            }
+            _ => return Err(invalid("bad reason tag")),
+        };
+        let mut claims = Vec::new();
+        for _ in 0..read_varint(reader)? {
+            claims.push((read_decimal(reader)?, read_decimal(reader)?));
+        }
+        if claims.is_empty() {
+            return Err(invalid("open dispute without claims"));
+        }
+        account.restore_dispute(SavedDispute {
+            tx,
+            deposit,
+            claimed,
+            reason,
+            charged_back,
+            claims,
+        });
+    }
+    for _ in 0..read_varint(reader)? {
+        let tx = read_varint(reader)?;
+        account.restore_reservation(tx, read_decimal(reader)?);
+    }
+    Ok(())
+}
+
+fn write_decimal<W: Write>(writer: &mut W, value: Decimal) -> io::Result<()> {
+    writer.write_all(&value.0.to_le_bytes())
+}
+
+fn read_decimal<R: Read>(reader: &mut R) -> io::Result<Decimal> {
+    Ok(Decimal(i64::from_le_bytes(read_array(reader)?)))
+}
+
+fn write_exact<W: Write>(writer: &mut W, keys: &HashSet<TxKey>) -> io::Result<()> {
+    let mut by_client: BTreeMap<Option<ClientId>, Vec<TransactionId>> = BTreeMap::new();
+    for &(client, tx) in keys {
+        by_client.entry(client).or_default().push(tx);
+    }
+    writer.write_all(&[0])?;
+    write_varint(writer, by_client.len() as u64)?;
+    for (client, mut txs) in by_client {
+        match client {
+            Some(client) => {
+                writer.write_all(&[1])?;
+                writer.write_all(&client.to_le_bytes())?;
+            }
+            None => writer.write_all(&[0])?,
+        }
+        txs.sort_unstable();
+        write_varint(writer, txs.len() as u64)?;
+        let mut previous = 0;
+        for tx in txs {
+            write_varint(writer, tx - previous)?;
+            previous = tx;
+        }
+    }
+    Ok(())
+}
+
+fn read_exact<R: Read>(reader: &mut R) -> io::Result<HashSet<TxKey>> {
+    let mut keys = HashSet::new();
+    for _ in 0..read_varint(reader)? {
+        let client = match read_array::<1>(reader)?[0] {
+            0 => None,
+            1 => Some(ClientId::from_le_bytes(read_array(reader)?)),
+            _ => return Err(invalid("bad client tag")),
+        };
+        let mut tx: TransactionId = 0;
+        for _ in 0..read_varint(reader)? {
+            tx = tx
+                .checked_add(read_varint(reader)?)
+                .ok_or_else(|| invalid("tx ID out of range"))?;
+            keys.insert((client, tx));
+        }
+    }
+    Ok(keys)
+}
+
+fn write_bloom<W: Write>(writer: &mut W, filter: &BloomFilter) -> io::Result<()> {
+    writer.write_all(&[1])?;
+    write_varint(writer, filter.hashes as u64)?;
+    write_varint(writer, filter.words.len() as u64)?;
+    for word in &filter.words {
+        writer.write_all(&word.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_bloom<R: Read>(reader: &mut R) -> io::Result<BloomFilter> {
+    let hashes = read_varint(reader)?;
+    let len = read_varint(reader)?;
+    if !(1..=32).contains(&hashes) || len == 0 {
+        return Err(invalid("bad bloom filter parameters"));
+    }
+    // Read word by word rather than trusting `len` for one allocation
+    let mut words = Vec::new();
+    for _ in 0..len {
+        words.push(u64::from_le_bytes(read_array(reader)?));
+    }
+    Ok(BloomFilter {
+        words,
+        hashes: hashes as u32,
+    })
+}
+
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return writer.write_all(&[byte]);
+        }
+        writer.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let [byte] = read_array(reader)?;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid("varint too long"))
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
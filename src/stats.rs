@@ -0,0 +1,100 @@
+//! Counters describing what the engine did with the transactions it received,
+//! and other state it keeps observable while serving.
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::{ClientId, DecimalWide};
+
+/// Running counts of notable transaction outcomes, and sums of the funds moved
+/// in and out of accounts (see [`verify_conservation`](crate::verify_conservation)).
+///
+/// The sums are kept in 128 bits, since they can exceed what any one account holds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcessStats {
+    /// Withdrawals (and order holds) rejected because they exceeded the available balance.
+    pub insufficient_funds: u64,
+    /// Deposits or withdrawals rejected because they had no amount.
+    pub missing_amount: u64,
+    /// Combined `total` of the accounts the engine was seeded with.
+    pub opening_total: DecimalWide,
+    /// Sum of accepted deposits.
+    pub deposited: DecimalWide,
+    /// Funds paid out by accepted withdrawals, `withdraw_all`s, and closes.
+    pub withdrawn: DecimalWide,
+    /// Funds reversed by chargebacks: the full amount of each charged-back claim.
+    pub charged_back: DecimalWide,
+    /// Chargeback shortfalls written off under
+    /// [`NegativeTotalPolicy::ClampZero`](crate::NegativeTotalPolicy::ClampZero),
+    /// which stay in `total` despite being counted in `charged_back`.
+    pub written_off: DecimalWide,
+    /// Time spent reading and parsing CSV input, including any time the reader
+    /// waited on a full channel. Only recorded by the CSV entry points.
+    pub read_duration: Duration,
+    /// Time the engine spent applying transactions, excluding time waiting for them.
+    pub process_duration: Duration,
+}
+
+/// Shared view of an engine's [`ProcessStats`]; clones observe the same counters.
+///
+/// Obtain one from [`PaymentsEngine::stats`](crate::PaymentsEngine::stats) before
+/// serving, and read it back once processing completes.
+#[derive(Debug, Clone, Default)]
+pub struct StatsHandle {
+    stats: Arc<Mutex<ProcessStats>>,
+}
+
+impl StatsHandle {
+    /// Copy of the counters as of now.
+    pub fn snapshot(&self) -> ProcessStats {
+        *self.stats.lock().unwrap()
+    }
+
+    pub(crate) fn update(&self, f: impl FnOnce(&mut ProcessStats)) {
+        f(&mut self.stats.lock().unwrap());
+    }
+}
+
+/// Shared view of which clients' accounts are currently locked; clones observe
+/// the same set.
+///
+/// Obtain one from [`PaymentsEngine::locked_set`](crate::PaymentsEngine::locked_set)
+/// before serving. The engine keeps it up to date as chargebacks lock accounts
+/// and unlocks reinstate them, so reading it never scans the accounts.
+#[derive(Debug, Clone, Default)]
+pub struct LockedSet {
+    clients: Arc<Mutex<BTreeSet<ClientId>>>,
+}
+
+impl LockedSet {
+    /// Locked client IDs as of now, in ascending order.
+    pub fn clients(&self) -> Vec<ClientId> {
+        self.clients.lock().unwrap().iter().copied().collect()
+    }
+
+    pub fn contains(&self, client: ClientId) -> bool {
+        self.clients.lock().unwrap().contains(&client)
+    }
+
+    pub fn len(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn set(&self, client: ClientId, locked: bool) {
+        let mut clients = self.clients.lock().unwrap();
+        if locked {
+            clients.insert(client);
+        } else {
+            clients.remove(&client);
+        }
+    }
+
+    pub(crate) fn reset(&self, clients: impl IntoIterator<Item = ClientId>) {
+        *self.clients.lock().unwrap() = clients.into_iter().collect();
+    }
+}
@@ -0,0 +1,78 @@
+//! Pluggable storage for the engine's client accounts.
+//!
+//! The engine keeps accounts in a `BTreeMap` by default. Embedders with very
+//! large client counts can implement [`AccountStore`] over their own backend
+//! (e.g., a disk-backed cache) and serve with
+//! [`PaymentsEngine::serve_with_store`](crate::PaymentsEngine::serve_with_store).
+
+use std::collections::BTreeMap;
+
+use crate::ClientId;
+use crate::client_account::ClientAccount;
+
+/// Storage for client accounts, keyed by client ID.
+pub trait AccountStore {
+    fn get(&self, client: ClientId) -> Option<&ClientAccount>;
+
+    fn get_mut(&mut self, client: ClientId) -> Option<&mut ClientAccount>;
+
+    /// Store `account` under its `client_id`, replacing any existing account.
+    fn insert(&mut self, account: ClientAccount);
+
+    /// Number of accounts stored.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every stored account, in any order.
+    fn iter(&self) -> Box<dyn Iterator<Item = &ClientAccount> + '_>;
+
+    /// The account for `client`, first inserting the one built by `create` if
+    /// there is none.
+    ///
+    /// The default looks the client up twice; override it if the backend has
+    /// a cheaper entry API.
+    fn get_or_insert_with(
+        &mut self,
+        client: ClientId,
+        create: impl FnOnce() -> ClientAccount,
+    ) -> &mut ClientAccount {
+        if self.get(client).is_none() {
+            self.insert(create());
+        }
+        self.get_mut(client)
+            .expect("account store lost an account just inserted")
+    }
+}
+
+impl AccountStore for BTreeMap<ClientId, ClientAccount> {
+    fn get(&self, client: ClientId) -> Option<&ClientAccount> {
+        BTreeMap::get(self, &client)
+    }
+
+    fn get_mut(&mut self, client: ClientId) -> Option<&mut ClientAccount> {
+        BTreeMap::get_mut(self, &client)
+    }
+
+    fn insert(&mut self, account: ClientAccount) {
+        BTreeMap::insert(self, account.client_id, account);
+    }
+
+    fn len(&self) -> usize {
+        BTreeMap::len(self)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = &ClientAccount> + '_> {
+        Box::new(self.values())
+    }
+
+    fn get_or_insert_with(
+        &mut self,
+        client: ClientId,
+        create: impl FnOnce() -> ClientAccount,
+    ) -> &mut ClientAccount {
+        self.entry(client).or_insert_with(create)
+    }
+}
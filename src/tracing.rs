@@ -1,12 +1,91 @@
 //! Super simple tracing macros which emulate the `tracing` crate.
 //!
-//! Logs are printed to stderr with level prefixes.
+//! Logs are printed to stderr with level prefixes by default. Embedders can
+//! route them elsewhere (a buffer, a file, a channel) by installing a
+//! [`LogSink`] with [`set_logger`].
+
+use std::fmt;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Severity of a log message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Info,
+    Error,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Info => "INFO",
+            Self::Error => "ERROR",
+        })
+    }
+}
+
+/// Destination for the crate's log messages, in place of stderr.
+///
+/// Called from whichever thread logged, including the engine's task and the
+/// blocking CSV reader.
+pub trait LogSink: Send + Sync {
+    fn log(&self, level: LogLevel, message: &str);
+}
+
+/// The installed sink; `None` logs to stderr.
+static LOGGER: RwLock<Option<Box<dyn LogSink>>> = RwLock::new(None);
+
+/// Route all log messages to `sink` from now on, replacing any earlier sink.
+///
+/// The logger is process-wide, shared by every engine.
+pub fn set_logger(sink: impl LogSink + 'static) {
+    *LOGGER.write().unwrap() = Some(Box::new(sink));
+}
+
+/// Go back to logging to stderr.
+pub fn reset_logger() {
+    *LOGGER.write().unwrap() = None;
+}
+
+/// Implementation detail of the [`info!`] and [`error!`] macros.
+#[doc(hidden)]
+pub fn log(level: LogLevel, args: fmt::Arguments<'_>) {
+    match LOGGER.read().unwrap().as_deref() {
+        Some(sink) => sink.log(level, &args.to_string()),
+        None => eprintln!("[{}] {}", level, args),
+    }
+}
+
+/// In-memory [`LogSink`] whose clones share the same messages.
+#[derive(Debug, Clone, Default)]
+pub struct LogCapture {
+    messages: Arc<Mutex<Vec<(LogLevel, String)>>>,
+}
+
+impl LogCapture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot of all messages logged so far, in order.
+    pub fn messages(&self) -> Vec<(LogLevel, String)> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+impl LogSink for LogCapture {
+    fn log(&self, level: LogLevel, message: &str) {
+        self.messages
+            .lock()
+            .unwrap()
+            .push((level, message.to_string()));
+    }
+}
 
 /// Logs an info message.
 #[macro_export]
 macro_rules! info {
     ($($arg:tt)*) => {
-        eprintln!("[INFO] {}", format!($($arg)*))
+        $crate::__log($crate::LogLevel::Info, format_args!($($arg)*))
     };
 }
 
@@ -14,12 +93,15 @@ macro_rules! info {
 #[macro_export]
 macro_rules! error {
     ($($arg:tt)*) => {
-        eprintln!("[ERROR] {}", format!($($arg)*))
+        $crate::__log($crate::LogLevel::Error, format_args!($($arg)*))
     };
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::{ClientAccount, Transaction};
+
     #[test]
     fn test_info_macro() {
         info!("abc{}", "def"); // prints [INFO] abcdef
@@ -36,4 +118,25 @@ mod tests {
         let name = "test";
         info!("value: {}, name: {}", x, name); // prints [INFO] value: 42, name: test
     }
+
+    #[test]
+    fn test_custom_logger_captures_rejection() {
+        let capture = LogCapture::new();
+        set_logger(capture.clone());
+        let mut account = ClientAccount::new(1);
+        let _ = account.adjudicate_claim(Transaction::dispute(1, 987_654_321));
+        reset_logger();
+
+        // Other tests may log concurrently while the capture is installed
+        let rejection = capture
+            .messages()
+            .into_iter()
+            .find(|(_, message)| message.contains("987654321"));
+        let (level, message) = rejection.expect("rejection was not logged to the sink");
+        assert_eq!(level, LogLevel::Error);
+        assert!(
+            message.starts_with("Received dispute-related request for unknown"),
+            "{message}"
+        );
+    }
 }
@@ -3,12 +3,14 @@
 //! Defines the core [`Transaction`] struct that represents a single operation
 //! (deposit, withdrawal, dispute, resolve, or chargeback) read from CSV input.
 
-use serde::Deserialize;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
 
 use crate::{ClientId, TransactionId, decimal::Decimal};
 
 /// Transaction types.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TransactionType {
     Deposit,
@@ -16,19 +18,301 @@ pub enum TransactionType {
     Dispute,
     Resolve,
     Chargeback,
+    /// Zero out and permanently close an account. See [`ClientAccount::close`](crate::ClientAccount::close).
+    Close,
+    /// Lift a chargeback lock once the underlying issue is settled. See
+    /// [`ClientAccount::unlock`](crate::ClientAccount::unlock).
+    Unlock,
+    /// Reserve funds for a pending order, moving them from available to held
+    /// outside the dispute state machine.
+    Hold,
+    /// Return the funds reserved by the [`Hold`](TransactionType::Hold) with the same tx ID.
+    Release,
+    /// Withdraw whatever is currently available (held funds stay put). Any
+    /// `amount` on the row is ignored; the ledger records the amount actually
+    /// withdrawn as an ordinary, non-disputable withdrawal.
+    #[serde(rename = "withdraw_all")]
+    WithdrawAll,
 }
 
 /// Transaction record.
-#[derive(Debug, Deserialize, Clone, Copy)]
+///
+/// # Tx ID semantics
+///
+/// Only deposits, withdrawals, `withdraw_all`s, and order holds introduce a
+/// tx ID, which is counted for duplicate detection. Dispute, resolve, and
+/// chargeback rows carry no ID of their own: their `tx` *refers to* the deposit
+/// being disputed, so they are neither recorded in the ledger nor counted for
+/// duplicate detection. Consequently a dispute-related row can only ever target
+/// a deposit; a tx ID that has only appeared on dispute rows is unknown.
+/// Likewise a release refers to its hold, and the `tx` of a close or unlock
+/// row is not used at all.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
 pub struct Transaction {
     #[serde(rename = "type")]
     pub transaction_type: TransactionType,
+    /// Left empty for dispute-related rows. An empty or blank field
+    /// deserializes to `None`, which deposits and withdrawals then reject as
+    /// [`RejectionReason::MissingAmount`](crate::RejectionReason::MissingAmount).
+    #[serde(
+        default,
+        deserialize_with = "crate::decimal::deserialize_optional_amount"
+    )]
     pub amount: Option<Decimal>,
     pub tx: TransactionId,
     pub client: ClientId,
+    /// When the transaction happened, in caller-defined units (e.g., Unix
+    /// seconds). Optional in CSV input; only used by
+    /// [`PaymentsEngine::with_reorder_window`](crate::PaymentsEngine::with_reorder_window).
+    #[serde(default)]
+    pub timestamp: Option<i64>,
+    /// Reason code given on a dispute row (e.g., `fraud`), from an optional
+    /// `reason` column. Kept on the deposit's
+    /// [`DisputeRecord`](crate::DisputeRecord); ignored on other rows.
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Where the transaction came from (e.g., the input file name set by
+    /// [`run_many`](crate::run_many)), for tracing rejections back to their
+    /// origin. Never read from CSV; shared so tagging every row stays cheap.
+    #[serde(skip)]
+    pub source: Option<Arc<str>>,
+}
+
+/// [`Transaction`] row from a feed writing `amount` as an integer count of
+/// minor units. See [`AmountFormat::MinorUnits`](crate::AmountFormat::MinorUnits).
+#[derive(Deserialize)]
+pub(crate) struct MinorUnitsTransaction {
+    #[serde(rename = "type")]
+    transaction_type: TransactionType,
+    amount: Option<i64>,
+    tx: TransactionId,
+    client: ClientId,
+    #[serde(default)]
+    timestamp: Option<i64>,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+impl MinorUnitsTransaction {
+    pub(crate) fn into_transaction(self, decimals: u32) -> Transaction {
+        Transaction {
+            transaction_type: self.transaction_type,
+            amount: self
+                .amount
+                .map(|units| Decimal::from_minor_units(units, decimals)),
+            tx: self.tx,
+            client: self.client,
+            timestamp: self.timestamp,
+            reason: self.reason,
+            source: None,
+        }
+    }
 }
 
 impl Transaction {
+    /// Create a deposit of `amount` into `client`'s account.
+    ///
+    /// ```
+    /// use crypto_events::{Decimal, Transaction, TransactionType};
+    ///
+    /// let tx = Transaction::deposit(1, 7, Decimal::from_f64(1.5));
+    /// assert_eq!(tx.transaction_type, TransactionType::Deposit);
+    /// assert_eq!(tx.amount, Some(Decimal::new(15000)));
+    /// ```
+    pub fn deposit(client: ClientId, tx: TransactionId, amount: Decimal) -> Self {
+        Self::with_amount(TransactionType::Deposit, client, tx, amount)
+    }
+
+    /// Create a withdrawal of `amount` from `client`'s account.
+    ///
+    /// ```
+    /// use crypto_events::{Decimal, Transaction, TransactionType};
+    ///
+    /// let tx = Transaction::withdrawal(1, 8, Decimal::from_f64(0.5));
+    /// assert_eq!(tx.transaction_type, TransactionType::Withdrawal);
+    /// ```
+    pub fn withdrawal(client: ClientId, tx: TransactionId, amount: Decimal) -> Self {
+        Self::with_amount(TransactionType::Withdrawal, client, tx, amount)
+    }
+
+    /// Create a dispute of transaction `tx` on `client`'s account.
+    ///
+    /// ```
+    /// use crypto_events::{Transaction, TransactionType};
+    ///
+    /// let tx = Transaction::dispute(1, 7);
+    /// assert_eq!(tx.transaction_type, TransactionType::Dispute);
+    /// assert_eq!(tx.amount, None);
+    /// ```
+    pub fn dispute(client: ClientId, tx: TransactionId) -> Self {
+        Self::without_amount(TransactionType::Dispute, client, tx)
+    }
+
+    /// Create a resolution of the dispute on transaction `tx`.
+    ///
+    /// ```
+    /// use crypto_events::{Transaction, TransactionType};
+    ///
+    /// assert_eq!(Transaction::resolve(1, 7).transaction_type, TransactionType::Resolve);
+    /// ```
+    pub fn resolve(client: ClientId, tx: TransactionId) -> Self {
+        Self::without_amount(TransactionType::Resolve, client, tx)
+    }
+
+    /// Create a chargeback of the dispute on transaction `tx`.
+    ///
+    /// ```
+    /// use crypto_events::{Transaction, TransactionType};
+    ///
+    /// assert_eq!(Transaction::chargeback(1, 7).transaction_type, TransactionType::Chargeback);
+    /// ```
+    pub fn chargeback(client: ClientId, tx: TransactionId) -> Self {
+        Self::without_amount(TransactionType::Chargeback, client, tx)
+    }
+
+    /// Create a request to close `client`'s account.
+    ///
+    /// ```
+    /// use crypto_events::{Transaction, TransactionType};
+    ///
+    /// assert_eq!(Transaction::close(1, 9).transaction_type, TransactionType::Close);
+    /// ```
+    pub fn close(client: ClientId, tx: TransactionId) -> Self {
+        Self::without_amount(TransactionType::Close, client, tx)
+    }
+
+    /// Create an administrative request to unlock `client`'s account.
+    ///
+    /// ```
+    /// use crypto_events::{Transaction, TransactionType};
+    ///
+    /// assert_eq!(Transaction::unlock(1, 9).transaction_type, TransactionType::Unlock);
+    /// ```
+    pub fn unlock(client: ClientId, tx: TransactionId) -> Self {
+        Self::without_amount(TransactionType::Unlock, client, tx)
+    }
+
+    /// Create a withdrawal of all of `client`'s available funds.
+    ///
+    /// ```
+    /// use crypto_events::{Transaction, TransactionType};
+    ///
+    /// let tx = Transaction::withdraw_all(1, 8);
+    /// assert_eq!(tx.transaction_type, TransactionType::WithdrawAll);
+    /// assert_eq!(tx.amount, None);
+    /// ```
+    pub fn withdraw_all(client: ClientId, tx: TransactionId) -> Self {
+        Self::without_amount(TransactionType::WithdrawAll, client, tx)
+    }
+
+    /// Create an order hold reserving `amount` of `client`'s available funds.
+    ///
+    /// ```
+    /// use crypto_events::{Decimal, Transaction, TransactionType};
+    ///
+    /// let tx = Transaction::hold(1, 7, Decimal::from_f64(2.0));
+    /// assert_eq!(tx.transaction_type, TransactionType::Hold);
+    /// assert_eq!(tx.amount, Some(Decimal::from_f64(2.0)));
+    /// ```
+    pub fn hold(client: ClientId, tx: TransactionId, amount: Decimal) -> Self {
+        Self::with_amount(TransactionType::Hold, client, tx, amount)
+    }
+
+    /// Create a release of the order hold `tx` on `client`'s account.
+    ///
+    /// ```
+    /// use crypto_events::{Transaction, TransactionType};
+    ///
+    /// assert_eq!(Transaction::release(1, 7).transaction_type, TransactionType::Release);
+    /// ```
+    pub fn release(client: ClientId, tx: TransactionId) -> Self {
+        Self::without_amount(TransactionType::Release, client, tx)
+    }
+
+    /// Tag this transaction with the `source` it was read from.
+    ///
+    /// ```
+    /// use crypto_events::Transaction;
+    ///
+    /// let tx = Transaction::dispute(1, 7).with_source("march.csv".into());
+    /// assert_eq!(tx.source.as_deref(), Some("march.csv"));
+    /// ```
+    pub fn with_source(mut self, source: Arc<str>) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Give this dispute a `reason` code.
+    ///
+    /// ```
+    /// use crypto_events::Transaction;
+    ///
+    /// let tx = Transaction::dispute(1, 7).with_reason("fraud");
+    /// assert_eq!(tx.reason.as_deref(), Some("fraud"));
+    /// ```
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Attach a timestamp to this transaction.
+    ///
+    /// ```
+    /// use crypto_events::Transaction;
+    ///
+    /// assert_eq!(Transaction::dispute(1, 7).at(1_700_000_000).timestamp, Some(1_700_000_000));
+    /// ```
+    pub fn at(mut self, timestamp: i64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    fn with_amount(
+        transaction_type: TransactionType,
+        client: ClientId,
+        tx: TransactionId,
+        amount: Decimal,
+    ) -> Self {
+        Self {
+            transaction_type,
+            amount: Some(amount),
+            tx,
+            client,
+            timestamp: None,
+            reason: None,
+            source: None,
+        }
+    }
+
+    fn without_amount(
+        transaction_type: TransactionType,
+        client: ClientId,
+        tx: TransactionId,
+    ) -> Self {
+        Self {
+            transaction_type,
+            amount: None,
+            tx,
+            client,
+            timestamp: None,
+            reason: None,
+            source: None,
+        }
+    }
+
+    /// Returns true if this transaction claims its tx ID for duplicate
+    /// detection: deposits, (full) withdrawals, and holds. Dispute-related rows and
+    /// releases refer to an earlier ID, and close/unlock rows ignore theirs.
+    pub fn claims_tx_id(&self) -> bool {
+        matches!(
+            self.transaction_type,
+            TransactionType::Deposit
+                | TransactionType::Withdrawal
+                | TransactionType::WithdrawAll
+                | TransactionType::Hold
+        )
+    }
+
     /// Returns true if the transaction type is dispute, resolve, or chargeback.
     pub fn is_dispute_related(&self) -> bool {
         matches!(
@@ -49,6 +333,9 @@ mod tests {
             amount: None,
             tx: 1,
             client: 1,
+            timestamp: None,
+            reason: None,
+            source: None,
         };
 
         assert!(!make_tx(TransactionType::Deposit).is_dispute_related());
@@ -56,5 +343,65 @@ mod tests {
         assert!(make_tx(TransactionType::Dispute).is_dispute_related());
         assert!(make_tx(TransactionType::Resolve).is_dispute_related());
         assert!(make_tx(TransactionType::Chargeback).is_dispute_related());
+        assert!(!make_tx(TransactionType::Close).is_dispute_related());
+        assert!(!make_tx(TransactionType::Unlock).is_dispute_related());
+        assert!(!make_tx(TransactionType::Hold).is_dispute_related());
+        assert!(!make_tx(TransactionType::Release).is_dispute_related());
+        assert!(!make_tx(TransactionType::WithdrawAll).is_dispute_related());
+    }
+
+    #[test]
+    fn test_deserialize_empty_amount_as_none() {
+        // No trimming, so the blank field reaches the deserializer as-is
+        let input = "type,client,tx,amount\n\
+                     deposit,1,1,\n\
+                     deposit,1,2,   \n\
+                     deposit,1,3,\"\"\n\
+                     deposit,1,4,2.5\n";
+        let amounts: Vec<Option<Decimal>> = csv::Reader::from_reader(input.as_bytes())
+            .deserialize::<Transaction>()
+            .map(|tx| tx.unwrap().amount)
+            .collect();
+        assert_eq!(
+            amounts,
+            vec![None, None, None, Some(Decimal::from_f64(2.5))]
+        );
+
+        // A malformed amount is still a parse error
+        let input = "type,client,tx,amount\ndeposit,1,1,abc\n";
+        let mut reader = csv::Reader::from_reader(input.as_bytes());
+        assert!(reader.deserialize::<Transaction>().next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_deserialize_optional_timestamp() {
+        let input = "type,client,tx,amount,timestamp\n\
+                     deposit,1,1,1.5,1700000000\n\
+                     dispute,1,1,,\n";
+        let transactions: Vec<Transaction> = csv::Reader::from_reader(input.as_bytes())
+            .deserialize()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(transactions[0].timestamp, Some(1700000000));
+        assert_eq!(transactions[1].timestamp, None);
+
+        // The column itself is optional
+        let input = "type,client,tx,amount\ndeposit,1,1,1.5\n";
+        let tx: Transaction = csv::Reader::from_reader(input.as_bytes())
+            .deserialize()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.timestamp, None);
+        assert_eq!(tx.reason, None);
+
+        let input = "type,client,tx,amount,reason\n\
+                     dispute,1,1,,fraud\n\
+                     dispute,1,2,,\n";
+        let reasons: Vec<Option<String>> = csv::Reader::from_reader(input.as_bytes())
+            .deserialize::<Transaction>()
+            .map(|tx| tx.unwrap().reason)
+            .collect();
+        assert_eq!(reasons, vec![Some("fraud".to_string()), None]);
     }
 }
@@ -1,6 +1,10 @@
 //! Integration tests for the payments engine CSV processing.
 
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+use crypto_events::{Delimiter, EngineError, InputOptions};
 
 /// Get path to test input file.
 fn test_input(filename: &str) -> PathBuf {
@@ -19,6 +23,15 @@ async fn run_and_capture(filename: &str) -> String {
     String::from_utf8(output).expect("output should be valid UTF-8")
 }
 
+/// Run the engine on a test file with the given input options.
+async fn run_with_options_and_capture(filename: &str, options: InputOptions) -> String {
+    let mut output = Vec::new();
+    crypto_events::run_with_options(test_input(filename), &mut output, options)
+        .await
+        .expect("run should succeed");
+    String::from_utf8(output).expect("output should be valid UTF-8")
+}
+
 // ========== Integration Tests ==========
 
 #[tokio::test]
@@ -106,6 +119,50 @@ async fn test_duplicate_tx_id_ignored() {
     );
 }
 
+#[tokio::test]
+async fn test_empty_amount_rejected_as_missing() {
+    let output = run_and_capture("empty_amount.csv").await;
+
+    // Deposits with an empty or blank amount are dropped; the rest still apply
+    assert_eq!(
+        output,
+        "client,available,held,total,locked\n\
+         1,2.0,0.0,2.0,false\n"
+    );
+}
+
+#[tokio::test]
+async fn test_rejections_written_to_errors_file() {
+    let (mut output, mut errors) = (Vec::new(), Vec::new());
+    let never_stopped = Arc::new(AtomicBool::new(false));
+    crypto_events::run_with_rejections(
+        test_input("rejections.csv"),
+        &mut output,
+        &mut errors,
+        never_stopped,
+    )
+    .await
+    .expect("run should succeed");
+
+    // Client 2's rejected deposit still creates its (empty) account
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "client,available,held,total,locked\n\
+         1,0.0,0.0,0.0,true\n\
+         2,0.0,0.0,0.0,false\n"
+    );
+    assert_eq!(
+        String::from_utf8(errors).unwrap(),
+        "type,client,tx,amount,reason\n\
+         withdrawal,1,2,15.0,insufficient available funds\n\
+         deposit,2,1,5.0,duplicate transaction ID\n\
+         dispute,1,99,,unknown transaction\n\
+         resolve,1,1,,transaction is not under dispute\n\
+         deposit,2,3,,missing amount\n\
+         deposit,1,4,1.0,account is locked\n"
+    );
+}
+
 #[tokio::test]
 async fn test_output_sorted_by_client_id() {
     // basic_transactions.csv creates client 1 first, then client 2
@@ -116,3 +173,208 @@ async fn test_output_sorted_by_client_id() {
     assert!(lines[1].starts_with("1,"));
     assert!(lines[2].starts_with("2,"));
 }
+
+#[tokio::test]
+async fn test_tx_id_beyond_u32() {
+    let output = run_and_capture("large_tx_id.csv").await;
+
+    // tx IDs up to u64::MAX parse, and the largest one can be disputed
+    assert_eq!(
+        output,
+        "client,available,held,total,locked\n\
+         1,5.0,10.0,15.0,false\n"
+    );
+}
+
+#[tokio::test]
+async fn test_progress_callback_invoked_every_n() {
+    use std::sync::{Arc, Mutex};
+
+    let counts = Arc::new(Mutex::new(Vec::new()));
+    let recorded = counts.clone();
+    let mut output = Vec::new();
+
+    // basic_transactions.csv has 4 rows
+    crypto_events::run_with_progress(
+        test_input("basic_transactions.csv"),
+        &mut output,
+        2,
+        Some(Box::new(move |count| recorded.lock().unwrap().push(count))),
+    )
+    .await
+    .expect("run should succeed");
+
+    assert_eq!(*counts.lock().unwrap(), vec![2, 4]);
+    // Output is unaffected by progress reporting
+    assert_eq!(
+        output,
+        run_and_capture("basic_transactions.csv").await.into_bytes()
+    );
+}
+
+#[tokio::test]
+async fn test_resume_from_output_round_trip() {
+    use crypto_events::{Decimal, PaymentsEngine, Transaction};
+
+    let first_output = run_and_capture("basic_transactions.csv").await;
+    let accounts = crypto_events::load_accounts_csv(first_output.as_bytes()).unwrap();
+
+    // Loading the output and writing it back is lossless
+    let mut reloaded = Vec::new();
+    crypto_events::write_accounts_csv(accounts.clone(), &mut reloaded).unwrap();
+    assert_eq!(String::from_utf8(reloaded).unwrap(), first_output);
+
+    let engine = PaymentsEngine::new().with_accounts(accounts);
+    let sender = engine.sender();
+    let handle = engine.serve().await;
+    for tx in [
+        Transaction::deposit(1, 10, Decimal::from_f64(1.5)),
+        Transaction::withdrawal(2, 11, Decimal::from_f64(5.0)),
+        Transaction::deposit(3, 12, Decimal::from_f64(7.0)),
+    ] {
+        sender.send(tx).await.unwrap();
+    }
+    drop(sender);
+    let accounts = handle.await.unwrap().unwrap();
+
+    let mut output = Vec::new();
+    crypto_events::write_accounts_csv(accounts, &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "client,available,held,total,locked\n\
+         1,10.0,0.0,10.0,false\n\
+         2,15.0,0.0,15.0,false\n\
+         3,7.0,0.0,7.0,false\n"
+    );
+}
+
+#[tokio::test]
+async fn test_bom_and_crlf_match_clean_input() {
+    // Same rows as basic_transactions.csv, with a UTF-8 BOM and CRLF endings
+    let output = run_and_capture("bom_crlf.csv").await;
+
+    assert_eq!(output, run_and_capture("basic_transactions.csv").await);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_fixtures_unchanged_on_multi_thread_runtime() {
+    // The reader runs on a blocking thread; output must not depend on the runtime flavor
+    assert_eq!(
+        run_and_capture("basic_transactions.csv").await,
+        "client,available,held,total,locked\n\
+         1,8.5,0.0,8.5,false\n\
+         2,20.0,0.0,20.0,false\n"
+    );
+    assert_eq!(
+        run_and_capture("dispute_chargeback.csv").await,
+        "client,available,held,total,locked\n\
+         1,50.0,0.0,50.0,true\n"
+    );
+}
+
+#[tokio::test]
+async fn test_input_larger_than_channel_buffer() {
+    // Enough rows that the blocking reader has to wait on the engine repeatedly
+    let path = std::env::temp_dir().join(format!(
+        "crypto_events_large_input_{}.csv",
+        std::process::id()
+    ));
+    let mut input = String::from("type,client,tx,amount\n");
+    for tx in 1..=1000 {
+        input.push_str(&format!("deposit,{},{},0.5\n", tx % 2 + 1, tx));
+    }
+    std::fs::write(&path, input).unwrap();
+
+    let mut output = Vec::new();
+    crypto_events::run(&path, &mut output)
+        .await
+        .expect("run should succeed");
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "client,available,held,total,locked\n\
+         1,250.0,0.0,250.0,false\n\
+         2,250.0,0.0,250.0,false\n"
+    );
+}
+
+#[tokio::test]
+async fn test_semicolon_delimited_input() {
+    // Same rows as basic_transactions.csv
+    let expected = run_and_capture("basic_transactions.csv").await;
+    for delimiter in [Delimiter::Byte(b';'), Delimiter::Auto] {
+        let options = InputOptions {
+            delimiter,
+            ..InputOptions::default()
+        };
+        let output = run_with_options_and_capture("semicolon_delimited.csv", options).await;
+        assert_eq!(output, expected, "{:?}", delimiter);
+    }
+}
+
+#[tokio::test]
+async fn test_compute_returns_accounts_without_writing() {
+    let accounts = crypto_events::compute(test_input("basic_transactions.csv"))
+        .await
+        .expect("compute should succeed");
+
+    // Writing them out gives exactly what `run` prints
+    let mut output = Vec::new();
+    crypto_events::write_accounts_csv(accounts, &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        run_and_capture("basic_transactions.csv").await
+    );
+
+    let error = crypto_events::compute(test_input("missing.csv"))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, EngineError::Io(_)), "{error:?}");
+}
+
+#[tokio::test]
+async fn test_headerless_input_with_positional_columns() {
+    // Same rows as basic_transactions.csv, as `amount,type,tx,client` without a header
+    let expected = run_and_capture("basic_transactions.csv").await;
+    let options = InputOptions {
+        columns: Some(["amount", "type", "tx", "client"].map(String::from).into()),
+        ..InputOptions::default()
+    };
+    let output = run_with_options_and_capture("legacy_headerless.csv", options).await;
+    assert_eq!(output, expected);
+}
+
+#[tokio::test]
+async fn test_tab_delimited_input() {
+    // Same rows as basic_transactions.csv
+    let expected = run_and_capture("basic_transactions.csv").await;
+    for delimiter in [Delimiter::Byte(b'\t'), Delimiter::Auto] {
+        let options = InputOptions {
+            delimiter,
+            ..InputOptions::default()
+        };
+        let output = run_with_options_and_capture("tab_delimited.csv", options).await;
+        assert_eq!(output, expected, "{:?}", delimiter);
+    }
+}
+
+#[tokio::test]
+async fn test_mislabeled_header_rejected() {
+    let mut output = Vec::new();
+    let error = crypto_events::run(test_input("mislabeled_header.csv"), &mut output)
+        .await
+        .expect_err("a mislabeled header should fail the run");
+
+    assert_eq!(
+        error.downcast_ref::<EngineError>(),
+        Some(&EngineError::BadHeader {
+            found: vec!["type".into(), "client".into(), "tx".into(), "amt".into()],
+            expected: vec!["type", "client", "tx", "amount"],
+        })
+    );
+    assert!(
+        output.is_empty(),
+        "nothing should be written on a bad header"
+    );
+}